@@ -0,0 +1,91 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::document::Document;
+use crate::levels::Place;
+use crate::room::RoomId;
+
+/// For each pair of rooms that touch, how many unwalled cell edges they share on all the levels, with each stair
+/// between them counting as one more. Pairs are ordered so that the smaller id comes first.
+pub fn shared_boundaries(document: &Document) -> BTreeMap<(RoomId, RoomId), usize> {
+    let levels = document.levels();
+    let shape = document.scale.shape;
+    let mut boundaries = BTreeMap::new();
+    let mut add = |room: RoomId, other: RoomId| {
+        if other != room {
+            *boundaries
+                .entry((room.min(other), room.max(other)))
+                .or_insert(0) += 1;
+        }
+    };
+
+    for level in levels.iter() {
+        let cells = &level.cells;
+        for (row, col, cell) in cells.iter() {
+            let room = if let Some(room) = cell {
+                *room
+            } else {
+                continue;
+            };
+
+            // Each pair of cells is counted once, from the one that comes first
+            for (other_row, other_col) in shape
+                .neighbours((row, col))
+                .into_iter()
+                .filter(|other| *other > (row, col))
+            {
+                let other = match cells.get(other_row, other_col).copied().flatten() {
+                    Some(other) => other,
+                    None => continue,
+                };
+
+                if !level.edges.is_walled((row, col), (other_row, other_col)) {
+                    add(room, other);
+                }
+            }
+        }
+    }
+
+    let room_at = |place: Place| {
+        levels
+            .get(place.level)
+            .and_then(|level| level.cells.get(place.row, place.col).copied().flatten())
+    };
+    for stair in document.stairs.iter() {
+        if let (Some(room), Some(other)) = (room_at(stair.from), room_at(stair.to)) {
+            add(room, other);
+        }
+    }
+
+    boundaries
+}
+
+/// For each painted room, the rooms that share at least one unwalled cell edge or stair with it
+pub fn neighbours(document: &Document) -> BTreeMap<RoomId, BTreeSet<RoomId>> {
+    let mut neighbours: BTreeMap<RoomId, BTreeSet<RoomId>> = BTreeMap::new();
+
+    for room in cell_counts(document).keys() {
+        neighbours.entry(*room).or_default();
+    }
+
+    for (a, b) in shared_boundaries(document).keys() {
+        neighbours.entry(*a).or_default().insert(*b);
+        neighbours.entry(*b).or_default().insert(*a);
+    }
+
+    neighbours
+}
+
+/// The number of cells painted with each room, on all the levels
+pub fn cell_counts(document: &Document) -> BTreeMap<RoomId, usize> {
+    let mut counts = BTreeMap::new();
+
+    for level in document.levels().iter() {
+        for (_, _, cell) in level.cells.iter() {
+            if let Some(room) = cell {
+                *counts.entry(*room).or_insert(0) += 1;
+            }
+        }
+    }
+
+    counts
+}
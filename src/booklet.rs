@@ -0,0 +1,103 @@
+use std::fmt::Write;
+
+use crate::adjacency;
+use crate::document::Document;
+use crate::export::{self, escape};
+use crate::markdown;
+use crate::tile;
+
+/// Render a printable room key: an overview map of each level followed by one section per room
+pub fn html(
+    document: &Document,
+    tile_materials: &tile::Materials,
+    svg_options: &export::SvgOptions,
+) -> String {
+    let neighbours = adjacency::neighbours(document);
+    let cell_counts = adjacency::cell_counts(document);
+
+    let mut output = String::new();
+
+    output.push_str(concat!(
+        "<!DOCTYPE html>\n",
+        "<html lang=\"en\">\n",
+        "<head>\n",
+        "<meta charset=\"UTF-8\">\n",
+        "<title>Room Key</title>\n",
+        "<style>\n",
+        "body { font-family: serif; max-width: 50em; margin: auto; }\n",
+        "section { break-inside: avoid; }\n",
+        ".overview svg { width: 100%; height: auto; }\n",
+        ".read-aloud { border-left: 3px solid grey; padding-left: 1em; font-style: italic; }\n",
+        ".gm-notes { background: #eee; padding: 0.5em; }\n",
+        "</style>\n",
+        "</head>\n",
+        "<body>\n",
+        "<h1>Room Key</h1>\n",
+    ));
+
+    let levels = document.levels();
+    for (index, level) in levels.iter().enumerate() {
+        // Writing to a String never fails
+        if levels.len() > 1 {
+            let _ = writeln!(output, "<h2>{}</h2>", escape(&level.name));
+        }
+        let level_document = document
+            .switch_level(index)
+            .unwrap_or_else(|| document.clone());
+        output.push_str("<div class=\"overview\">\n");
+        output.push_str(&export::svg(&level_document, tile_materials, svg_options));
+        output.push_str("</div>\n");
+    }
+
+    for (id, room) in document.rooms.iter() {
+        let squares = cell_counts.get(&id).copied().unwrap_or(0);
+
+        let _ = writeln!(output, "<section>\n<h2>{}</h2>", escape(&room.heading()));
+        let _ = writeln!(
+            output,
+            "<p><strong>Area:</strong> {} squares ({})</p>",
+            squares,
+            document.scale.format_area(squares)
+        );
+
+        let exits = neighbours
+            .get(&id)
+            .into_iter()
+            .flatten()
+            .filter_map(|other| document.rooms.get(*other))
+            .map(|other| escape(&other.heading()))
+            .collect::<Vec<_>>();
+
+        let _ = writeln!(
+            output,
+            "<p><strong>Exits:</strong> {}</p>",
+            if exits.is_empty() {
+                String::from("none")
+            } else {
+                exits.join(", ")
+            }
+        );
+
+        if !room.description.is_empty() {
+            let _ = writeln!(
+                output,
+                "<div class=\"read-aloud\">{}</div>",
+                markdown::to_html(&room.description)
+            );
+        }
+
+        if !room.gm_notes.is_empty() {
+            let _ = writeln!(
+                output,
+                "<div class=\"gm-notes\">{}</div>",
+                markdown::to_html(&room.gm_notes)
+            );
+        }
+
+        output.push_str("</section>\n");
+    }
+
+    output.push_str("</body>\n</html>\n");
+
+    output
+}
@@ -0,0 +1,355 @@
+//! Dungeon generation by binary space partitioning: the map is split into one area per room, a room is placed in
+//! each area, and corridors join the rooms on either side of every split.
+
+use crate::rng::Rng;
+use crate::room::{Room, RoomId, Rooms};
+use crate::tile;
+use crate::Cells;
+
+/// The name of the room the corridors are painted with
+pub const CORRIDORS: &str = "Corridors";
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BspSettings {
+    pub seed: u64,
+    pub rows: usize,
+    pub cols: usize,
+    /// How many rooms to aim for. Fewer are generated if they won't fit.
+    pub rooms: usize,
+    pub min_room_size: usize,
+    pub max_room_size: usize,
+    pub corridor_width: usize,
+}
+
+impl Default for BspSettings {
+    fn default() -> Self {
+        Self {
+            seed: 1,
+            rows: 32,
+            cols: 32,
+            rooms: 8,
+            min_room_size: 4,
+            max_room_size: 8,
+            corridor_width: 1,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Rect {
+    top: usize,
+    left: usize,
+    height: usize,
+    width: usize,
+}
+
+impl Rect {
+    fn centre(&self) -> (usize, usize) {
+        (self.top + self.height / 2, self.left + self.width / 2)
+    }
+
+    /// Split into two at `at` rows down, or columns across if not `horizontal`
+    fn split(&self, horizontal: bool, at: usize) -> (Self, Self) {
+        if horizontal {
+            (
+                Self {
+                    height: at,
+                    ..*self
+                },
+                Self {
+                    top: self.top + at,
+                    height: self.height - at,
+                    ..*self
+                },
+            )
+        } else {
+            (
+                Self { width: at, ..*self },
+                Self {
+                    left: self.left + at,
+                    width: self.width - at,
+                    ..*self
+                },
+            )
+        }
+    }
+}
+
+enum Node {
+    Leaf(Rect),
+    Split(Box<Node>, Box<Node>),
+}
+
+struct Builder<'a> {
+    settings: &'a BspSettings,
+    materials: &'a [tile::Material],
+    rng: Rng,
+    cells: Vec<Option<RoomId>>,
+    corridors: Vec<bool>,
+    rooms: Rooms,
+}
+
+impl Builder<'_> {
+    /// Divide `rect` into areas for `count` rooms
+    fn partition(&mut self, rect: Rect, count: usize) -> Node {
+        // Leave a cell either side of the smallest room
+        let min_area = self.settings.min_room_size + 2;
+        let can_split_rows = rect.height >= 2 * min_area;
+        let can_split_cols = rect.width >= 2 * min_area;
+
+        let horizontal = match (count > 1, can_split_rows, can_split_cols) {
+            (false, _, _) | (true, false, false) => return Node::Leaf(rect),
+            (true, true, false) => true,
+            (true, false, true) => false,
+            (true, true, true) if rect.height == rect.width => self.rng.chance(0.5),
+            (true, true, true) => rect.height > rect.width,
+        };
+
+        // Share the rooms out in proportion to the space, give or take an eighth
+        let length = if horizontal { rect.height } else { rect.width };
+        let first_count = count / 2;
+        let jitter = length / 8;
+        let at = (length * first_count / count + self.rng.range(0, 2 * jitter))
+            .saturating_sub(jitter)
+            .max(min_area)
+            .min(length - min_area);
+
+        let (first, second) = rect.split(horizontal, at);
+        Node::Split(
+            Box::new(self.partition(first, first_count)),
+            Box::new(self.partition(second, count - first_count)),
+        )
+    }
+
+    fn material(&mut self) -> tile::Material {
+        self.rng
+            .choose(self.materials)
+            .cloned()
+            .expect("materials must not be empty")
+    }
+
+    /// Pick a size for a room in an area `space` cells long, leaving a cell either side
+    fn room_size(&mut self, space: usize) -> usize {
+        let max = self.settings.max_room_size.min(space - 2);
+        let min = self.settings.min_room_size.min(max);
+        self.rng.range(min, max)
+    }
+
+    fn place_room(&mut self, area: Rect) -> Option<(usize, usize)> {
+        if area.height < 3 || area.width < 3 {
+            return None;
+        }
+
+        let (height, width) = (self.room_size(area.height), self.room_size(area.width));
+        let room = Rect {
+            top: area.top + 1 + self.rng.below(area.height - 1 - height),
+            left: area.left + 1 + self.rng.below(area.width - 1 - width),
+            height,
+            width,
+        };
+
+        let label = (self.rooms.len() + 1).to_string();
+        let material = self.material();
+        let (rooms, id) = self.rooms.push(Room::new(material, label));
+        self.rooms = rooms;
+
+        for row in room.top..room.top + room.height {
+            for col in room.left..room.left + room.width {
+                self.cells[row * self.settings.cols + col] = Some(id);
+            }
+        }
+
+        Some(room.centre())
+    }
+
+    /// Mark a square of corridor `corridor_width` cells across
+    fn dig(&mut self, row: usize, col: usize) {
+        let width = self.settings.corridor_width.max(1);
+        for row in row..(row + width).min(self.settings.rows) {
+            for col in col..(col + width).min(self.settings.cols) {
+                self.corridors[row * self.settings.cols + col] = true;
+            }
+        }
+    }
+
+    /// Dig an L-shaped corridor between two cells
+    fn connect(&mut self, from: (usize, usize), to: (usize, usize)) {
+        let corner = if self.rng.chance(0.5) {
+            (from.0, to.1)
+        } else {
+            (to.0, from.1)
+        };
+
+        for &(a, b) in &[(from, corner), (corner, to)] {
+            for row in a.0.min(b.0)..=a.0.max(b.0) {
+                for col in a.1.min(b.1)..=a.1.max(b.1) {
+                    self.dig(row, col);
+                }
+            }
+        }
+    }
+
+    /// Place the rooms and join them up, returning a cell in one of the rooms
+    fn build(&mut self, node: &Node) -> Option<(usize, usize)> {
+        match node {
+            Node::Leaf(area) => self.place_room(*area),
+            Node::Split(first, second) => match (self.build(first), self.build(second)) {
+                (Some(a), Some(b)) => {
+                    self.connect(a, b);
+                    Some(if self.rng.chance(0.5) { a } else { b })
+                }
+                (a, b) => a.or(b),
+            },
+        }
+    }
+}
+
+/// Generate a dungeon. The same settings always give the same map. Returns `None` if there are no materials, or if
+/// the map is too small for any room.
+pub fn generate(settings: &BspSettings, materials: &tile::Materials) -> Option<(Cells, Rooms)> {
+    if materials.is_empty() || settings.rows == 0 || settings.cols == 0 {
+        return None;
+    }
+
+    let mut builder = Builder {
+        settings,
+        materials: materials.as_ref(),
+        rng: Rng::new(settings.seed),
+        cells: vec![None; settings.rows * settings.cols],
+        corridors: vec![false; settings.rows * settings.cols],
+        rooms: Rooms::new(),
+    };
+
+    let root = Rect {
+        top: 0,
+        left: 0,
+        height: settings.rows,
+        width: settings.cols,
+    };
+    let tree = builder.partition(root, settings.rooms.max(1));
+    builder.build(&tree);
+    if builder.rooms.is_empty() {
+        return None;
+    }
+
+    // Corridors get a room of their own, added last so that the rooms keep their numbers
+    if builder.corridors.contains(&true) {
+        let material = builder.material();
+        let mut corridor = Room::new(material, String::from("C"));
+        corridor.name = String::from(CORRIDORS);
+        let (rooms, id) = builder.rooms.push(corridor);
+        builder.rooms = rooms;
+
+        for (cell, dug) in builder.cells.iter_mut().zip(&builder.corridors) {
+            if *dug && cell.is_none() {
+                *cell = Some(id);
+            }
+        }
+    }
+
+    let cells = Cells::with_dimensions(settings.rows, settings.cols)
+        .map_indexed(|row, col, _| builder.cells[row * settings.cols + col]);
+
+    Some((cells, builder.rooms))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+
+    #[test]
+    fn same_seed_gives_same_map() {
+        let settings = BspSettings {
+            seed: 42,
+            ..BspSettings::default()
+        };
+        let (a, rooms_a) = generate(&settings, &materials()).unwrap();
+        let (b, rooms_b) = generate(&settings, &materials()).unwrap();
+
+        assert_eq!(contents(&a), contents(&b));
+        assert_eq!(room_names(&rooms_a), room_names(&rooms_b));
+    }
+
+    #[test]
+    fn maps_too_small_for_a_room_are_not_generated() {
+        // A room needs a cell of rock either side of it
+        let size = |size| BspSettings {
+            rows: size,
+            cols: size,
+            ..BspSettings::default()
+        };
+        assert!(generate(&size(1), &materials()).is_none());
+        assert!(generate(&size(2), &materials()).is_none());
+
+        let (cells, rooms) = generate(&size(3), &materials()).unwrap();
+        assert_eq!(rooms.len(), 1);
+        assert_eq!(cells.get(1, 1), Some(&rooms.first_id()));
+    }
+
+    #[test]
+    fn different_seeds_give_different_maps() {
+        let (a, _) = generate(&BspSettings::default(), &materials()).unwrap();
+        let (b, _) = generate(
+            &BspSettings {
+                seed: 2,
+                ..BspSettings::default()
+            },
+            &materials(),
+        )
+        .unwrap();
+
+        assert_ne!(contents(&a), contents(&b));
+    }
+
+    #[test]
+    fn rooms_match_settings() {
+        for seed in 0..20 {
+            let settings = BspSettings {
+                seed,
+                ..BspSettings::default()
+            };
+            let (cells, rooms) = generate(&settings, &materials()).unwrap();
+
+            // One room per area plus the corridors
+            assert_eq!(rooms.len(), settings.rooms + 1, "seed {}", seed);
+
+            for (id, room) in rooms.iter().filter(|(_, room)| room.label != "C") {
+                let room_cells: Vec<_> = cells
+                    .iter()
+                    .filter(|(_, _, cell)| **cell == Some(id))
+                    .map(|(row, col, _)| (row, col))
+                    .collect();
+                let height = room_cells.iter().map(|c| c.0).max().unwrap() + 1
+                    - room_cells.iter().map(|c| c.0).min().unwrap();
+                let width = room_cells.iter().map(|c| c.1).max().unwrap() + 1
+                    - room_cells.iter().map(|c| c.1).min().unwrap();
+
+                assert_eq!(
+                    room_cells.len(),
+                    height * width,
+                    "room {} is not a rectangle",
+                    room.label
+                );
+                for size in &[height, width] {
+                    assert!((settings.min_room_size..=settings.max_room_size).contains(size));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rooms_are_connected() {
+        for seed in 0..20 {
+            for corridor_width in 1..=2 {
+                let settings = BspSettings {
+                    seed,
+                    corridor_width,
+                    ..BspSettings::default()
+                };
+                let (cells, _) = generate(&settings, &materials()).unwrap();
+                assert!(is_connected(&cells), "seed {} is disconnected", seed);
+            }
+        }
+    }
+}
@@ -0,0 +1,600 @@
+//! A campaign: maps kept and saved together, with exits that lead from a cell on one map to a cell on another, such
+//! as a town gate to the overland map, and on to a dungeon entrance.
+
+use crate::document::Document;
+use crate::levels::Place;
+use crate::list::List;
+use crate::room::Room;
+use crate::stocking::StockingTables;
+use crate::tile;
+
+/// Identifies a map independently of its position in the campaign.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+pub struct MapId(usize);
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Map {
+    pub id: MapId,
+    pub name: String,
+    pub document: Document,
+}
+
+/// A cell on a level of a map
+#[derive(Copy, Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Location {
+    pub map: MapId,
+    pub place: Place,
+}
+
+/// A one-way link from a cell to somewhere else in the campaign
+#[derive(Copy, Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Exit {
+    pub from: Location,
+    pub to: Location,
+}
+
+/// The version of the save format written by `to_json`. Files from before the format was versioned load as version 0.
+const VERSION: u32 = 1;
+
+/// Saved as JSON, so fields added later need a `#[serde(default)]` for older files to load.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Campaign {
+    #[serde(default)]
+    version: u32,
+    next_id: usize,
+    /// The map being edited is only brought up to date when switching away from it, so use `store` before reading it
+    maps: List<Map>,
+    current: MapId,
+    #[serde(default)]
+    pub exits: List<Exit>,
+    /// The random tables used to stock the rooms of every map
+    #[serde(default)]
+    pub stocking_tables: StockingTables,
+}
+
+impl Campaign {
+    pub fn new(document: Document) -> Self {
+        let id = MapId(0);
+
+        Self {
+            version: VERSION,
+            next_id: 1,
+            maps: List::from(vec![Map {
+                id,
+                name: String::from("Map 1"),
+                document,
+            }]),
+            current: id,
+            exits: List::new(),
+            stocking_tables: StockingTables::default(),
+        }
+    }
+
+    pub fn current(&self) -> MapId {
+        self.current
+    }
+
+    pub fn maps(&self) -> impl Iterator<Item = &Map> {
+        self.maps.iter()
+    }
+
+    pub fn get(&self, id: MapId) -> Option<&Map> {
+        self.maps.iter().find(|map| map.id == id)
+    }
+
+    fn index_of(&self, id: MapId) -> Option<usize> {
+        self.maps.iter().position(|map| map.id == id)
+    }
+
+    fn set(&self, map: Map) -> Option<Self> {
+        Some(Self {
+            maps: self.maps.set(self.index_of(map.id)?, map)?,
+            ..self.clone()
+        })
+    }
+
+    /// The campaign with the current map's document brought up to date
+    pub fn store(&self, document: &Document) -> Self {
+        self.get(self.current)
+            .and_then(|map| {
+                self.set(Map {
+                    document: document.clone(),
+                    ..map.clone()
+                })
+            })
+            .unwrap_or_else(|| self.clone())
+    }
+
+    /// Store the current map's document and switch to another map, returning its document
+    pub fn switch(&self, document: &Document, id: MapId) -> Option<(Self, Document)> {
+        if id == self.current {
+            return None;
+        }
+
+        let stored = self.store(document);
+        let target = stored.get(id)?.document.clone();
+
+        Some((
+            Self {
+                current: id,
+                ..stored
+            },
+            target,
+        ))
+    }
+
+    /// Add a map after the others, returning the new campaign and the new map's id
+    pub fn add_map(&self, document: Document) -> (Self, MapId) {
+        let id = MapId(self.next_id);
+        let map = Map {
+            id,
+            name: format!("Map {}", self.maps.len() + 1),
+            document,
+        };
+
+        (
+            Self {
+                next_id: self.next_id + 1,
+                maps: self.maps.push_back(map),
+                ..self.clone()
+            },
+            id,
+        )
+    }
+
+    pub fn rename_map(&self, id: MapId, name: String) -> Option<Self> {
+        self.set(Map {
+            name,
+            ..self.get(id)?.clone()
+        })
+    }
+
+    /// Remove a map along with the exits to and from it. The current map cannot be removed.
+    pub fn remove_map(&self, id: MapId) -> Option<Self> {
+        if id == self.current {
+            return None;
+        }
+
+        Some(Self {
+            maps: self.maps.remove(self.index_of(id)?)?,
+            exits: self
+                .exits
+                .iter()
+                .filter(|exit| exit.from.map != id && exit.to.map != id)
+                .copied()
+                .collect(),
+            ..self.clone()
+        })
+    }
+
+    pub fn add_exit(&self, exit: Exit) -> Self {
+        Self {
+            exits: self.exits.push_back(exit),
+            ..self.clone()
+        }
+    }
+
+    /// The campaign once level `removed` of map `id` has gone: exits on that level are removed, and exits on the
+    /// levels below it move up one
+    pub fn without_level(&self, id: MapId, removed: usize) -> Self {
+        let renumber = |location: Location| {
+            if location.map != id {
+                return Some(location);
+            }
+            Some(Location {
+                place: location.place.without_level(removed)?,
+                ..location
+            })
+        };
+
+        Self {
+            exits: self
+                .exits
+                .iter()
+                .filter_map(|exit| {
+                    Some(Exit {
+                        from: renumber(exit.from)?,
+                        to: renumber(exit.to)?,
+                    })
+                })
+                .collect(),
+            ..self.clone()
+        }
+    }
+
+    /// The campaign without the exits to or from places that aren't on the current map's `document`, as after
+    /// undoing the addition of a level or generating a smaller map
+    pub fn fitted(&self, document: &Document) -> Self {
+        let fits = |location: &Location| {
+            location.map != self.current || document.is_place(&location.place)
+        };
+
+        Self {
+            exits: self
+                .exits
+                .iter()
+                .filter(|exit| fits(&exit.from) && fits(&exit.to))
+                .copied()
+                .collect(),
+            ..self.clone()
+        }
+    }
+
+    /// The exit leading from a cell, if there is one
+    pub fn exit_at(&self, from: Location) -> Option<&Exit> {
+        self.exits.iter().find(|exit| exit.from == from)
+    }
+
+    /// Save the campaign, with the current map's document, as JSON
+    pub fn to_json(&self, document: &Document) -> Result<String, String> {
+        let campaign = Self {
+            version: VERSION,
+            ..self.store(document)
+        };
+        serde_json::to_string(&campaign).map_err(|err| err.to_string())
+    }
+
+    /// Check that a loaded campaign makes sense: that its maps are sound and have their own ids, and that its exits
+    /// lead from and to cells that exist
+    fn validate(&self) -> Result<(), String> {
+        if self.version > VERSION {
+            return Err(String::from(
+                "The campaign was saved by a newer version of the program",
+            ));
+        }
+
+        for (index, map) in self.maps.iter().enumerate() {
+            if map.id.0 >= self.next_id || self.index_of(map.id) != Some(index) {
+                return Err(format!("{}: the map's id is already in use", map.name));
+            }
+            map.document
+                .validate()
+                .map_err(|err| format!("{}, {}", map.name, err))?;
+        }
+
+        let exists = |location: &Location| {
+            self.get(location.map)
+                .is_some_and(|map| map.document.is_place(&location.place))
+        };
+        if !self
+            .exits
+            .iter()
+            .all(|exit| exists(&exit.from) && exists(&exit.to))
+        {
+            return Err(String::from("An exit leads off the map"));
+        }
+
+        Ok(())
+    }
+
+    /// Load a campaign saved by `to_json`, returning it and the document of its current map. Rooms are given the
+    /// tile materials of the same name, where there are any.
+    pub fn from_json(
+        json: &str,
+        tile_materials: &tile::Materials,
+    ) -> Result<(Self, Document), String> {
+        let campaign: Self = serde_json::from_str(json).map_err(|err| err.to_string())?;
+        campaign.validate()?;
+
+        let maps = campaign
+            .maps
+            .iter()
+            .map(|map| Map {
+                document: with_materials(&map.document, tile_materials),
+                ..map.clone()
+            })
+            .collect();
+        let campaign = Self { maps, ..campaign };
+
+        let document = campaign
+            .get(campaign.current)
+            .ok_or_else(|| String::from("The current map is missing"))?
+            .document
+            .clone();
+
+        Ok((campaign, document))
+    }
+}
+
+/// The document with its rooms' materials replaced by the loaded materials of the same name
+fn with_materials(document: &Document, tile_materials: &tile::Materials) -> Document {
+    let rooms = document
+        .rooms
+        .iter()
+        .fold(document.rooms.clone(), |rooms, (id, room)| {
+            let material = tile_materials
+                .as_ref()
+                .iter()
+                .find(|material| material.to_string() == room.tile_material.to_string());
+            match material {
+                Some(material) => rooms
+                    .set(
+                        id,
+                        Room {
+                            tile_material: material.clone(),
+                            ..room.clone()
+                        },
+                    )
+                    .unwrap_or(rooms),
+                None => rooms,
+            }
+        });
+
+    Document {
+        rooms,
+        ..document.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Grid;
+    use crate::room::Rooms;
+    use crate::scale::Scale;
+    use crate::test_support::materials;
+
+    fn document(label: &str) -> Document {
+        let (rooms, _) =
+            Rooms::new().push(Room::new(materials().as_ref()[0].clone(), label.into()));
+        Document::new(3, 3, rooms, Scale::default())
+    }
+
+    fn location(map: MapId, row: usize) -> Location {
+        Location {
+            map,
+            place: Place {
+                level: 0,
+                row,
+                col: 0,
+            },
+        }
+    }
+
+    #[test]
+    fn switching_maps_keeps_each_document() {
+        let campaign = Campaign::new(document("town"));
+        let (campaign, overland) = campaign.add_map(document("overland"));
+
+        let (campaign, shown) = campaign.switch(&document("town edited"), overland).unwrap();
+        assert_eq!(shown.rooms.iter().next().unwrap().1.label, "overland");
+        assert_eq!(campaign.current(), overland);
+
+        let (_, town) = campaign.switch(&shown, MapId(0)).unwrap();
+        assert_eq!(town.rooms.iter().next().unwrap().1.label, "town edited");
+    }
+
+    #[test]
+    fn removing_a_map_removes_its_exits() {
+        let campaign = Campaign::new(document("town"));
+        let (campaign, overland) = campaign.add_map(document("overland"));
+        let (campaign, dungeon) = campaign.add_map(document("dungeon"));
+        let campaign = campaign
+            .add_exit(Exit {
+                from: location(MapId(0), 0),
+                to: location(overland, 1),
+            })
+            .add_exit(Exit {
+                from: location(overland, 2),
+                to: location(dungeon, 0),
+            });
+
+        assert_eq!(campaign.remove_map(MapId(0)), None);
+        let campaign = campaign.remove_map(dungeon).unwrap();
+        assert_eq!(campaign.maps().count(), 2);
+        assert_eq!(campaign.exits.len(), 1);
+        assert_eq!(
+            campaign.exit_at(location(MapId(0), 0)).map(|exit| exit.to),
+            Some(location(overland, 1))
+        );
+    }
+
+    #[test]
+    fn damaged_campaigns_fail_to_load() {
+        let tile_materials = materials();
+        let campaign = Campaign::new(document("town"));
+        let (campaign, overland) = campaign.add_map(document("overland"));
+        let town = document("town");
+        let json = campaign.to_json(&town).unwrap();
+        assert!(Campaign::from_json(&json, &tile_materials).is_ok());
+
+        let load = |campaign: &Campaign, document: &Document| {
+            Campaign::from_json(&campaign.to_json(document).unwrap(), &tile_materials)
+        };
+
+        // A grid whose rows don't match its size
+        let short = Document {
+            cells: town.cells.crop(0, 0, 2, 3),
+            ..town.clone()
+        };
+        let json = campaign
+            .to_json(&short)
+            .unwrap()
+            .replacen("\"rows\":2", "\"rows\":3", 1);
+        assert!(Campaign::from_json(&json, &tile_materials).is_err());
+
+        // Fog that doesn't match the cells
+        let fogged = Document {
+            fog: Grid::with_dimensions(2, 3),
+            ..town.clone()
+        };
+        assert!(load(&campaign, &fogged).is_err());
+
+        // A current level that doesn't exist
+        let lost = Document {
+            level: 1,
+            ..town.clone()
+        };
+        assert!(load(&campaign, &lost).is_err());
+
+        // Rooms sharing an id, or with an id that would be handed out again
+        let (rooms, _) = town
+            .rooms
+            .push(Room::new(materials().as_ref()[0].clone(), "2".into()));
+        let json = campaign
+            .to_json(&Document {
+                rooms,
+                ..town.clone()
+            })
+            .unwrap();
+        assert!(Campaign::from_json(&json, &tile_materials).is_ok());
+        let json = json.replacen("[1,{", "[0,{", 1);
+        assert!(Campaign::from_json(&json, &tile_materials).is_err());
+        let json = campaign
+            .to_json(&town)
+            .unwrap()
+            .replacen("\"next\":1", "\"next\":0", 1);
+        assert!(Campaign::from_json(&json, &tile_materials).is_err());
+
+        // Scales that measure nothing
+        let scaled = |per_square, miles_per_hex| Document {
+            scale: Scale {
+                per_square,
+                miles_per_hex,
+                ..Scale::default()
+            },
+            ..town.clone()
+        };
+        assert!(load(&campaign, &scaled(0.0, None)).is_err());
+        assert!(load(&campaign, &scaled(-5.0, None)).is_err());
+        assert!(scaled(f64::INFINITY, None).validate().is_err());
+        assert!(scaled(f64::NAN, None).validate().is_err());
+        assert!(load(&campaign, &scaled(5.0, Some(0.0))).is_err());
+        assert!(load(&campaign, &scaled(5.0, Some(6.0))).is_ok());
+
+        // Exits and stairs leading off the map
+        let exit = campaign.add_exit(Exit {
+            from: location(MapId(0), 0),
+            to: location(overland, 3),
+        });
+        assert!(load(&exit, &town).is_err());
+        let stair = town.add_stair(crate::levels::Stair {
+            kind: crate::levels::StairKind::Ladder,
+            from: location(MapId(0), 0).place,
+            to: Place {
+                level: 1,
+                row: 0,
+                col: 0,
+            },
+        });
+        assert!(load(&campaign, &stair).is_err());
+
+        // Saved by a later version
+        let json = campaign.to_json(&town).unwrap().replacen(
+            &format!("\"version\":{}", VERSION),
+            "\"version\":99",
+            1,
+        );
+        assert!(Campaign::from_json(&json, &tile_materials).is_err());
+    }
+
+    #[test]
+    fn campaigns_saved_before_versioning_still_load() {
+        let campaign = Campaign::new(document("town"));
+        let json = campaign.to_json(&document("town")).unwrap().replacen(
+            &format!("\"version\":{},", VERSION),
+            "",
+            1,
+        );
+        assert!(!json.contains("version"));
+
+        let (loaded, _) = Campaign::from_json(&json, &materials()).unwrap();
+        assert_eq!(loaded.maps().count(), 1);
+        assert_eq!(loaded.stocking_tables, StockingTables::default());
+    }
+
+    #[test]
+    fn stocking_tables_are_saved_with_the_campaign() {
+        let mut campaign = Campaign::new(document("town"));
+        campaign.stocking_tables.monsters.dice = String::from("1d4");
+        campaign.stocking_tables.monsters.entries.truncate(4);
+
+        let json = campaign.to_json(&document("town")).unwrap();
+        let (loaded, _) = Campaign::from_json(&json, &materials()).unwrap();
+        assert_eq!(loaded.stocking_tables, campaign.stocking_tables);
+    }
+
+    #[test]
+    fn saved_campaigns_load_with_the_same_maps_and_exits() {
+        let tile_materials = materials();
+        let campaign = Campaign::new(document("town"));
+        let (campaign, overland) = campaign.add_map(document("overland"));
+        let campaign = campaign
+            .rename_map(overland, String::from("The Wilds"))
+            .unwrap()
+            .add_exit(Exit {
+                from: location(MapId(0), 1),
+                to: location(overland, 2),
+            });
+        let town = document("town");
+        let town = Document {
+            cells: town.cells.set(1, 1, town.rooms.first_id()).unwrap(),
+            ..town
+        };
+
+        let json = campaign.to_json(&town).unwrap();
+        let (loaded, document) = Campaign::from_json(&json, &tile_materials).unwrap();
+
+        assert_eq!(document.cells.get(1, 1), Some(&town.rooms.first_id()));
+        assert_eq!(
+            document.rooms.iter().next().unwrap().1.tile_material,
+            tile_materials.as_ref()[0]
+        );
+        assert_eq!(loaded.get(overland).unwrap().name, "The Wilds");
+        assert_eq!(
+            loaded.exits.iter().collect::<Vec<_>>(),
+            campaign.exits.iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn removing_a_level_moves_the_exits_below_it_up() {
+        let town = document("town").add_level().add_level();
+        let campaign = Campaign::new(town.clone());
+        let (campaign, overland) = campaign.add_map(document("overland"));
+        let exit = |level, row| Exit {
+            from: Location {
+                map: MapId(0),
+                place: Place { level, row, col: 0 },
+            },
+            to: location(overland, row),
+        };
+        let campaign = campaign
+            .add_exit(exit(0, 0))
+            .add_exit(exit(1, 1))
+            .add_exit(exit(2, 2));
+
+        let town = town.remove_level(1).unwrap();
+        let campaign = campaign.without_level(MapId(0), 1);
+        assert_eq!(
+            campaign.exits.iter().copied().collect::<Vec<_>>(),
+            vec![exit(0, 0), exit(1, 2)]
+        );
+
+        let json = campaign.to_json(&town).unwrap();
+        let (loaded, _) = Campaign::from_json(&json, &materials()).unwrap();
+        assert_eq!(
+            loaded.exits.iter().collect::<Vec<_>>(),
+            campaign.exits.iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn exits_off_a_smaller_map_are_removed() {
+        let town = document("town");
+        let campaign = Campaign::new(town.clone()).add_exit(Exit {
+            from: location(MapId(0), 2),
+            to: location(MapId(0), 0),
+        });
+
+        let smaller = Document {
+            cells: town.cells.crop(0, 0, 2, 3),
+            fog: town.fog.crop(0, 0, 2, 3),
+            ..town
+        };
+        let campaign = campaign.fitted(&smaller);
+        assert_eq!(campaign.exits.len(), 0);
+        assert!(Campaign::from_json(&campaign.to_json(&smaller).unwrap(), &materials()).is_ok());
+    }
+}
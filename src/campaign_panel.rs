@@ -0,0 +1,109 @@
+use yew::prelude::*;
+
+use crate::campaign::{Campaign, Location, MapId};
+use crate::input::{change_file, change_value};
+
+pub struct CampaignPanel {
+    props: Props,
+}
+
+#[derive(Clone, Properties)]
+pub struct Props {
+    /// The campaign with the current map brought up to date
+    pub campaign: Campaign,
+    pub map_selected: Callback<MapId>,
+    pub map_added: Callback<()>,
+    pub map_removed: Callback<MapId>,
+    pub map_renamed: Callback<(MapId, String)>,
+    pub exit_removed: Callback<usize>,
+    pub exit_followed: Callback<usize>,
+    /// Where the exit being placed leads from, once it's been clicked
+    pub exit_from: Option<Location>,
+    pub save: Callback<()>,
+    pub load: Callback<web_sys::File>,
+}
+
+impl CampaignPanel {
+    fn location(&self, location: Location) -> String {
+        let map = self.props.campaign.get(location.map);
+        let level = map.and_then(|map| map.document.levels.get(location.place.level));
+
+        format!(
+            "{}, {}, row {}, column {}",
+            map.map(|map| map.name.as_str()).unwrap_or_default(),
+            level.map(|level| level.name.as_str()).unwrap_or_default(),
+            location.place.row + 1,
+            location.place.col + 1
+        )
+    }
+}
+
+impl Component for CampaignPanel {
+    type Message = ();
+    type Properties = Props;
+
+    fn create(props: Self::Properties, _link: ComponentLink<Self>) -> Self {
+        Self { props }
+    }
+
+    fn update(&mut self, _msg: Self::Message) -> ShouldRender {
+        false
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.props = props;
+        true
+    }
+
+    fn view(&self) -> Html {
+        let current = self.props.campaign.current();
+        let status = match self.props.exit_from {
+            Some(from) => format!(
+                "From {}: click where it leads, on any map",
+                self.location(from)
+            ),
+            None => String::from("With the Exit tool, click the exit and then where it leads"),
+        };
+        let load = self.props.load.clone();
+
+        html!(
+            <fieldset>
+                <legend>{"Campaign"}</legend>
+                <table>
+                    { for self.props.campaign.maps().map(|map| {
+                        let id = map.id;
+                        let class = if id == current { Some("selected") } else { None };
+                        html!(
+                            <tr>
+                                <td><button class=class onclick=self.props.map_selected.reform(move |_| id)>{"Show"}</button></td>
+                                <td><input type="text" value=map.name.clone() onchange=self.props.map_renamed.reform(move |data| (id, change_value(data))) /></td>
+                                <td><button disabled=id == current onclick=self.props.map_removed.reform(move |_| id)>{"Remove"}</button></td>
+                            </tr>
+                        )
+                    }) }
+                </table>
+                <button onclick=self.props.map_added.reform(|_| ())>{"Add Map"}</button>
+                <p>{ status }</p>
+                <table>
+                    { for self.props.campaign.exits.iter().enumerate().map(|(index, exit)| html!(
+                        <tr>
+                            <td>{ self.location(exit.from) }</td>
+                            <td>{"→ "}{ self.location(exit.to) }</td>
+                            <td><button onclick=self.props.exit_followed.reform(move |_| index)>{"Go"}</button></td>
+                            <td><button onclick=self.props.exit_removed.reform(move |_| index)>{"Remove"}</button></td>
+                        </tr>
+                    )) }
+                </table>
+                <button onclick=self.props.save.reform(|_| ())>{"Save Campaign"}</button>
+                <label>
+                    {"Open Campaign "}
+                    <input type="file" accept=".json,application/json" onchange=Callback::from(move |data| {
+                        if let Some(file) = change_file(data) {
+                            load.emit(file);
+                        }
+                    }) />
+                </label>
+            </fieldset>
+        )
+    }
+}
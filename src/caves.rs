@@ -0,0 +1,335 @@
+//! Cave generation with a cellular automaton: a random fill of rock is smoothed until it settles into caverns.
+
+use std::collections::VecDeque;
+
+use crate::hex::GridShape;
+use crate::rng::Rng;
+use crate::room::{Room, RoomId, Rooms};
+use crate::tile;
+use crate::Cells;
+
+/// The name of the room the tunnels between caverns are painted with
+pub const TUNNELS: &str = "Tunnels";
+
+/// Caverns smaller than this are filled in
+const MIN_CAVERN_CELLS: usize = 6;
+
+/// What to do with caverns that aren't connected to each other
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Islands {
+    KeepLargest,
+    Connect,
+}
+
+impl Islands {
+    pub const ALL: [Islands; 2] = [Islands::KeepLargest, Islands::Connect];
+}
+
+impl std::fmt::Display for Islands {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Islands::KeepLargest => "Keep the largest cavern",
+            Islands::Connect => "Connect with tunnels",
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CaveSettings {
+    pub seed: u64,
+    pub rows: usize,
+    pub cols: usize,
+    /// Percentage of cells that start as rock
+    pub fill_percent: usize,
+    pub steps: usize,
+    pub islands: Islands,
+}
+
+impl Default for CaveSettings {
+    fn default() -> Self {
+        Self {
+            seed: 1,
+            rows: 32,
+            cols: 32,
+            fill_percent: 45,
+            steps: 5,
+            islands: Islands::Connect,
+        }
+    }
+}
+
+/// Open (`true`) and rock (`false`) cells, indexed by row then column
+struct Cave {
+    rows: usize,
+    cols: usize,
+    open: Vec<bool>,
+}
+
+impl Cave {
+    fn is_open(&self, row: isize, col: isize) -> bool {
+        row >= 0
+            && col >= 0
+            && (row as usize) < self.rows
+            && (col as usize) < self.cols
+            && self.open[row as usize * self.cols + col as usize]
+    }
+
+    /// One smoothing step: a cell becomes rock if most of its eight neighbours are rock, and open if most are open
+    fn smooth(&self) -> Self {
+        let mut open = self.open.clone();
+
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                let rock = (-1..=1)
+                    .flat_map(|dr| (-1..=1).map(move |dc| (dr, dc)))
+                    .filter(|&(dr, dc)| (dr, dc) != (0, 0))
+                    .filter(|&(dr, dc)| !self.is_open(row as isize + dr, col as isize + dc))
+                    .count();
+
+                match rock {
+                    0..=3 => open[row * self.cols + col] = true,
+                    4 => {}
+                    _ => open[row * self.cols + col] = false,
+                }
+            }
+        }
+
+        Self { open, ..*self }
+    }
+
+    /// The connected caverns, largest first
+    fn caverns(&self) -> Vec<Vec<(usize, usize)>> {
+        let mut seen = vec![false; self.open.len()];
+        let mut caverns = Vec::new();
+
+        for start in 0..self.open.len() {
+            if !self.open[start] || seen[start] {
+                continue;
+            }
+
+            let mut cavern = Vec::new();
+            let mut stack = vec![start];
+            seen[start] = true;
+            while let Some(index) = stack.pop() {
+                let (row, col) = (index / self.cols, index % self.cols);
+                cavern.push((row, col));
+
+                for &(dr, dc) in &[(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                    let (row, col) = (row as isize + dr, col as isize + dc);
+                    if self.is_open(row, col) {
+                        let index = row as usize * self.cols + col as usize;
+                        if !seen[index] {
+                            seen[index] = true;
+                            stack.push(index);
+                        }
+                    }
+                }
+            }
+
+            caverns.push(cavern);
+        }
+
+        // Sorting is stable, so caverns of the same size stay in scan order
+        caverns.sort_by_key(|cavern| std::cmp::Reverse(cavern.len()));
+        caverns
+    }
+}
+
+/// The cell of `cavern` closest to any of the `joined` cells, paired with that joined cell.
+/// A breadth-first search spreading out from every joined cell at once reaches it first.
+fn nearest(
+    rows: usize,
+    cols: usize,
+    joined: &[(usize, usize)],
+    cavern: &[(usize, usize)],
+) -> Option<((usize, usize), (usize, usize))> {
+    let mut target = vec![false; rows * cols];
+    for &(row, col) in cavern {
+        target[row * cols + col] = true;
+    }
+
+    // The joined cell each reached cell was reached from
+    let mut source = vec![None; rows * cols];
+    let mut queue = VecDeque::new();
+    for &cell in joined {
+        source[cell.0 * cols + cell.1] = Some(cell);
+        queue.push_back(cell);
+    }
+
+    while let Some(cell) = queue.pop_front() {
+        let from = source[cell.0 * cols + cell.1]?;
+        if target[cell.0 * cols + cell.1] {
+            return Some((cell, from));
+        }
+
+        for (row, col) in GridShape::Square.neighbours(cell) {
+            if row < rows && col < cols && source[row * cols + col].is_none() {
+                source[row * cols + col] = Some(from);
+                queue.push_back((row, col));
+            }
+        }
+    }
+
+    None
+}
+
+/// The cells of a tunnel running across then down (or down then across) between two cells
+fn tunnel(from: (usize, usize), to: (usize, usize), across_first: bool) -> Vec<(usize, usize)> {
+    let corner = if across_first {
+        (from.0, to.1)
+    } else {
+        (to.0, from.1)
+    };
+
+    let mut cells = Vec::new();
+    for &(a, b) in &[(from, corner), (corner, to)] {
+        for row in a.0.min(b.0)..=a.0.max(b.0) {
+            for col in a.1.min(b.1)..=a.1.max(b.1) {
+                cells.push((row, col));
+            }
+        }
+    }
+    cells
+}
+
+/// Generate a cave. The same settings always give the same map. Returns `None` if there are no materials, or if no
+/// cavern is big enough to keep.
+pub fn generate(settings: &CaveSettings, materials: &tile::Materials) -> Option<(Cells, Rooms)> {
+    let (rows, cols) = (settings.rows, settings.cols);
+    if materials.is_empty() || rows == 0 || cols == 0 {
+        return None;
+    }
+
+    let mut rng = Rng::new(settings.seed);
+    let fill = settings.fill_percent.min(100) as f64 / 100.0;
+
+    // The edge of the map is always rock
+    let open = (0..rows * cols)
+        .map(|index| {
+            let (row, col) = (index / cols, index % cols);
+            let border = row == 0 || col == 0 || row == rows - 1 || col == cols - 1;
+            !rng.chance(fill) && !border
+        })
+        .collect();
+    let cave = (0..settings.steps).fold(Cave { rows, cols, open }, |cave, _| cave.smooth());
+
+    let mut caverns: Vec<_> = cave
+        .caverns()
+        .into_iter()
+        .filter(|cavern| cavern.len() >= MIN_CAVERN_CELLS)
+        .collect();
+    if caverns.is_empty() {
+        return None;
+    }
+    if settings.islands == Islands::KeepLargest {
+        caverns.truncate(1);
+    }
+
+    let materials = materials.as_ref();
+    let mut rooms = Rooms::new();
+    let mut cells: Vec<Option<RoomId>> = vec![None; rows * cols];
+
+    for cavern in &caverns {
+        let label = (rooms.len() + 1).to_string();
+        let mut room = Room::new(rng.choose(materials)?.clone(), label);
+        room.name = String::from("Cavern");
+        let (new_rooms, id) = rooms.push(room);
+        rooms = new_rooms;
+
+        for &(row, col) in cavern {
+            cells[row * cols + col] = Some(id);
+        }
+    }
+
+    // Join each cavern to the nearest of those already joined, through whatever rock is between them
+    let mut tunnels = Vec::new();
+    if let Some((first, rest)) = caverns.split_first() {
+        let mut joined = first.clone();
+        for cavern in rest {
+            let (from, to) = nearest(rows, cols, &joined, cavern)?;
+            tunnels.extend(tunnel(from, to, rng.chance(0.5)));
+            joined.extend(cavern);
+        }
+    }
+
+    tunnels.retain(|&(row, col)| cells[row * cols + col].is_none());
+    if !tunnels.is_empty() {
+        let mut room = Room::new(rng.choose(materials)?.clone(), String::from("T"));
+        room.name = String::from(TUNNELS);
+        let (new_rooms, id) = rooms.push(room);
+        rooms = new_rooms;
+
+        for (row, col) in tunnels {
+            cells[row * cols + col] = Some(id);
+        }
+    }
+
+    let cells =
+        Cells::with_dimensions(rows, cols).map_indexed(|row, col, _| cells[row * cols + col]);
+
+    Some((cells, rooms))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+
+    #[test]
+    fn same_seed_gives_same_cave() {
+        let settings = CaveSettings {
+            seed: 7,
+            ..CaveSettings::default()
+        };
+        let (a, rooms_a) = generate(&settings, &materials()).unwrap();
+        let (b, rooms_b) = generate(&settings, &materials()).unwrap();
+
+        assert_eq!(contents(&a), contents(&b));
+        assert_eq!(room_names(&rooms_a), room_names(&rooms_b));
+    }
+
+    #[test]
+    fn solid_rock_is_not_a_cave() {
+        let solid = CaveSettings {
+            fill_percent: 100,
+            ..CaveSettings::default()
+        };
+        assert!(generate(&solid, &materials()).is_none());
+
+        // Too small for any cavern inside the rock around the edge
+        let tiny = CaveSettings {
+            rows: 3,
+            cols: 3,
+            fill_percent: 0,
+            ..CaveSettings::default()
+        };
+        assert!(generate(&tiny, &materials()).is_none());
+    }
+
+    #[test]
+    fn keeping_the_largest_cavern_gives_one_room() {
+        for seed in 0..10 {
+            let settings = CaveSettings {
+                seed,
+                islands: Islands::KeepLargest,
+                ..CaveSettings::default()
+            };
+            let (cells, rooms) = generate(&settings, &materials()).unwrap();
+
+            assert_eq!(rooms.len(), 1, "seed {}", seed);
+            assert!(is_connected(&cells), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn tunnels_connect_every_cavern() {
+        for seed in 0..10 {
+            let settings = CaveSettings {
+                seed,
+                ..CaveSettings::default()
+            };
+            let (cells, _) = generate(&settings, &materials()).unwrap();
+            assert!(is_connected(&cells), "seed {}", seed);
+        }
+    }
+}
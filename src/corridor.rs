@@ -0,0 +1,331 @@
+//! Corridor routing: an A* search for the cheapest corridor joining two rooms, preferring empty cells and straight runs.
+
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, BinaryHeap};
+
+use crate::edges::EdgeId;
+use crate::hex::GridShape;
+use crate::room::RoomId;
+use crate::Cells;
+
+/// Corridors run between square cells, turning up, right, down or left
+const DIRECTIONS: usize = 4;
+
+/// The room a corridor is painted as
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CorridorRoom {
+    New,
+    Selected,
+}
+
+impl CorridorRoom {
+    pub const ALL: [CorridorRoom; 2] = [CorridorRoom::New, CorridorRoom::Selected];
+}
+
+impl std::fmt::Display for CorridorRoom {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            CorridorRoom::New => "A new room",
+            CorridorRoom::Selected => "The selected room",
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CorridorSettings {
+    pub width: usize,
+    /// Extra cost of each turn, counted in cells
+    pub turn_cost: usize,
+    /// Extra cost of each cell of another room the corridor crosses, counted in cells
+    pub room_cost: usize,
+    pub doors: bool,
+    pub room: CorridorRoom,
+}
+
+impl Default for CorridorSettings {
+    fn default() -> Self {
+        Self {
+            width: 1,
+            turn_cost: 2,
+            room_cost: 10,
+            doors: true,
+            room: CorridorRoom::New,
+        }
+    }
+}
+
+pub struct Corridor {
+    /// The empty cells to paint
+    pub cells: Vec<(usize, usize)>,
+    /// A door into each room, if asked for
+    pub doors: Vec<EdgeId>,
+}
+
+/// Find the cheapest corridor from room `from` to room `to`. Returns `None` if there's no way through or the rooms
+/// already touch.
+pub fn route(
+    cells: &Cells,
+    from: RoomId,
+    to: RoomId,
+    settings: &CorridorSettings,
+) -> Option<Corridor> {
+    let width = settings.width.max(1);
+    let (rows, cols) = (cells.rows(), cells.cols());
+    if from == to || rows < width || cols < width {
+        return None;
+    }
+
+    // Positions are the top left cells of the corridor's width-by-width square
+    let (position_rows, position_cols) = (rows - width + 1, cols - width + 1);
+    let room_at = |(row, col): (usize, usize)| cells.get(row, col).copied().flatten();
+    let square = |position: usize| {
+        let (top, left) = (position / position_cols, position % position_cols);
+        (top..top + width).flat_map(move |row| (left..left + width).map(move |col| (row, col)))
+    };
+    let touches =
+        |position: usize, room: RoomId| square(position).any(|cell| room_at(cell) == Some(room));
+    let crossing_cost = |position: usize| {
+        square(position)
+            .filter(|cell| room_at(*cell).is_some_and(|room| room != from && room != to))
+            .count()
+            * settings.room_cost
+    };
+
+    // Cells needed to reach the target's bounding box, which never overestimates
+    let target: Vec<_> = cells
+        .iter()
+        .filter(|(_, _, cell)| **cell == Some(to))
+        .map(|(row, col, _)| (row, col))
+        .collect();
+    let (top, bottom) = (
+        target.iter().map(|c| c.0).min()?,
+        target.iter().map(|c| c.0).max()?,
+    );
+    let (left, right) = (
+        target.iter().map(|c| c.1).min()?,
+        target.iter().map(|c| c.1).max()?,
+    );
+    let heuristic = |position: usize| {
+        let (row, col) = (position / position_cols, position % position_cols);
+        let rows_away = top
+            .saturating_sub(row + width - 1)
+            .max(row.saturating_sub(bottom));
+        let cols_away = left
+            .saturating_sub(col + width - 1)
+            .max(col.saturating_sub(right));
+        rows_away + cols_away
+    };
+
+    // Search states are a position and the direction it was entered from, so turns can be costed. The extra
+    // direction is for the starting positions, which haven't come from anywhere.
+    let states = position_rows * position_cols * (DIRECTIONS + 1);
+    let mut costs = vec![usize::MAX; states];
+    let mut came_from = vec![None; states];
+    let mut open = BinaryHeap::new();
+
+    for position in (0..position_rows * position_cols).filter(|position| touches(*position, from)) {
+        let state = position * (DIRECTIONS + 1) + DIRECTIONS;
+        costs[state] = 0;
+        open.push(Reverse((heuristic(position), 0, state)));
+    }
+
+    let mut goal = None;
+    while let Some(Reverse((_, cost, state))) = open.pop() {
+        if cost > costs[state] {
+            continue;
+        }
+
+        let (position, direction) = (state / (DIRECTIONS + 1), state % (DIRECTIONS + 1));
+        if touches(position, to) {
+            goal = Some(state);
+            break;
+        }
+
+        let cell = (position / position_cols, position % position_cols);
+        for step in 0..DIRECTIONS {
+            let (row, col) = match GridShape::Square.neighbour(cell, step) {
+                Some((row, col)) if row < position_rows && col < position_cols => (row, col),
+                _ => continue,
+            };
+
+            let next = row * position_cols + col;
+            let turn = if direction != DIRECTIONS && direction != step {
+                settings.turn_cost
+            } else {
+                0
+            };
+            let next_cost = cost + 1 + turn + crossing_cost(next);
+            let next_state = next * (DIRECTIONS + 1) + step;
+            if next_cost < costs[next_state] {
+                costs[next_state] = next_cost;
+                came_from[next_state] = Some(state);
+                open.push(Reverse((
+                    next_cost + heuristic(next),
+                    next_cost,
+                    next_state,
+                )));
+            }
+        }
+    }
+
+    let mut path = vec![goal?];
+    while let Some(previous) = came_from[*path.last()?] {
+        path.push(previous);
+    }
+    path.reverse();
+    let path: Vec<usize> = path
+        .into_iter()
+        .map(|state| state / (DIRECTIONS + 1))
+        .collect();
+
+    // Only empty cells are painted: where the corridor crosses a room, it runs through the room
+    let mut painted = BTreeSet::new();
+    let corridor_cells: Vec<_> = path
+        .iter()
+        .flat_map(|position| square(*position))
+        .filter(|cell| room_at(*cell).is_none() && painted.insert(*cell))
+        .collect();
+    if corridor_cells.is_empty() {
+        return None;
+    }
+
+    // Each door goes in the middle of where the corridor first meets its room
+    let meeting = |room: RoomId, position: &usize| {
+        let mut edges: Vec<_> = square(*position)
+            .filter(|cell| room_at(*cell).is_none())
+            .flat_map(|cell| {
+                GridShape::Square
+                    .neighbours(cell)
+                    .into_iter()
+                    .filter(|neighbour| room_at(*neighbour) == Some(room))
+                    .filter_map(move |neighbour| EdgeId::between(cell, neighbour))
+            })
+            .collect();
+        edges.sort();
+        edges.dedup();
+        edges
+    };
+    let middle = |edges: Vec<EdgeId>| edges[edges.len() / 2];
+
+    let doors = if settings.doors {
+        let from_door = path
+            .iter()
+            .map(|position| meeting(from, position))
+            .find(|edges| !edges.is_empty());
+        let to_door = path
+            .iter()
+            .rev()
+            .map(|position| meeting(to, position))
+            .find(|edges| !edges.is_empty());
+        from_door.into_iter().chain(to_door).map(middle).collect()
+    } else {
+        Vec::new()
+    };
+
+    Some(Corridor {
+        cells: corridor_cells,
+        doors,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Grid;
+    use crate::room::{Room, Rooms};
+    use crate::test_support::*;
+
+    /// Cells from rows of characters, with `.` for an empty cell and a digit for the room with that index
+    fn layout(rows: &[&str]) -> (Cells, Vec<RoomId>) {
+        let material = materials().as_ref()[0].clone();
+        let mut rooms = Rooms::new();
+        let ids: Vec<_> = (0..10)
+            .map(|label| {
+                let (new_rooms, id) = rooms.push(Room::new(material.clone(), label.to_string()));
+                rooms = new_rooms;
+                id
+            })
+            .collect();
+
+        let cells = Grid::with_dimensions(rows.len(), rows[0].len()).map_indexed(
+            |row, col, _: &Option<RoomId>| {
+                let digit = rows[row].as_bytes()[col]
+                    .checked_sub(b'0')
+                    .filter(|digit| *digit < 10)?;
+                Some(ids[digit as usize])
+            },
+        );
+        (cells, ids)
+    }
+
+    fn render(cells: &Cells, corridor: &Corridor) -> Vec<String> {
+        (0..cells.rows())
+            .map(|row| {
+                (0..cells.cols())
+                    .map(|col| match cells.get(row, col).copied().flatten() {
+                        Some(_) => '#',
+                        None if corridor.cells.contains(&(row, col)) => '+',
+                        None => '.',
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn joins_two_rooms_with_doors_at_both_ends() {
+        let (cells, ids) = layout(&["00.....11", "00.....11"]);
+        let corridor = route(&cells, ids[0], ids[1], &CorridorSettings::default()).unwrap();
+
+        assert_eq!(corridor.cells.len(), 5);
+        assert!(corridor
+            .cells
+            .iter()
+            .all(|(row, _)| *row == corridor.cells[0].0));
+        let row = corridor.cells[0].0;
+        assert_eq!(
+            corridor.doors,
+            vec![EdgeId::left(row, 2), EdgeId::left(row, 7)]
+        );
+    }
+
+    #[test]
+    fn turns_as_little_as_possible() {
+        let (cells, ids) = layout(&["0.....", "......", "......", ".....1"]);
+        let corridor = route(&cells, ids[0], ids[1], &CorridorSettings::default()).unwrap();
+
+        let rendered = render(&cells, &corridor);
+        let turns = rendered == ["#+++++", ".....+", ".....+", ".....#"]
+            || rendered == ["#.....", "+.....", "+.....", "+++++#"];
+        assert!(turns, "{:?}", rendered);
+    }
+
+    #[test]
+    fn goes_round_other_rooms_unless_that_costs_more() {
+        let (cells, ids) = layout(&["......", "0.22.1", "..22..", "......"]);
+
+        let around = route(&cells, ids[0], ids[1], &CorridorSettings::default()).unwrap();
+        assert!(!render(&cells, &around)[1].contains('+'));
+
+        let cheap_rooms = CorridorSettings {
+            room_cost: 0,
+            ..CorridorSettings::default()
+        };
+        let through = route(&cells, ids[0], ids[1], &cheap_rooms).unwrap();
+        assert_eq!(render(&cells, &through)[1], "#+##+#");
+    }
+
+    #[test]
+    fn wide_corridors_fill_their_width() {
+        let (cells, ids) = layout(&["00....11", "00....11"]);
+        let settings = CorridorSettings {
+            width: 2,
+            doors: false,
+            ..CorridorSettings::default()
+        };
+        let corridor = route(&cells, ids[0], ids[1], &settings).unwrap();
+
+        assert_eq!(render(&cells, &corridor), ["##++++##", "##++++##"]);
+        assert!(corridor.doors.is_empty());
+    }
+}
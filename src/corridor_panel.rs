@@ -0,0 +1,99 @@
+use yew::prelude::*;
+
+use crate::corridor::{CorridorRoom, CorridorSettings};
+use crate::input::change_value;
+
+pub struct CorridorPanel {
+    props: Props,
+    link: ComponentLink<Self>,
+}
+
+pub enum Msg {
+    Width(String),
+    TurnCost(String),
+    RoomCost(String),
+    Doors(bool),
+    Room(String),
+}
+
+#[derive(Clone, Properties)]
+pub struct Props {
+    pub settings: CorridorSettings,
+    pub settings_changed: Callback<CorridorSettings>,
+    /// The label of the room the corridor starts from, once it's been clicked
+    pub from: Option<String>,
+}
+
+fn cost(value: &str) -> Option<usize> {
+    value.parse().ok()
+}
+
+impl Component for CorridorPanel {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        Self { props, link }
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        let mut settings = self.props.settings;
+
+        match msg {
+            Msg::Width(value) => match cost(&value).filter(|width| *width > 0) {
+                Some(width) => settings.width = width,
+                None => return true,
+            },
+            Msg::TurnCost(value) => match cost(&value) {
+                Some(turn_cost) => settings.turn_cost = turn_cost,
+                None => return true,
+            },
+            Msg::RoomCost(value) => match cost(&value) {
+                Some(room_cost) => settings.room_cost = room_cost,
+                None => return true,
+            },
+            Msg::Doors(doors) => settings.doors = doors,
+            Msg::Room(room) => {
+                if let Some(room) = CorridorRoom::ALL.iter().find(|r| r.to_string() == room) {
+                    settings.room = *room;
+                }
+            }
+        }
+
+        self.props.settings_changed.emit(settings);
+
+        false
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.props = props;
+        true
+    }
+
+    fn view(&self) -> Html {
+        let settings = self.props.settings;
+        let status = match &self.props.from {
+            Some(label) => format!("From room {}: click the room to join it to", label),
+            None => String::from("Click the room to start from"),
+        };
+
+        html!(
+            <fieldset>
+                <legend>{"Corridor"}</legend>
+                <p>{ status }</p>
+                <label>{"Width"}<input type="number" min="1" value=settings.width.to_string() onchange=self.link.callback(|data| Msg::Width(change_value(data))) /></label>
+                <label>{"Cost of a turn"}<input type="number" min="0" value=settings.turn_cost.to_string() onchange=self.link.callback(|data| Msg::TurnCost(change_value(data))) /></label>
+                <label>{"Cost of crossing a room, per cell"}<input type="number" min="0" value=settings.room_cost.to_string() onchange=self.link.callback(|data| Msg::RoomCost(change_value(data))) /></label>
+                <label>
+                    {"Paint as"}
+                    <select onchange=self.link.callback(|data| Msg::Room(change_value(data)))>
+                        { for CorridorRoom::ALL.iter().map(|room| html!(
+                            <option selected=*room == settings.room>{ room.to_string() }</option>
+                        )) }
+                    </select>
+                </label>
+                <label class="inline"><input type="checkbox" checked=settings.doors onclick=self.link.callback(move |_| Msg::Doors(!settings.doors)) />{"Doors at both ends"}</label>
+            </fieldset>
+        )
+    }
+}
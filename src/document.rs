@@ -0,0 +1,682 @@
+use std::collections::BTreeMap;
+
+use crate::edges::{Edge, Edges};
+use crate::fog::Fog;
+use crate::grid::Grid;
+use crate::levels::{Level, Place, Stair, Stairs};
+use crate::lights::Lights;
+use crate::list::List;
+use crate::room::{Room, RoomId, Rooms};
+use crate::scale::Scale;
+use crate::tokens::{Token, Tokens};
+use crate::Cells;
+
+/// Everything that undo and redo act upon. The cells, edges, tokens, fog and lights are those of the current level,
+/// so everything that works on a single map works on whichever level is shown.
+///
+/// Documents are saved in campaign files, so fields added later need a `#[serde(default)]` for older files to load.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Document {
+    pub cells: Cells,
+    pub rooms: Rooms,
+    #[serde(default)]
+    pub edges: Edges,
+    #[serde(default)]
+    pub scale: Scale,
+    /// Creatures and characters, kept apart from the cells they stand on
+    #[serde(default)]
+    pub tokens: Tokens,
+    pub fog: Fog,
+    #[serde(default)]
+    pub lights: Lights,
+    /// Every level, from the top down. The current level's entry is only brought up to date when switching away
+    /// from it, so use `levels()` to read them all.
+    pub levels: List<Level>,
+    pub level: usize,
+    #[serde(default)]
+    pub stairs: Stairs,
+}
+
+impl Document {
+    /// A new document with one level
+    pub fn new(rows: usize, cols: usize, rooms: Rooms, scale: Scale) -> Self {
+        let level = Level::new(String::from("Level 1"), rows, cols);
+
+        Self {
+            cells: level.cells.clone(),
+            rooms,
+            edges: level.edges.clone(),
+            scale,
+            tokens: level.tokens.clone(),
+            fog: level.fog.clone(),
+            lights: level.lights.clone(),
+            levels: List::from(vec![level]),
+            level: 0,
+            stairs: Stairs::new(),
+        }
+    }
+
+    /// `document` showing `level`
+    fn load(level: &Level, document: Self) -> Self {
+        Self {
+            cells: level.cells.clone(),
+            edges: level.edges.clone(),
+            tokens: level.tokens.clone(),
+            fog: level.fog.clone(),
+            lights: level.lights.clone(),
+            ..document
+        }
+    }
+
+    /// The current level as it stands
+    pub fn current_level(&self) -> Level {
+        Level {
+            name: self
+                .levels
+                .get(self.level)
+                .map(|level| level.name.clone())
+                .unwrap_or_default(),
+            cells: self.cells.clone(),
+            edges: self.edges.clone(),
+            tokens: self.tokens.clone(),
+            fog: self.fog.clone(),
+            lights: self.lights.clone(),
+        }
+    }
+
+    /// Every level, from the top down
+    pub fn levels(&self) -> List<Level> {
+        self.levels
+            .set(self.level, self.current_level())
+            .unwrap_or_else(|| self.levels.clone())
+    }
+
+    /// The level under the current one, if there is one
+    pub fn level_below(&self) -> Option<&Level> {
+        self.levels.get(self.level + 1)
+    }
+
+    /// Show another level, leaving the current one as it is
+    pub fn switch_level(&self, index: usize) -> Option<Self> {
+        if index == self.level {
+            return None;
+        }
+
+        let levels = self.levels();
+        Some(Self::load(
+            &levels.get(index)?.clone(),
+            Self {
+                level: index,
+                levels,
+                ..self.clone()
+            },
+        ))
+    }
+
+    /// Add an empty level the size of the current one below the bottom level
+    pub fn add_level(&self) -> Self {
+        let level = Level::new(
+            format!("Level {}", self.levels.len() + 1),
+            self.cells.rows(),
+            self.cells.cols(),
+        );
+
+        Self {
+            levels: self.levels.push_back(level),
+            ..self.clone()
+        }
+    }
+
+    pub fn rename_level(&self, index: usize, name: String) -> Option<Self> {
+        let level = Level {
+            name,
+            ..self.levels.get(index)?.clone()
+        };
+
+        Some(Self {
+            levels: self.levels.set(index, level)?,
+            ..self.clone()
+        })
+    }
+
+    /// Remove a level along with any stairs to it. The last remaining level cannot be removed.
+    pub fn remove_level(&self, index: usize) -> Option<Self> {
+        if self.levels.len() <= 1 || index >= self.levels.len() {
+            return None;
+        }
+
+        let levels = self.levels().remove(index)?;
+        let current = if self.level > index || self.level == levels.len() {
+            self.level - 1
+        } else {
+            self.level
+        };
+        let stairs = self
+            .stairs
+            .iter()
+            .filter_map(|stair| stair.without_level(index))
+            .collect();
+
+        Some(Self::load(
+            &levels.get(current)?.clone(),
+            Self {
+                level: current,
+                levels,
+                stairs,
+                ..self.clone()
+            },
+        ))
+    }
+
+    pub fn add_stair(&self, stair: Stair) -> Self {
+        Self {
+            stairs: self.stairs.push_back(stair),
+            ..self.clone()
+        }
+    }
+
+    /// Replace the current level with a generated one. Rooms are shared by every level, so generated rooms that aren't
+    /// already in the document are added with fresh ids. Rooms the generator painted over are removed if they are left
+    /// on no level and nothing has been written about them. Stairs to the current level are removed along with its
+    /// walls, tokens, fog and lights.
+    pub fn generated(&self, cells: Cells, generated: Rooms) -> Self {
+        let (rooms, ids) = generated.iter().fold(
+            (self.rooms.clone(), BTreeMap::new()),
+            |(rooms, mut ids), (id, room)| {
+                if self.rooms.get(id) == Some(room) {
+                    ids.insert(id, id);
+                    (rooms, ids)
+                } else {
+                    let (rooms, new_id) = rooms.push(room.clone());
+                    ids.insert(id, new_id);
+                    (rooms, ids)
+                }
+            },
+        );
+        let cells = cells.map(|cell| cell.and_then(|id| ids.get(&id).copied()));
+
+        let levels = self.levels();
+        let replaced = |id: RoomId| self.cells.iter().any(|(_, _, cell)| *cell == Some(id));
+        let used = |id: RoomId| {
+            ids.values().any(|&new_id| new_id == id)
+                || levels.iter().enumerate().any(|(index, level)| {
+                    index != self.level && level.cells.iter().any(|(_, _, cell)| *cell == Some(id))
+                })
+        };
+        let written = |room: &Room| {
+            !room.name.is_empty() || !room.description.is_empty() || !room.gm_notes.is_empty()
+        };
+        let rooms = self
+            .rooms
+            .iter()
+            .filter(|&(id, room)| replaced(id) && !used(id) && !written(room))
+            .fold(rooms, |rooms, (id, _)| rooms.remove(id).unwrap_or(rooms));
+
+        let (rows, cols) = (cells.rows(), cells.cols());
+        Self {
+            cells,
+            rooms,
+            edges: Edges::new(),
+            tokens: Tokens::new(),
+            fog: Grid::with_dimensions(rows, cols),
+            lights: Lights::new(),
+            stairs: self
+                .stairs
+                .iter()
+                .filter(|stair| stair.ends_on(self.level).is_empty())
+                .copied()
+                .collect(),
+            ..self.clone()
+        }
+    }
+
+    /// The same document with the cells of every level remapped
+    fn remap_cells(&self, f: impl Fn(RoomId) -> Option<RoomId>) -> Self {
+        let remap = |cells: &Cells| cells.map(|cell| cell.and_then(&f));
+
+        Self {
+            cells: remap(&self.cells),
+            levels: self
+                .levels
+                .iter()
+                .map(|level| Level {
+                    cells: remap(&level.cells),
+                    ..level.clone()
+                })
+                .collect(),
+            ..self.clone()
+        }
+    }
+
+    /// Remove a room, leaving the cells it covered empty. The last remaining room cannot be deleted.
+    pub fn delete_room(&self, id: RoomId) -> Option<Self> {
+        if self.rooms.len() <= 1 {
+            return None;
+        }
+
+        Some(Self {
+            rooms: self.rooms.remove(id)?,
+            ..self.remap_cells(|room| if room == id { None } else { Some(room) })
+        })
+    }
+
+    /// Reassign the cells of room `from` to room `into`, then remove room `from`.
+    pub fn merge_rooms(&self, from: RoomId, into: RoomId) -> Option<Self> {
+        if from == into || !self.rooms.contains(into) {
+            return None;
+        }
+
+        Some(Self {
+            rooms: self.rooms.remove(from)?,
+            ..self.remap_cells(|room| Some(if room == from { into } else { room }))
+        })
+    }
+
+    /// Move a room so that it ends up at position `index` in the room list.
+    pub fn move_room(&self, id: RoomId, index: usize) -> Option<Self> {
+        if index >= self.rooms.len() || self.rooms.index_of(id) == Some(index) {
+            return None;
+        }
+
+        Some(Self {
+            rooms: self.rooms.move_to(id, index)?,
+            ..self.clone()
+        })
+    }
+
+    /// Check that a loaded document makes sense: that its rooms have their own ids and its scale measures something,
+    /// that every level's grids are the size they claim, that its fog matches its cells, and that cells, walls,
+    /// tokens, lights and stairs stay on the map and refer to rooms and levels that exist.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.level >= self.levels.len() {
+            return Err(String::from("The current level is missing"));
+        }
+        if !self.rooms.has_consistent_ids() {
+            return Err(String::from("A room's id is already in use"));
+        }
+        let positive = |distance: f64| distance.is_finite() && distance > 0.0;
+        if !positive(self.scale.per_square) || !self.scale.miles_per_hex.is_none_or(positive) {
+            return Err(String::from("The scale isn't a positive distance"));
+        }
+
+        let levels = self.levels();
+        for level in levels.iter() {
+            let fail = |problem: &str| Err(format!("{}: {}", level.name, problem));
+            let cells = &level.cells;
+            let on_map = |(row, col): (usize, usize)| row < cells.rows() && col < cells.cols();
+
+            if !cells.is_consistent() || !level.fog.is_consistent() {
+                return fail("the grid is the wrong size");
+            }
+            if !level.fog.same_size(cells) {
+                return fail("the fog doesn't match the map");
+            }
+            if cells
+                .iter()
+                .any(|(_, _, cell)| cell.is_some_and(|id| !self.rooms.contains(id)))
+            {
+                return fail("a cell belongs to a missing room");
+            }
+            if level.edges.iter().any(|(id, _)| {
+                let (before, after) = id.cells();
+                !before.is_some_and(on_map) && !on_map(after)
+            }) {
+                return fail("a wall is off the map");
+            }
+            if level.tokens.iter().any(|token| {
+                token.row.saturating_add(token.size.cells()) > cells.rows()
+                    || token.col.saturating_add(token.size.cells()) > cells.cols()
+            }) {
+                return fail("a token is off the map");
+            }
+            if level
+                .lights
+                .iter()
+                .any(|light| !on_map((light.row, light.col)))
+            {
+                return fail("a light is off the map");
+            }
+        }
+
+        if self.stairs.iter().any(|stair| {
+            [stair.from, stair.to]
+                .iter()
+                .any(|place| !self.is_place(place))
+        }) {
+            return Err(String::from("A stair leads off the map"));
+        }
+
+        Ok(())
+    }
+
+    /// Whether the place is a cell on one of the levels
+    pub fn is_place(&self, place: &Place) -> bool {
+        let levels = self.levels();
+        levels
+            .get(place.level)
+            .is_some_and(|level| level.cells.get(place.row, place.col).is_some())
+    }
+
+    /// Whether the cell is part of a room. In the player view that means the players can see it.
+    pub fn shows_cell(&self, row: usize, col: usize) -> bool {
+        self.cells.get(row, col).is_some_and(Option::is_some)
+    }
+
+    /// Whether any part of the token stands on a room
+    pub fn shows(&self, token: &Token) -> bool {
+        token.cells().any(|(row, col)| self.shows_cell(row, col))
+    }
+
+    /// What the players may see: revealed cells of rooms that aren't hidden, and the walls, tokens, lights and stairs
+    /// beside them. GM notes are removed and secret doors look like walls.
+    pub fn player_view(&self) -> Self {
+        let rooms = self
+            .rooms
+            .iter()
+            .fold(self.rooms.clone(), |rooms, (id, room)| {
+                if room.hidden {
+                    rooms.remove(id)
+                } else {
+                    rooms.set(
+                        id,
+                        Room {
+                            gm_notes: String::new(),
+                            ..room.clone()
+                        },
+                    )
+                }
+                .unwrap_or(rooms)
+            });
+
+        let cells = self.cells.map_indexed(|row, col, cell| {
+            cell.filter(|id| rooms.contains(*id) && self.fog.get(row, col) == Some(&true))
+        });
+        let shown = |(row, col): (usize, usize)| cells.get(row, col).is_some_and(Option::is_some);
+
+        let edges = self
+            .edges
+            .iter()
+            .filter(|(id, _)| {
+                let (before, after) = id.cells();
+                before.is_some_and(shown) || shown(after)
+            })
+            .map(|(id, edge)| match edge {
+                Edge::SecretDoor => (id, Edge::Wall),
+                edge => (id, edge),
+            })
+            .collect();
+        let lights = self
+            .lights
+            .iter()
+            .filter(|light| shown((light.row, light.col)))
+            .cloned()
+            .collect();
+
+        let stairs = self
+            .stairs
+            .iter()
+            .filter(|stair| {
+                stair
+                    .ends_on(self.level)
+                    .iter()
+                    .all(|(end, _)| shown((end.row, end.col)))
+            })
+            .copied()
+            .collect();
+
+        let view = Self {
+            cells,
+            rooms,
+            edges,
+            lights,
+            stairs,
+            ..self.clone()
+        };
+
+        Self {
+            tokens: self
+                .tokens
+                .iter()
+                .filter(|token| view.shows(token))
+                .cloned()
+                .collect(),
+            ..view
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edges::EdgeId;
+    use crate::levels::StairKind;
+
+    fn document() -> (Document, RoomId) {
+        let material = crate::test_support::materials().as_ref()[0].clone();
+        let (rooms, id) = Rooms::new().push(Room::new(material, "1".into()));
+        (Document::new(4, 4, rooms, Scale::default()), id)
+    }
+
+    fn place(level: usize) -> Place {
+        Place {
+            level,
+            row: 1,
+            col: 1,
+        }
+    }
+
+    #[test]
+    fn levels_keep_their_own_cells() {
+        let (document, id) = document();
+        let document = Document {
+            cells: document.cells.set(0, 0, Some(id)).unwrap(),
+            ..document.add_level()
+        };
+
+        let below = document.switch_level(1).unwrap();
+        assert_eq!(below.cells.get(0, 0), Some(&None));
+        assert_eq!(below.level_below(), None);
+
+        let above = below.switch_level(0).unwrap();
+        assert_eq!(above.cells.get(0, 0), Some(&Some(id)));
+        assert_eq!(
+            above.level_below().map(|level| level.name.as_str()),
+            Some("Level 2")
+        );
+    }
+
+    #[test]
+    fn removing_a_level_removes_its_stairs_and_renumbers_the_rest() {
+        let (document, _) = document();
+        let document = document.add_level().add_level();
+        let stair = |from, to| Stair {
+            kind: StairKind::Ladder,
+            from: place(from),
+            to: place(to),
+        };
+        let document = document
+            .add_stair(stair(0, 1))
+            .add_stair(stair(0, 2))
+            .switch_level(2)
+            .unwrap();
+
+        let removed = document.remove_level(1).unwrap();
+        assert_eq!(removed.levels().len(), 2);
+        assert_eq!(removed.level, 1);
+        assert_eq!(removed.levels().get(1).unwrap().name, "Level 3");
+        assert_eq!(
+            removed.stairs.iter().collect::<Vec<_>>(),
+            vec![&stair(0, 1)]
+        );
+
+        let last = removed.remove_level(0).unwrap();
+        assert_eq!(last.remove_level(0), None);
+    }
+
+    #[test]
+    fn generating_a_level_keeps_the_rooms_of_the_others() {
+        let (document, id) = document();
+        let material = crate::test_support::materials().as_ref()[0].clone();
+        let (rooms, replaced) = document.rooms.push(Room::new(material.clone(), "2".into()));
+        let (rooms, named) = rooms.push(Room {
+            name: String::from("Crypt"),
+            ..Room::new(material.clone(), "3".into())
+        });
+        let (rooms, unpainted) = rooms.push(Room::new(material.clone(), "4".into()));
+        let cells = document
+            .cells
+            .set(3, 2, Some(replaced))
+            .and_then(|cells| cells.set(3, 3, Some(named)))
+            .unwrap();
+        let document = Document {
+            cells,
+            rooms,
+            ..document
+        }
+        .add_level()
+        .add_stair(Stair {
+            kind: StairKind::Stairs,
+            from: place(0),
+            to: place(1),
+        })
+        .switch_level(1)
+        .unwrap();
+        let document = Document {
+            cells: document.cells.set(2, 2, Some(id)).unwrap(),
+            ..document
+        }
+        .switch_level(0)
+        .unwrap();
+
+        // Generators number their rooms from the start, so their ids clash with the document's
+        let (generated, first) = Rooms::new().push(Room::new(material.clone(), "A".into()));
+        let (generated, second) = generated.push(Room::new(material, "B".into()));
+        let cells = Grid::with_dimensions(4, 4)
+            .set(0, 0, Some(first))
+            .and_then(|cells| cells.set(0, 1, Some(second)))
+            .unwrap();
+
+        let document = document.generated(cells, generated);
+        let label = |cell: Option<&Option<RoomId>>| {
+            cell.copied()
+                .flatten()
+                .and_then(|id| document.rooms.get(id))
+                .map(|room| room.label.as_str())
+        };
+        assert_eq!(label(document.cells.get(0, 0)), Some("A"));
+        assert_eq!(label(document.cells.get(0, 1)), Some("B"));
+        assert_eq!(
+            label(document.levels().get(1).unwrap().cells.get(2, 2)),
+            Some("1")
+        );
+        assert!(!document.rooms.contains(replaced));
+        assert!(document.rooms.contains(named));
+        assert!(document.rooms.contains(unpainted));
+        assert_eq!(document.rooms.len(), 5);
+        assert!(document.stairs.is_empty());
+    }
+
+    #[test]
+    fn the_player_view_only_keeps_stairs_on_shown_cells() {
+        let (document, id) = document();
+        let stair = |row| Stair {
+            kind: StairKind::Trapdoor,
+            from: Place {
+                level: 0,
+                row,
+                col: 1,
+            },
+            to: place(1),
+        };
+        let document = Document {
+            cells: document.cells.map(|_| Some(id)),
+            fog: document.fog.set(1, 1, true).unwrap(),
+            ..document.add_level()
+        }
+        .add_stair(stair(1))
+        .add_stair(stair(2));
+
+        let view = document.player_view();
+        assert_eq!(view.stairs.iter().collect::<Vec<_>>(), vec![&stair(1)]);
+    }
+
+    #[test]
+    fn the_player_view_hides_secrets() {
+        let (document, id) = document();
+        let material = crate::test_support::materials().as_ref()[0].clone();
+        let rooms = document
+            .rooms
+            .set(
+                id,
+                Room {
+                    gm_notes: "The statue is a mimic".into(),
+                    ..Room::new(material.clone(), "1".into())
+                },
+            )
+            .unwrap();
+        let (rooms, hidden) = rooms.push(Room {
+            hidden: true,
+            ..Room::new(material, "2".into())
+        });
+        let fog = crate::fog::set_all(&document.fog, true).unwrap();
+        let document = Document {
+            cells: document
+                .cells
+                .map_indexed(|_, col, _| Some(if col < 2 { id } else { hidden })),
+            rooms,
+            edges: document
+                .edges
+                .set(EdgeId::left(0, 1), Some(Edge::SecretDoor))
+                .set(EdgeId::left(1, 2), Some(Edge::Door))
+                .set(EdgeId::left(0, 3), Some(Edge::Wall)),
+            fog: (0..4).fold(fog, |fog, col| fog.set(3, col, false).unwrap()),
+            ..document
+        };
+
+        let view = document.player_view();
+        assert_eq!(view.rooms.ids().collect::<Vec<_>>(), vec![id]);
+        assert_eq!(view.rooms.get(id).unwrap().gm_notes, "");
+        assert_eq!(
+            view.cells
+                .iter()
+                .filter(|(_, _, cell)| cell.is_some())
+                .count(),
+            6
+        );
+        assert_eq!(view.cells.get(3, 0), Some(&None));
+        assert_eq!(view.cells.get(0, 2), Some(&None));
+
+        // The secret door looks like a wall, and the hidden room's wall is left out
+        assert_eq!(
+            view.edges.iter().collect::<Vec<_>>(),
+            vec![
+                (EdgeId::left(0, 1), Edge::Wall),
+                (EdgeId::left(1, 2), Edge::Door)
+            ]
+        );
+    }
+
+    #[test]
+    fn deleting_a_room_clears_it_from_every_level() {
+        let (document, id) = document();
+        let material = crate::test_support::materials().as_ref()[0].clone();
+        let (rooms, _) = document.rooms.push(Room::new(material, "2".into()));
+        let document = Document {
+            rooms,
+            ..document.add_level()
+        };
+        let document = document.switch_level(1).unwrap();
+        let document = Document {
+            cells: document.cells.set(2, 2, Some(id)).unwrap(),
+            ..document
+        }
+        .switch_level(0)
+        .unwrap();
+
+        let deleted = document.delete_room(id).unwrap();
+        assert_eq!(
+            deleted.levels().get(1).unwrap().cells.get(2, 2),
+            Some(&None)
+        );
+    }
+}
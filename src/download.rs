@@ -0,0 +1,24 @@
+use wasm_bindgen::{JsCast, JsValue};
+
+/// Offer `contents` to the user as a file download
+pub fn download(file_name: &str, mime_type: &str, contents: &str) -> Result<(), JsValue> {
+    let parts = js_sys::Array::of1(&JsValue::from_str(contents));
+    let properties = web_sys::BlobPropertyBag::new();
+    properties.set_type(mime_type);
+    let blob = web_sys::Blob::new_with_str_sequence_and_options(&parts, &properties)?;
+
+    let url = web_sys::Url::create_object_url_with_blob(&blob)?;
+
+    let document = web_sys::window()
+        .and_then(|window| window.document())
+        .ok_or_else(|| JsValue::from_str("No document"))?;
+
+    let anchor = document
+        .create_element("a")?
+        .dyn_into::<web_sys::HtmlElement>()?;
+    anchor.set_attribute("href", &url)?;
+    anchor.set_attribute("download", file_name)?;
+    anchor.click();
+
+    web_sys::Url::revoke_object_url(&url)
+}
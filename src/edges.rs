@@ -0,0 +1,187 @@
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum Edge {
+    Wall,
+    Door,
+    /// A door that players see as a wall until it is found
+    SecretDoor,
+}
+
+#[derive(
+    Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
+)]
+pub enum Side {
+    Top,
+    Left,
+}
+
+/// An edge between two cells, named by the cell below or to the right of it.
+/// The bottom edge of a cell is the top edge of the cell below, and so on.
+#[derive(
+    Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
+)]
+pub struct EdgeId {
+    pub row: usize,
+    pub col: usize,
+    pub side: Side,
+}
+
+impl EdgeId {
+    pub fn top(row: usize, col: usize) -> Self {
+        Self {
+            row,
+            col,
+            side: Side::Top,
+        }
+    }
+
+    pub fn left(row: usize, col: usize) -> Self {
+        Self {
+            row,
+            col,
+            side: Side::Left,
+        }
+    }
+
+    pub fn bottom(row: usize, col: usize) -> Self {
+        Self::top(row + 1, col)
+    }
+
+    pub fn right(row: usize, col: usize) -> Self {
+        Self::left(row, col + 1)
+    }
+
+    /// The edge between two orthogonally adjacent cells
+    pub fn between(a: (usize, usize), b: (usize, usize)) -> Option<Self> {
+        let ((row_a, col_a), (row_b, col_b)) = (a.min(b), a.max(b));
+
+        if col_a == col_b && row_a + 1 == row_b {
+            Some(Self::top(row_b, col_b))
+        } else if row_a == row_b && col_a + 1 == col_b {
+            Some(Self::left(row_b, col_b))
+        } else {
+            None
+        }
+    }
+
+    /// The cells on either side of the edge. The first is missing for edges along the top or left of the grid.
+    pub fn cells(&self) -> (Option<(usize, usize)>, (usize, usize)) {
+        match self.side {
+            Side::Top => (
+                self.row.checked_sub(1).map(|row| (row, self.col)),
+                (self.row, self.col),
+            ),
+            Side::Left => (
+                self.col.checked_sub(1).map(|col| (self.row, col)),
+                (self.row, self.col),
+            ),
+        }
+    }
+
+    /// The edge closest to a point, given in cells
+    pub fn nearest(x: f64, y: f64) -> Self {
+        let row = y.max(0.0) as usize;
+        let col = x.max(0.0) as usize;
+        let fx = x - col as f64;
+        let fy = y - row as f64;
+
+        let candidates = [
+            (fy, Self::top(row, col)),
+            (1.0 - fy, Self::bottom(row, col)),
+            (fx, Self::left(row, col)),
+            (1.0 - fx, Self::right(row, col)),
+        ];
+
+        candidates
+            .iter()
+            .fold(candidates[0], |best, candidate| {
+                if candidate.0 < best.0 {
+                    *candidate
+                } else {
+                    best
+                }
+            })
+            .1
+    }
+
+    /// The rectangle used to draw a door on the edge, as (x, y, width, height) in cells
+    pub fn door_rect(&self) -> (f64, f64, f64, f64) {
+        let (row, col) = (self.row as f64, self.col as f64);
+        match self.side {
+            Side::Top => (col + 0.25, row - 0.1, 0.5, 0.2),
+            Side::Left => (col - 0.1, row + 0.25, 0.2, 0.5),
+        }
+    }
+
+    /// The end points of the edge, as (x, y) in cells
+    pub fn line(&self) -> ((usize, usize), (usize, usize)) {
+        match self.side {
+            Side::Top => ((self.col, self.row), (self.col + 1, self.row)),
+            Side::Left => ((self.col, self.row), (self.col, self.row + 1)),
+        }
+    }
+}
+
+/// Walls and doors placed on the edges between cells. Saved as a list, since JSON keys can only be strings.
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(into = "Vec<(EdgeId, Edge)>", from = "Vec<(EdgeId, Edge)>")]
+pub struct Edges(Rc<BTreeMap<EdgeId, Edge>>);
+
+impl Edges {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, id: EdgeId) -> Option<Edge> {
+        self.0.get(&id).copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (EdgeId, Edge)> + '_ {
+        self.0.iter().map(|(id, edge)| (*id, *edge))
+    }
+
+    pub fn set(&self, id: EdgeId, edge: Option<Edge>) -> Self {
+        if self.get(id) == edge {
+            return self.clone();
+        }
+
+        let mut edges = self.0.as_ref().clone();
+        match edge {
+            Some(edge) => edges.insert(id, edge),
+            None => edges.remove(&id),
+        };
+
+        Self(Rc::new(edges))
+    }
+
+    /// Whether movement between two orthogonally adjacent cells is blocked by a wall
+    pub fn is_walled(&self, a: (usize, usize), b: (usize, usize)) -> bool {
+        EdgeId::between(a, b).and_then(|id| self.get(id)) == Some(Edge::Wall)
+    }
+}
+
+impl From<Edges> for Vec<(EdgeId, Edge)> {
+    fn from(edges: Edges) -> Self {
+        edges.iter().collect()
+    }
+}
+
+impl From<Vec<(EdgeId, Edge)>> for Edges {
+    fn from(edges: Vec<(EdgeId, Edge)>) -> Self {
+        edges.into_iter().collect()
+    }
+}
+
+impl std::iter::FromIterator<(EdgeId, Edge)> for Edges {
+    fn from_iter<I: IntoIterator<Item = (EdgeId, Edge)>>(iter: I) -> Self {
+        Self(Rc::new(iter.into_iter().collect()))
+    }
+}
+
+impl PartialEq for Edges {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
@@ -0,0 +1,297 @@
+use std::fmt::Write;
+
+use yew::prelude::*;
+use yew::virtual_dom::VTag;
+
+use crate::document::Document;
+use crate::edges::Edge;
+use crate::iso::{self, IsoSettings, Projection};
+use crate::labels;
+use crate::tile;
+
+/// The size in pixels of one cell in exported images
+const CELL_SIZE: usize = 32;
+
+pub struct SvgOptions {
+    pub show_labels: bool,
+}
+
+/// Escape text for use in XML and HTML content and attribute values
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Tile patterns for every material, for cells to be filled with
+fn write_patterns(output: &mut String, tile_materials: &tile::Materials) {
+    output.push_str("<defs>\n");
+    for material in tile_materials.as_ref() {
+        let size = material.size();
+        let _ = writeln!(
+            output,
+            r#"<pattern id="{}" patternUnits="userSpaceOnUse" width="{}" height="{}"><image href="{}" width="{}" height="{}"/></pattern>"#,
+            escape(&material.name()),
+            size,
+            size,
+            escape(&material.href()),
+            size,
+            size
+        );
+    }
+    output.push_str("</defs>\n");
+}
+
+/// Render the map as a standalone SVG image
+pub fn svg(document: &Document, tile_materials: &tile::Materials, options: &SvgOptions) -> String {
+    let shape = document.scale.shape;
+    let (width, height) = shape.size(document.cells.rows(), document.cells.cols());
+
+    let mut output = String::new();
+
+    // Writing to a String never fails
+    let _ = writeln!(
+        output,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}">"#,
+        width * CELL_SIZE as f64,
+        height * CELL_SIZE as f64,
+        width,
+        height
+    );
+
+    write_patterns(&mut output, tile_materials);
+
+    for (row, col, cell) in document.cells.iter() {
+        if let Some(room) = cell.and_then(|id| document.rooms.get(id)) {
+            let fill = room.tile_material.url_reference();
+            let _ = match shape.polygon((row, col)) {
+                Some(points) => writeln!(
+                    output,
+                    r#"<polygon points="{}" style="fill:{}"/>"#,
+                    points, fill
+                ),
+                None => writeln!(
+                    output,
+                    r#"<rect x="{}" y="{}" width="1" height="1" style="fill:{}"/>"#,
+                    col, row, fill
+                ),
+            };
+        }
+    }
+
+    for (id, edge) in document.edges.iter() {
+        let ((x1, y1), (x2, y2)) = id.line();
+        match edge {
+            Edge::Wall => {
+                let _ = writeln!(
+                    output,
+                    r#"<line x1="{}" y1="{}" x2="{}" y2="{}" {}/>"#,
+                    x1,
+                    y1,
+                    x2,
+                    y2,
+                    format_attributes(WALL_ATTRIBUTES)
+                );
+            }
+            Edge::Door | Edge::SecretDoor => {
+                let (x, y, width, height) = id.door_rect();
+                let attributes = if edge == Edge::Door {
+                    DOOR_ATTRIBUTES
+                } else {
+                    SECRET_DOOR_ATTRIBUTES
+                };
+                let _ = writeln!(
+                    output,
+                    r#"<rect x="{}" y="{}" width="{}" height="{}" {}/>"#,
+                    x,
+                    y,
+                    width,
+                    height,
+                    format_attributes(attributes)
+                );
+            }
+        }
+    }
+
+    for stair in document.stairs.iter() {
+        for (end, target) in stair.ends_on(document.level) {
+            let (x, y) = shape.centre((end.row, end.col));
+            let arrow = if target.level < end.level {
+                "▲"
+            } else {
+                "▼"
+            };
+            let _ = writeln!(
+                output,
+                r#"<text x="{}" y="{}" {}>{}{}</text>"#,
+                x,
+                y,
+                format_attributes(LABEL_ATTRIBUTES),
+                stair.kind.symbol(),
+                arrow
+            );
+        }
+    }
+
+    if options.show_labels {
+        for label in labels::labels(&document.cells, &document.rooms, shape) {
+            let (x, y) = shape.point(label.x, label.y);
+            let _ = writeln!(
+                output,
+                r#"<text x="{}" y="{}" {}>{}</text>"#,
+                x,
+                y,
+                format_attributes(LABEL_ATTRIBUTES),
+                escape(&label.text)
+            );
+        }
+    }
+
+    output.push_str("</svg>\n");
+
+    output
+}
+
+/// Render the map in isometric projection as a standalone SVG image
+pub fn iso_svg(
+    document: &Document,
+    tile_materials: &tile::Materials,
+    settings: &IsoSettings,
+    options: &SvgOptions,
+) -> String {
+    let cells = &document.cells;
+    let projection = Projection::new(cells.rows(), cells.cols(), settings);
+    let (width, height) = projection.size();
+
+    let mut output = String::new();
+
+    let _ = writeln!(
+        output,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}">"#,
+        width * CELL_SIZE as f64,
+        height * CELL_SIZE as f64,
+        width,
+        height
+    );
+
+    write_patterns(&mut output, tile_materials);
+
+    let _ = writeln!(
+        output,
+        r#"<g transform="{}">"#,
+        projection.floor_transform()
+    );
+    for (row, col, cell) in cells.iter() {
+        if let Some(room) = cell.and_then(|id| document.rooms.get(id)) {
+            let _ = writeln!(
+                output,
+                r#"<rect x="{}" y="{}" width="1" height="1" style="fill:{}"/>"#,
+                col,
+                row,
+                room.tile_material.url_reference()
+            );
+        }
+    }
+    output.push_str("</g>\n");
+
+    for face in iso::faces(cells, &document.edges, &projection) {
+        let _ = writeln!(
+            output,
+            r#"<polygon points="{}" fill="{}" {}/>"#,
+            face.points,
+            face.fill,
+            format_attributes(FACE_ATTRIBUTES)
+        );
+    }
+
+    if options.show_labels {
+        for label in labels::labels(cells, &document.rooms, document.scale.shape) {
+            let (x, y) = projection.project(label.x, label.y, 0.0);
+            let _ = writeln!(
+                output,
+                r#"<text x="{}" y="{}" {}>{}</text>"#,
+                x,
+                y,
+                format_attributes(LABEL_ATTRIBUTES),
+                escape(&label.text)
+            );
+        }
+    }
+
+    output.push_str("</svg>\n");
+
+    output
+}
+
+/// SVG presentation attributes, as (name, value) pairs
+pub type Attributes = &'static [(&'static str, &'static str)];
+
+/// Presentation attributes for room labels: dark text with a light halo so it reads over any tile
+pub const LABEL_ATTRIBUTES: Attributes = &[
+    ("font-family", "sans-serif"),
+    ("font-size", "0.6"),
+    ("font-weight", "bold"),
+    ("text-anchor", "middle"),
+    ("dominant-baseline", "central"),
+    ("fill", "black"),
+    ("stroke", "white"),
+    ("stroke-width", "0.15"),
+    ("stroke-linejoin", "round"),
+    ("paint-order", "stroke"),
+];
+
+pub const WALL_ATTRIBUTES: Attributes = &[
+    ("stroke", "black"),
+    ("stroke-width", "0.2"),
+    ("stroke-linecap", "round"),
+];
+
+pub const DOOR_ATTRIBUTES: Attributes = &[
+    ("fill", "saddlebrown"),
+    ("stroke", "black"),
+    ("stroke-width", "0.05"),
+];
+
+pub const SECRET_DOOR_ATTRIBUTES: Attributes = &[
+    ("fill", "plum"),
+    ("stroke", "black"),
+    ("stroke-width", "0.05"),
+    ("stroke-dasharray", "0.1 0.05"),
+];
+
+pub const FACE_ATTRIBUTES: Attributes = &[
+    ("stroke", "black"),
+    ("stroke-width", "0.03"),
+    ("stroke-linejoin", "round"),
+];
+
+/// Attributes written out for an SVG file
+fn format_attributes(attributes: Attributes) -> String {
+    attributes
+        .iter()
+        .map(|(name, value)| format!(r#"{}="{}""#, name, value))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A group drawn in the app the same way its contents are exported, and ignoring the mouse
+pub fn group(attributes: Attributes, children: Html) -> Html {
+    let mut group = VTag::new("g");
+    group.add_attribute("pointer-events", &"none");
+    for (name, value) in attributes {
+        group.add_attribute(name, value);
+    }
+    group.add_child(children);
+    group.into()
+}
@@ -0,0 +1,80 @@
+use crate::grid::Grid;
+use crate::room::RoomId;
+use crate::Cells;
+
+/// Which cells the players have seen. Every cell starts hidden.
+pub type Fog = Grid<bool>;
+
+/// How the fog tool reveals or hides cells
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FogMode {
+    /// Every cell of the room under the cursor
+    Room,
+    /// The cell under the cursor
+    Brush,
+    /// A rectangle dragged out with the cursor
+    Rectangle,
+}
+
+impl FogMode {
+    pub const ALL: [FogMode; 3] = [FogMode::Room, FogMode::Brush, FogMode::Rectangle];
+}
+
+impl std::fmt::Display for FogMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self, f)
+    }
+}
+
+/// Set the cells matching `selected` to `revealed`, or return `None` if none of them change
+fn set_where(fog: &Fog, selected: impl Fn(usize, usize) -> bool, revealed: bool) -> Option<Fog> {
+    if fog
+        .iter()
+        .all(|(row, col, cell)| *cell == revealed || !selected(row, col))
+    {
+        return None;
+    }
+
+    Some(fog.map_indexed(
+        |row, col, cell| {
+            if selected(row, col) {
+                revealed
+            } else {
+                *cell
+            }
+        },
+    ))
+}
+
+pub fn set_all(fog: &Fog, revealed: bool) -> Option<Fog> {
+    set_where(fog, |_, _| true, revealed)
+}
+
+/// Reveal every visible cell
+pub fn reveal(fog: &Fog, visible: &Grid<bool>) -> Option<Fog> {
+    set_where(fog, |row, col| visible.get(row, col) == Some(&true), true)
+}
+
+pub fn set_room(fog: &Fog, cells: &Cells, room: RoomId, revealed: bool) -> Option<Fog> {
+    set_where(
+        fog,
+        |row, col| cells.get(row, col) == Some(&Some(room)),
+        revealed,
+    )
+}
+
+/// Set the rectangle with corners at cells `a` and `b`, inclusive
+pub fn set_rectangle(
+    fog: &Fog,
+    a: (usize, usize),
+    b: (usize, usize),
+    revealed: bool,
+) -> Option<Fog> {
+    let rows = a.0.min(b.0)..=a.0.max(b.0);
+    let cols = a.1.min(b.1)..=a.1.max(b.1);
+    set_where(
+        fog,
+        |row, col| rows.contains(&row) && cols.contains(&col),
+        revealed,
+    )
+}
@@ -0,0 +1,92 @@
+use yew::prelude::*;
+
+use crate::fog::FogMode;
+use crate::input::change_value;
+use crate::vision::VisionSettings;
+
+pub struct FogPanel {
+    props: Props,
+    link: ComponentLink<Self>,
+}
+
+pub enum Msg {
+    Mode(String),
+}
+
+#[derive(Clone, Properties)]
+pub struct Props {
+    pub mode: FogMode,
+    pub mode_changed: Callback<FogMode>,
+    pub player_view: bool,
+    pub player_view_changed: Callback<bool>,
+    /// Reveal (`true`) or hide (`false`) the whole map
+    pub fog_set: Callback<bool>,
+    pub vision: VisionSettings,
+    pub vision_changed: Callback<VisionSettings>,
+}
+
+impl Component for FogPanel {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        Self { props, link }
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        match msg {
+            Msg::Mode(mode) => {
+                if let Some(mode) = FogMode::ALL.iter().find(|m| m.to_string() == mode) {
+                    self.props.mode_changed.emit(*mode);
+                }
+            }
+        }
+
+        false
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.props = props;
+        true
+    }
+
+    fn view(&self) -> Html {
+        let mode = self.props.mode;
+        let player_view = self.props.player_view;
+        let vision = self.props.vision;
+
+        html!(
+            <fieldset>
+                <legend>{"Fog of War"}</legend>
+                <label>
+                    {"Reveal by "}
+                    <select onchange=self.link.callback(|data| Msg::Mode(change_value(data)))>
+                        { for FogMode::ALL.iter().map(|option| html!(
+                            <option selected=*option == mode>{ option.to_string() }</option>
+                        )) }
+                    </select>
+                </label>
+                <button onclick=self.props.fog_set.reform(|_| true)>{"Reveal All"}</button>
+                <button onclick=self.props.fog_set.reform(|_| false)>{"Hide All"}</button>
+                <label class="inline">
+                    <input type="checkbox" checked=player_view onclick=self.props.player_view_changed.reform(move |_| !player_view) />
+                    {"Player View"}
+                </label>
+                <div>
+                    <label class="inline">
+                        <input type="checkbox" checked=vision.show onclick=self.props.vision_changed.reform(move |_| VisionSettings { show: !vision.show, ..vision }) />
+                        {"Show Vision"}
+                    </label>
+                    <label class="inline">
+                        <input type="checkbox" checked=vision.open_doors onclick=self.props.vision_changed.reform(move |_| VisionSettings { open_doors: !vision.open_doors, ..vision }) />
+                        {"Doors Open"}
+                    </label>
+                    <label class="inline">
+                        <input type="checkbox" checked=vision.auto_reveal onclick=self.props.vision_changed.reform(move |_| VisionSettings { auto_reveal: !vision.auto_reveal, ..vision }) />
+                        {"Reveal What Is Seen"}
+                    </label>
+                </div>
+            </fieldset>
+        )
+    }
+}
@@ -0,0 +1,306 @@
+use yew::prelude::*;
+
+use crate::bsp::{self, BspSettings};
+use crate::caves::{self, CaveSettings, Islands};
+use crate::input::change_value;
+use crate::rng;
+use crate::room::{RoomId, Rooms};
+use crate::tile;
+use crate::wfc::{self, WfcSettings};
+use crate::Cells;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Algorithm {
+    Bsp,
+    Caves,
+    Wfc,
+}
+
+impl Algorithm {
+    const ALL: [Algorithm; 3] = [Algorithm::Bsp, Algorithm::Caves, Algorithm::Wfc];
+}
+
+impl std::fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Algorithm::Bsp => "Rooms and corridors",
+            Algorithm::Caves => "Caves",
+            Algorithm::Wfc => "Copy an example",
+        })
+    }
+}
+
+pub struct GenerateDialog {
+    props: Props,
+    link: ComponentLink<Self>,
+    algorithm: Algorithm,
+    seed: String,
+    rows: usize,
+    cols: usize,
+    bsp: BspSettings,
+    caves: CaveSettings,
+    wfc: WfcSettings,
+    /// The outcome of the last attempt, if there's anything to report
+    message: Option<String>,
+}
+
+pub enum Msg {
+    Algorithm(String),
+    Seed(String),
+    RandomSeed,
+    Rows(String),
+    Cols(String),
+    Rooms(String),
+    MinRoomSize(String),
+    MaxRoomSize(String),
+    CorridorWidth(String),
+    FillPercent(String),
+    Steps(String),
+    Islands(String),
+    PatternSize(String),
+    Symmetry(bool),
+    MaxContradictions(String),
+    Generate,
+}
+
+#[derive(Clone, Properties)]
+pub struct Props {
+    pub tile_materials: tile::Materials,
+    /// The example map to copy
+    pub sample: Cells,
+    pub rooms: Rooms,
+    pub generated: Callback<(Cells, Rooms)>,
+    pub closed: Callback<()>,
+}
+
+fn set_positive(field: &mut usize, value: &str) {
+    if let Some(value) = value.parse().ok().filter(|value: &usize| *value > 0) {
+        *field = value;
+    }
+}
+
+impl GenerateDialog {
+    /// The new map and its rooms, with how many contradictions were backed out of along the way
+    fn generate(&self) -> Result<(Cells, Rooms, usize), String> {
+        let (seed, rows, cols) = (rng::parse_seed(&self.seed), self.rows, self.cols);
+        let materials = &self.props.tile_materials;
+        if materials.is_empty() {
+            return Err(String::from("There are no tiles to build the map from"));
+        }
+
+        let generated = match self.algorithm {
+            Algorithm::Bsp => bsp::generate(
+                &BspSettings {
+                    seed,
+                    rows,
+                    cols,
+                    max_room_size: self.bsp.max_room_size.max(self.bsp.min_room_size),
+                    ..self.bsp
+                },
+                materials,
+            ),
+            Algorithm::Caves => caves::generate(
+                &CaveSettings {
+                    seed,
+                    rows,
+                    cols,
+                    ..self.caves
+                },
+                materials,
+            ),
+            Algorithm::Wfc => {
+                let rooms = &self.props.rooms;
+                let ids: Vec<RoomId> = rooms.ids().collect();
+                let sample = self
+                    .props
+                    .sample
+                    .map(|cell| cell.and_then(|id| rooms.index_of(id)));
+
+                let generated = wfc::generate(
+                    &sample,
+                    &WfcSettings {
+                        seed,
+                        rows,
+                        cols,
+                        ..self.wfc
+                    },
+                )
+                .map_err(|err| err.to_string())?;
+                let cells = generated
+                    .cells
+                    .map(|cell| cell.and_then(|index| ids.get(index).copied()));
+
+                return Ok((cells, rooms.clone(), generated.contradictions));
+            }
+        };
+
+        generated
+            .map(|(cells, rooms)| (cells, rooms, 0))
+            .ok_or_else(|| String::from("No rooms fit with these settings; try a bigger map"))
+    }
+
+    fn settings(&self) -> Html {
+        match self.algorithm {
+            Algorithm::Bsp => {
+                let settings = &self.bsp;
+                html!(
+                    <>
+                        <label>{"Rooms"}<input type="number" min="1" value=settings.rooms.to_string() onchange=self.link.callback(|data| Msg::Rooms(change_value(data))) /></label>
+                        <label>{"Smallest room"}<input type="number" min="1" value=settings.min_room_size.to_string() onchange=self.link.callback(|data| Msg::MinRoomSize(change_value(data))) /></label>
+                        <label>{"Largest room"}<input type="number" min="1" value=settings.max_room_size.to_string() onchange=self.link.callback(|data| Msg::MaxRoomSize(change_value(data))) /></label>
+                        <label>{"Corridor width"}<input type="number" min="1" value=settings.corridor_width.to_string() onchange=self.link.callback(|data| Msg::CorridorWidth(change_value(data))) /></label>
+                    </>
+                )
+            }
+            Algorithm::Caves => {
+                let settings = &self.caves;
+                html!(
+                    <>
+                        <label>{"Initial rock %"}<input type="number" min="0" max="100" value=settings.fill_percent.to_string() onchange=self.link.callback(|data| Msg::FillPercent(change_value(data))) /></label>
+                        <label>{"Smoothing steps"}<input type="number" min="0" value=settings.steps.to_string() onchange=self.link.callback(|data| Msg::Steps(change_value(data))) /></label>
+                        <label>
+                            {"Separate caverns"}
+                            <select onchange=self.link.callback(|data| Msg::Islands(change_value(data)))>
+                                { for Islands::ALL.iter().map(|islands| html!(
+                                    <option selected=*islands == settings.islands>{ islands.to_string() }</option>
+                                )) }
+                            </select>
+                        </label>
+                    </>
+                )
+            }
+            Algorithm::Wfc => {
+                let settings = self.wfc;
+                let sample = &self.props.sample;
+                html!(
+                    <>
+                        <p>{ format!("Copying the {} by {} cells selected, or the whole map if nothing is selected", sample.cols(), sample.rows()) }</p>
+                        <label>{"Pattern size"}<input type="number" min="1" max="5" value=settings.pattern_size.to_string() onchange=self.link.callback(|data| Msg::PatternSize(change_value(data))) /></label>
+                        <label><input type="checkbox" checked=settings.symmetry onclick=self.link.callback(move |_| Msg::Symmetry(!settings.symmetry)) />{"Rotate and reflect patterns"}</label>
+                        <label>{"Contradictions before giving up"}<input type="number" min="0" value=settings.max_contradictions.to_string() onchange=self.link.callback(|data| Msg::MaxContradictions(change_value(data))) /></label>
+                    </>
+                )
+            }
+        }
+    }
+}
+
+impl Component for GenerateDialog {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        let bsp = BspSettings::default();
+        Self {
+            props,
+            link,
+            algorithm: Algorithm::Bsp,
+            seed: bsp.seed.to_string(),
+            rows: bsp.rows,
+            cols: bsp.cols,
+            bsp,
+            caves: CaveSettings::default(),
+            wfc: WfcSettings::default(),
+            message: None,
+        }
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        match msg {
+            Msg::Algorithm(algorithm) => {
+                if let Some(algorithm) = Algorithm::ALL.iter().find(|a| a.to_string() == algorithm)
+                {
+                    self.algorithm = *algorithm;
+                }
+            }
+            Msg::Seed(seed) => self.seed = seed,
+            Msg::RandomSeed => {
+                self.seed = ((js_sys::Math::random() * u32::MAX as f64) as u64).to_string();
+            }
+            Msg::Rows(value) => set_positive(&mut self.rows, &value),
+            Msg::Cols(value) => set_positive(&mut self.cols, &value),
+            Msg::Rooms(value) => set_positive(&mut self.bsp.rooms, &value),
+            Msg::MinRoomSize(value) => set_positive(&mut self.bsp.min_room_size, &value),
+            Msg::MaxRoomSize(value) => set_positive(&mut self.bsp.max_room_size, &value),
+            Msg::CorridorWidth(value) => set_positive(&mut self.bsp.corridor_width, &value),
+            Msg::FillPercent(value) => {
+                if let Some(percent) = value.parse().ok().filter(|percent| *percent <= 100) {
+                    self.caves.fill_percent = percent;
+                }
+            }
+            Msg::Steps(value) => {
+                if let Ok(steps) = value.parse() {
+                    self.caves.steps = steps;
+                }
+            }
+            Msg::Islands(islands) => {
+                if let Some(islands) = Islands::ALL.iter().find(|i| i.to_string() == islands) {
+                    self.caves.islands = *islands;
+                }
+            }
+            Msg::PatternSize(value) => {
+                if let Some(size) = value.parse().ok().filter(|size| (1..=5).contains(size)) {
+                    self.wfc.pattern_size = size;
+                }
+            }
+            Msg::Symmetry(symmetry) => self.wfc.symmetry = symmetry,
+            Msg::MaxContradictions(value) => {
+                if let Ok(max_contradictions) = value.parse() {
+                    self.wfc.max_contradictions = max_contradictions;
+                }
+            }
+            Msg::Generate => match self.generate() {
+                Ok((cells, rooms, contradictions)) => {
+                    self.message = if contradictions > 0 {
+                        Some(format!(
+                            "Backtracked out of {} contradictions",
+                            contradictions
+                        ))
+                    } else {
+                        None
+                    };
+                    self.props.generated.emit((cells, rooms));
+                }
+                Err(message) => self.message = Some(message),
+            },
+        }
+
+        true
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.props = props;
+        true
+    }
+
+    fn view(&self) -> Html {
+        let algorithm = self.algorithm;
+
+        html!(
+            <fieldset class="room-details">
+                <legend>{"Generate Map"}</legend>
+                <label>
+                    {"Algorithm"}
+                    <select onchange=self.link.callback(|data| Msg::Algorithm(change_value(data)))>
+                        { for Algorithm::ALL.iter().map(|option| html!(
+                            <option selected=*option == algorithm>{ option.to_string() }</option>
+                        )) }
+                    </select>
+                </label>
+                <label>
+                    {"Seed"}
+                    <input type="text" value=self.seed.clone() onchange=self.link.callback(|data| Msg::Seed(change_value(data))) />
+                    <button onclick=self.link.callback(|_| Msg::RandomSeed)>{"Random"}</button>
+                </label>
+                <label>{"Rows"}<input type="number" min="1" value=self.rows.to_string() onchange=self.link.callback(|data| Msg::Rows(change_value(data))) /></label>
+                <label>{"Columns"}<input type="number" min="1" value=self.cols.to_string() onchange=self.link.callback(|data| Msg::Cols(change_value(data))) /></label>
+                { self.settings() }
+                { for self.message.iter().map(|message| html!(<p>{ message }</p>)) }
+                <div>
+                    <button onclick=self.link.callback(|_| Msg::Generate)>{"Generate"}</button>
+                    <button onclick=self.props.closed.reform(|_| ())>{"Close"}</button>
+                </div>
+            </fieldset>
+        )
+    }
+}
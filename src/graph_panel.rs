@@ -0,0 +1,123 @@
+use yew::prelude::*;
+
+use crate::download;
+use crate::room_graph::RoomGraph;
+
+const SIZE: f64 = 240.0;
+const RADIUS: f64 = 90.0;
+
+pub struct GraphPanel {
+    props: Props,
+    link: ComponentLink<Self>,
+}
+
+pub enum Msg {
+    ExportDot,
+    ExportJson,
+}
+
+#[derive(Clone, Properties)]
+pub struct Props {
+    pub graph: RoomGraph,
+}
+
+impl GraphPanel {
+    fn position(&self, index: usize) -> (f64, f64) {
+        let angle = std::f64::consts::PI * 2.0 * (index as f64)
+            / (self.props.graph.nodes.len().max(1) as f64)
+            - std::f64::consts::FRAC_PI_2;
+
+        (
+            SIZE / 2.0 + RADIUS * angle.cos(),
+            SIZE / 2.0 + RADIUS * angle.sin(),
+        )
+    }
+
+    fn diagram(&self) -> Html {
+        let graph = &self.props.graph;
+        let dead_ends = graph.dead_ends();
+        let choke_points = graph.choke_points();
+
+        let position_of = |id| {
+            graph
+                .nodes
+                .iter()
+                .position(|node| node.id == id)
+                .map(|index| self.position(index))
+        };
+
+        html!(
+            <svg width=SIZE height=SIZE>
+                { for graph.edges.iter().filter_map(|edge| {
+                    let (x1, y1) = position_of(edge.from)?;
+                    let (x2, y2) = position_of(edge.to)?;
+                    let width = (edge.shared_edges as f64).sqrt().min(6.0);
+                    Some(html!(<line x1=x1 y1=y1 x2=x2 y2=y2 stroke="grey" stroke-width=width />))
+                }) }
+                { for graph.nodes.iter().enumerate().map(|(index, node)| {
+                    let (x, y) = self.position(index);
+                    let fill = if choke_points.contains(&node.id) {
+                        "salmon"
+                    } else if dead_ends.contains(&node.id) {
+                        "khaki"
+                    } else {
+                        "white"
+                    };
+                    html!(
+                        <g>
+                            <title>{ &node.name }</title>
+                            <circle cx=x cy=y r="12" fill=fill stroke="black" />
+                            <text x=x y=y text-anchor="middle" dominant-baseline="central" font-size="10">{ &node.label }</text>
+                        </g>
+                    )
+                }) }
+            </svg>
+        )
+    }
+}
+
+impl Component for GraphPanel {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        Self { props, link }
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        let result = match msg {
+            Msg::ExportDot => {
+                download::download("rooms.dot", "text/vnd.graphviz", &self.props.graph.to_dot())
+            }
+            Msg::ExportJson => match self.props.graph.to_json() {
+                Ok(json) => download::download("rooms.json", "application/json", &json),
+                Err(err) => Err(err.to_string().into()),
+            },
+        };
+
+        if let Err(err) = result {
+            log::error!("Failed to export room graph: {:?}", err);
+        }
+
+        false
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.props = props;
+        true
+    }
+
+    fn view(&self) -> Html {
+        let graph = &self.props.graph;
+
+        html!(
+            <fieldset>
+                <legend>{"Room Graph"}</legend>
+                { self.diagram() }
+                <p>{ format!("Loops: {}, dead ends (yellow): {}, choke points (red): {}", graph.loops(), graph.dead_ends().len(), graph.choke_points().len()) }</p>
+                <button onclick=self.link.callback(|_| Msg::ExportDot)>{"Export DOT"}</button>
+                <button onclick=self.link.callback(|_| Msg::ExportJson)>{"Export JSON"}</button>
+            </fieldset>
+        )
+    }
+}
@@ -4,7 +4,7 @@ use std::fmt::Debug;
 
 use crate::list::List;
 
-#[derive(Clone)]
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct Grid<T> {
     rows: usize,
     cols: usize,
@@ -33,9 +33,51 @@ impl<T> Grid<T> {
         })
     }
 
+    /// Whether the grid really has `rows` rows of `cols` cells, as grids loaded from a file might not
+    pub fn is_consistent(&self) -> bool {
+        self.cells.len() == self.rows && self.cells.iter().all(|row| row.len() == self.cols)
+    }
+
+    /// Whether the grid has the same number of rows and columns as `other`
+    pub fn same_size<U>(&self, other: &Grid<U>) -> bool {
+        self.rows == other.rows && self.cols == other.cols
+    }
+
     pub fn get(&self, row: usize, col: usize) -> Option<&T> {
         self.cells.get(row).and_then(|c| c.get(col))
     }
+
+    pub fn map<U>(&self, f: impl Fn(&T) -> U) -> Grid<U> {
+        Grid {
+            rows: self.rows,
+            cols: self.cols,
+            cells: self
+                .cells
+                .iter()
+                .map(|row| row.iter().map(&f).collect())
+                .collect(),
+        }
+    }
+
+    /// Like [`Grid::map`], but also passing each cell's row and column
+    pub fn map_indexed<U>(&self, f: impl Fn(usize, usize, &T) -> U) -> Grid<U> {
+        Grid {
+            rows: self.rows,
+            cols: self.cols,
+            cells: self
+                .cells
+                .iter()
+                .enumerate()
+                .map(|(row, cells)| {
+                    cells
+                        .iter()
+                        .enumerate()
+                        .map(|(col, cell)| f(row, col, cell))
+                        .collect()
+                })
+                .collect(),
+        }
+    }
 }
 
 impl<T: Clone + PartialEq> Grid<T> {
@@ -56,6 +98,25 @@ impl<T: Clone + PartialEq> Grid<T> {
     }
 }
 
+impl<T: Clone> Grid<T> {
+    /// The `rows` by `cols` part of the grid with its top left corner at `top`, `left`, cut short at the grid's edges
+    pub fn crop(&self, top: usize, left: usize, rows: usize, cols: usize) -> Self {
+        let cells: List<List<T>> = self
+            .cells
+            .iter()
+            .skip(top)
+            .take(rows)
+            .map(|row| row.iter().skip(left).take(cols).cloned().collect())
+            .collect();
+
+        Self {
+            rows: cells.len(),
+            cols: cells.get(0).map_or(0, |row| row.len()),
+            cells,
+        }
+    }
+}
+
 impl<T: Default> Grid<T> {
     pub fn with_dimensions(rows: usize, cols: usize) -> Self {
         Self {
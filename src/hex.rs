@@ -0,0 +1,339 @@
+//! Hexagonal grids. A hex map keeps its cells in the same rectangular `Grid`, read as offset coordinates: rows of
+//! pointy-top hexes with every odd row pushed right by half a hex, or columns of flat-top hexes with every odd column
+//! pushed down. Neighbours and distances are worked out in axial coordinates.
+
+use std::convert::TryFrom;
+
+/// The distance from the centre of a hex to a corner, for hexes one unit across their flat sides
+const RADIUS: f64 = 0.577_350_269_189_625_8;
+
+/// The steps to a square's neighbours: up, right, down and left
+const SQUARE_STEPS: [(isize, isize); 4] = [(-1, 0), (0, 1), (1, 0), (0, -1)];
+
+/// The six axial steps to a hex's neighbours
+const STEPS: [(isize, isize); 6] = [(1, 0), (1, -1), (0, -1), (-1, 0), (-1, 1), (0, 1)];
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum Orientation {
+    PointyTop,
+    FlatTop,
+}
+
+/// The shape of the map's cells
+#[derive(Copy, Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum GridShape {
+    Square,
+    Hex(Orientation),
+}
+
+impl GridShape {
+    pub const ALL: [GridShape; 3] = [
+        GridShape::Square,
+        GridShape::Hex(Orientation::PointyTop),
+        GridShape::Hex(Orientation::FlatTop),
+    ];
+
+    /// Where a point given in square cells, as laid out in the grid, is drawn. Cell centres land on hex centres.
+    pub fn point(&self, x: f64, y: f64) -> (f64, f64) {
+        let odd = |value: f64| value.max(0.0) as usize % 2 == 1;
+        match self {
+            GridShape::Square => (x, y),
+            GridShape::Hex(Orientation::PointyTop) => (
+                x + if odd(y) { 0.5 } else { 0.0 },
+                y * 1.5 * RADIUS + 0.25 * RADIUS,
+            ),
+            GridShape::Hex(Orientation::FlatTop) => (
+                x * 1.5 * RADIUS + 0.25 * RADIUS,
+                y + if odd(x) { 0.5 } else { 0.0 },
+            ),
+        }
+    }
+
+    pub fn centre(&self, (row, col): (usize, usize)) -> (f64, f64) {
+        self.point(col as f64 + 0.5, row as f64 + 0.5)
+    }
+
+    /// The (row, col) of the cell under a drawn point
+    pub fn cell_at(&self, x: f64, y: f64) -> Option<(usize, usize)> {
+        match self {
+            GridShape::Square if x >= 0.0 && y >= 0.0 => Some((y as usize, x as usize)),
+            GridShape::Square => None,
+            GridShape::Hex(orientation) => orientation.cell_at(x, y),
+        }
+    }
+
+    /// How many sides a cell has, and so how many directions lead from it
+    pub fn directions(&self) -> usize {
+        match self {
+            GridShape::Square => SQUARE_STEPS.len(),
+            GridShape::Hex(_) => STEPS.len(),
+        }
+    }
+
+    /// The cell across one side of a cell, numbering the sides from 0 to `directions()`. Squares are numbered up,
+    /// right, down and left. `None` if the cell would be above or left of the grid, but it may lie beyond the bottom
+    /// or right.
+    pub fn neighbour(
+        &self,
+        (row, col): (usize, usize),
+        direction: usize,
+    ) -> Option<(usize, usize)> {
+        match self {
+            GridShape::Square => {
+                let (dr, dc) = SQUARE_STEPS.get(direction)?;
+                Some((row.checked_add_signed(*dr)?, col.checked_add_signed(*dc)?))
+            }
+            GridShape::Hex(orientation) => orientation.neighbour((row, col), direction),
+        }
+    }
+
+    /// The cells sharing a side with a cell. Some may lie beyond the bottom or right of the grid.
+    pub fn neighbours(&self, cell: (usize, usize)) -> Vec<(usize, usize)> {
+        (0..self.directions())
+            .filter_map(|direction| self.neighbour(cell, direction))
+            .collect()
+    }
+
+    /// The width and height of a grid of cells when drawn
+    pub fn size(&self, rows: usize, cols: usize) -> (f64, f64) {
+        let (rows, cols) = (rows as f64, cols as f64);
+        match self {
+            GridShape::Square => (cols, rows),
+            GridShape::Hex(Orientation::PointyTop) => (cols + 0.5, (rows * 1.5 + 0.5) * RADIUS),
+            GridShape::Hex(Orientation::FlatTop) => ((cols * 1.5 + 0.5) * RADIUS, rows + 0.5),
+        }
+    }
+
+    /// The drawn bounding box (x, y, width, height) of a block of cells
+    pub fn bounds(
+        &self,
+        top: usize,
+        left: usize,
+        rows: usize,
+        cols: usize,
+    ) -> (f64, f64, f64, f64) {
+        let (half_width, half_height) = match self {
+            GridShape::Square => (0.5, 0.5),
+            GridShape::Hex(Orientation::PointyTop) => (0.5, RADIUS),
+            GridShape::Hex(Orientation::FlatTop) => (RADIUS, 0.5),
+        };
+        // The outermost hexes of a block may be in any of its border rows or columns, depending on the offsets
+        let border = (top..top + rows)
+            .flat_map(|row| vec![(row, left), (row, left + cols - 1)])
+            .chain((left..left + cols).flat_map(|col| vec![(top, col), (top + rows - 1, col)]));
+        let (mut min_x, mut min_y, mut max_x, mut max_y) = (f64::MAX, f64::MAX, f64::MIN, f64::MIN);
+        for cell in border {
+            let (x, y) = self.centre(cell);
+            min_x = min_x.min(x - half_width);
+            min_y = min_y.min(y - half_height);
+            max_x = max_x.max(x + half_width);
+            max_y = max_y.max(y + half_height);
+        }
+        (min_x, min_y, max_x - min_x, max_y - min_y)
+    }
+
+    /// The corners of a hex cell as SVG polygon points, or `None` for a square cell, which is drawn as a rect
+    pub fn polygon(&self, cell: (usize, usize)) -> Option<String> {
+        match self {
+            GridShape::Square => None,
+            GridShape::Hex(orientation) => {
+                let (x, y) = self.centre(cell);
+                let first = match orientation {
+                    Orientation::PointyTop => 30.0f64,
+                    Orientation::FlatTop => 0.0,
+                };
+                let points: Vec<_> = (0..6)
+                    .map(|corner| {
+                        let angle = (first + 60.0 * corner as f64).to_radians();
+                        format!("{},{}", x + RADIUS * angle.cos(), y + RADIUS * angle.sin())
+                    })
+                    .collect();
+                Some(points.join(" "))
+            }
+        }
+    }
+
+    /// What one cell is called when counting distances
+    pub fn cell_name(&self) -> &'static str {
+        match self {
+            GridShape::Square => "squares",
+            GridShape::Hex(_) => "hexes",
+        }
+    }
+}
+
+impl std::fmt::Display for GridShape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            GridShape::Square => "Squares",
+            GridShape::Hex(Orientation::PointyTop) => "Hexes, pointy top",
+            GridShape::Hex(Orientation::FlatTop) => "Hexes, flat top",
+        })
+    }
+}
+
+/// Axial hex coordinates: `q` runs along the rows of pointy-top hexes or down the columns of flat-top ones, and `r`
+/// runs diagonally across them
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Axial {
+    pub q: isize,
+    pub r: isize,
+}
+
+impl Axial {
+    pub fn distance(&self, other: Axial) -> usize {
+        let (dq, dr) = (self.q - other.q, self.r - other.r);
+        (dq.unsigned_abs() + dr.unsigned_abs() + (dq + dr).unsigned_abs()) / 2
+    }
+
+    /// The nearest hex to fractional axial coordinates
+    fn round(q: f64, r: f64) -> Self {
+        let s = -q - r;
+        let (mut rq, mut rr, rs) = (q.round(), r.round(), s.round());
+        let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+        if dq > dr && dq > ds {
+            rq = -rr - rs;
+        } else if dr > ds {
+            rr = -rq - rs;
+        }
+        Self {
+            q: rq as isize,
+            r: rr as isize,
+        }
+    }
+}
+
+impl Orientation {
+    /// Axial coordinates of the cell at (row, col) in offset coordinates
+    pub fn axial(&self, (row, col): (usize, usize)) -> Axial {
+        let (row, col) = (row as isize, col as isize);
+        match self {
+            Orientation::PointyTop => Axial {
+                q: col - (row - (row & 1)) / 2,
+                r: row,
+            },
+            Orientation::FlatTop => Axial {
+                q: col,
+                r: row - (col - (col & 1)) / 2,
+            },
+        }
+    }
+
+    /// Offset (row, col) coordinates of a hex, if it's not above or left of the grid
+    pub fn offset(&self, hex: Axial) -> Option<(usize, usize)> {
+        let (row, col) = match self {
+            Orientation::PointyTop => (hex.r, hex.q + (hex.r - (hex.r & 1)) / 2),
+            Orientation::FlatTop => (hex.r + (hex.q - (hex.q & 1)) / 2, hex.q),
+        };
+        Some((usize::try_from(row).ok()?, usize::try_from(col).ok()?))
+    }
+
+    pub fn neighbour(&self, cell: (usize, usize), direction: usize) -> Option<(usize, usize)> {
+        let hex = self.axial(cell);
+        let (dq, dr) = STEPS.get(direction)?;
+        self.offset(Axial {
+            q: hex.q + dq,
+            r: hex.r + dr,
+        })
+    }
+
+    pub fn neighbours(&self, cell: (usize, usize)) -> Vec<(usize, usize)> {
+        (0..STEPS.len())
+            .filter_map(|direction| self.neighbour(cell, direction))
+            .collect()
+    }
+
+    /// The number of steps between two cells
+    pub fn distance(&self, from: (usize, usize), to: (usize, usize)) -> usize {
+        self.axial(from).distance(self.axial(to))
+    }
+
+    fn cell_at(&self, x: f64, y: f64) -> Option<(usize, usize)> {
+        // Relative to the centre of the first hex, in radii
+        let (x, y) = match self {
+            Orientation::PointyTop => ((x - 0.5) / RADIUS, y / RADIUS - 1.0),
+            Orientation::FlatTop => (x / RADIUS - 1.0, (y - 0.5) / RADIUS),
+        };
+        let sqrt_3 = 3.0f64.sqrt();
+        let hex = match self {
+            Orientation::PointyTop => Axial::round(sqrt_3 / 3.0 * x - y / 3.0, 2.0 / 3.0 * y),
+            Orientation::FlatTop => Axial::round(2.0 / 3.0 * x, -x / 3.0 + sqrt_3 / 3.0 * y),
+        };
+        self.offset(hex)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORIENTATIONS: [Orientation; 2] = [Orientation::PointyTop, Orientation::FlatTop];
+
+    #[test]
+    fn offset_and_axial_coordinates_round_trip() {
+        for orientation in &ORIENTATIONS {
+            for row in 0..6 {
+                for col in 0..6 {
+                    let hex = orientation.axial((row, col));
+                    assert_eq!(orientation.offset(hex), Some((row, col)));
+                }
+            }
+        }
+
+        assert_eq!(Orientation::PointyTop.axial((3, 2)), Axial { q: 1, r: 3 });
+        assert_eq!(Orientation::FlatTop.axial((2, 3)), Axial { q: 3, r: 1 });
+    }
+
+    #[test]
+    fn hexes_have_six_neighbours_one_step_away() {
+        for orientation in &ORIENTATIONS {
+            let neighbours = orientation.neighbours((3, 3));
+            assert_eq!(neighbours.len(), 6);
+            assert!(neighbours
+                .iter()
+                .all(|cell| orientation.distance((3, 3), *cell) == 1));
+        }
+
+        // Odd rows of pointy-top hexes are pushed right, so they reach up and down to the right
+        let mut neighbours = Orientation::PointyTop.neighbours((1, 1));
+        neighbours.sort();
+        assert_eq!(
+            neighbours,
+            vec![(0, 1), (0, 2), (1, 0), (1, 2), (2, 1), (2, 2)]
+        );
+
+        assert_eq!(Orientation::PointyTop.neighbours((0, 0)).len(), 2);
+    }
+
+    #[test]
+    fn distances_count_steps_between_hexes() {
+        assert_eq!(Orientation::PointyTop.distance((0, 0), (0, 5)), 5);
+        assert_eq!(Orientation::PointyTop.distance((0, 0), (4, 0)), 4);
+        assert_eq!(Orientation::PointyTop.distance((0, 0), (4, 4)), 6);
+        assert_eq!(Orientation::FlatTop.distance((0, 0), (0, 4)), 4);
+        assert_eq!(Orientation::FlatTop.distance((0, 0), (4, 0)), 4);
+    }
+
+    #[test]
+    fn points_map_back_to_the_hexes_drawn_there() {
+        for shape in &GridShape::ALL {
+            for row in 0..5 {
+                for col in 0..5 {
+                    let (x, y) = shape.centre((row, col));
+                    assert_eq!(shape.cell_at(x, y), Some((row, col)), "{}", shape);
+
+                    // Just inside a corner is still the same cell
+                    let corner = shape.polygon((row, col)).map(|points| {
+                        let first = points.split(' ').next().unwrap().to_string();
+                        let mut coords = first.split(',').map(|c| c.parse::<f64>().unwrap());
+                        (coords.next().unwrap(), coords.next().unwrap())
+                    });
+                    if let Some((corner_x, corner_y)) = corner {
+                        let inside = (x + (corner_x - x) * 0.9, y + (corner_y - y) * 0.9);
+                        assert_eq!(shape.cell_at(inside.0, inside.1), Some((row, col)));
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,18 @@
+use yew::prelude::*;
+
+/// The new value of an `<input>`, `<textarea>` or `<select>` from its change event
+pub fn change_value(data: ChangeData) -> String {
+    match data {
+        ChangeData::Value(value) => value,
+        ChangeData::Select(element) => element.value(),
+        ChangeData::Files(_) => String::new(),
+    }
+}
+
+/// The first file chosen in an `<input type="file">` from its change event
+pub fn change_file(data: ChangeData) -> Option<web_sys::File> {
+    match data {
+        ChangeData::Files(files) => files.get(0),
+        _ => None,
+    }
+}
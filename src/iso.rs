@@ -0,0 +1,222 @@
+//! Isometric projection of the square map. The floor is drawn exactly as in the plan view and skewed into a diamond,
+//! and walls and doors stand up from their edges as flat faces, drawn from the back of the map to the front.
+
+use crate::edges::{Edge, EdgeId, Edges, Side};
+use crate::Cells;
+
+/// How the isometric view is shown
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct IsoSettings {
+    pub show: bool,
+    /// Height of the walls, in cells
+    pub wall_height: f64,
+}
+
+impl Default for IsoSettings {
+    fn default() -> Self {
+        Self {
+            show: false,
+            wall_height: 1.5,
+        }
+    }
+}
+
+/// Maps points on the grid, in cells, to and from the drawing. A cell's width becomes one unit to the right and half
+/// a unit down, and its height one unit to the left and half a unit down, with the top corner of the grid at the
+/// top middle.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Projection {
+    rows: usize,
+    cols: usize,
+    wall_height: f64,
+}
+
+impl Projection {
+    pub fn new(rows: usize, cols: usize, settings: &IsoSettings) -> Self {
+        Self {
+            rows,
+            cols,
+            wall_height: settings.wall_height.max(0.0),
+        }
+    }
+
+    /// Where the point (x, y) on the grid, raised by `z`, is drawn
+    pub fn project(&self, x: f64, y: f64, z: f64) -> (f64, f64) {
+        (
+            x - y + self.rows as f64,
+            (x + y) / 2.0 - z + self.wall_height,
+        )
+    }
+
+    /// The point on the floor drawn at (x, y)
+    pub fn unproject(&self, x: f64, y: f64) -> (f64, f64) {
+        let across = x - self.rows as f64;
+        let down = (y - self.wall_height) * 2.0;
+        ((down + across) / 2.0, (down - across) / 2.0)
+    }
+
+    /// The width and height of the drawing
+    pub fn size(&self) -> (f64, f64) {
+        let span = (self.rows + self.cols) as f64;
+        (span, span / 2.0 + self.wall_height)
+    }
+
+    /// An SVG transform that draws the floor, as laid out in cells, in projection
+    pub fn floor_transform(&self) -> String {
+        format!("matrix(1 0.5 -1 0.5 {} {})", self.rows, self.wall_height)
+    }
+}
+
+/// One upright face of a wall or door
+#[derive(Clone, Debug, PartialEq)]
+pub struct Face {
+    pub edge: EdgeId,
+    /// SVG polygon points
+    pub points: String,
+    pub fill: &'static str,
+}
+
+/// The faces to draw, back to front. Walls stand on every wall edge and wherever a room meets rock or the edge of
+/// the map; doors stand in their walls.
+pub fn faces(cells: &Cells, edges: &Edges, projection: &Projection) -> Vec<Face> {
+    let (rows, cols) = (cells.rows(), cells.cols());
+    let is_room = |(row, col): (usize, usize)| cells.get(row, col).is_some_and(Option::is_some);
+
+    let tops = (0..=rows).flat_map(|row| (0..cols).map(move |col| EdgeId::top(row, col)));
+    let lefts = (0..rows).flat_map(|row| (0..=cols).map(move |col| EdgeId::left(row, col)));
+
+    let mut standing: Vec<_> = tops
+        .chain(lefts)
+        .filter_map(|id| {
+            let (before, after) = id.cells();
+            let boundary = before.is_some_and(is_room) != is_room(after);
+            match edges.get(id) {
+                Some(edge) => Some((id, edge)),
+                None if boundary => Some((id, Edge::Wall)),
+                None => None,
+            }
+        })
+        .collect();
+
+    // Faces further back have smaller x + y, and are covered by those in front
+    let depth = |id: &EdgeId| {
+        let ((x1, y1), (x2, y2)) = id.line();
+        x1 + y1 + x2 + y2
+    };
+    standing.sort_by_key(|(id, _)| depth(id));
+
+    standing
+        .into_iter()
+        .map(|(id, edge)| {
+            let ((x1, y1), (x2, y2)) = id.line();
+            let corners = [
+                projection.project(x1 as f64, y1 as f64, 0.0),
+                projection.project(x2 as f64, y2 as f64, 0.0),
+                projection.project(x2 as f64, y2 as f64, projection.wall_height),
+                projection.project(x1 as f64, y1 as f64, projection.wall_height),
+            ];
+            let points: Vec<_> = corners
+                .iter()
+                .map(|(x, y)| format!("{},{}", x, y))
+                .collect();
+
+            // Walls running across the grid are shaded paler than those running down it
+            let fill = match (edge, id.side) {
+                (Edge::Wall, Side::Top) => "#b0aca4",
+                (Edge::Wall, Side::Left) => "#8a867f",
+                (Edge::Door, _) => "saddlebrown",
+                (Edge::SecretDoor, _) => "plum",
+            };
+
+            Face {
+                edge: id,
+                points: points.join(" "),
+                fill,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Grid;
+    use crate::room::{RoomId, Rooms};
+
+    fn single_room(rows: usize, cols: usize, cells: &[(usize, usize)]) -> Cells {
+        let material = crate::test_support::materials().as_ref()[0].clone();
+        let (_, id) = Rooms::new().push(crate::room::Room::new(material, "1".into()));
+        Grid::with_dimensions(rows, cols).map_indexed(|row, col, _: &Option<RoomId>| {
+            Some(id).filter(|_| cells.contains(&(row, col)))
+        })
+    }
+
+    #[test]
+    fn picking_undoes_the_projection() {
+        let projection = Projection::new(7, 4, &IsoSettings::default());
+        for &(x, y) in &[(0.0, 0.0), (3.5, 2.25), (4.0, 7.0)] {
+            let (sx, sy) = projection.project(x, y, 0.0);
+            let (ux, uy) = projection.unproject(sx, sy);
+            assert!((ux - x).abs() < 1e-9 && (uy - y).abs() < 1e-9);
+        }
+
+        // The whole floor fits in the drawing
+        let (width, height) = projection.size();
+        for &(x, y) in &[(0.0, 0.0), (4.0, 0.0), (0.0, 7.0), (4.0, 7.0)] {
+            let (sx, sy) = projection.project(x, y, 0.0);
+            assert!(sx >= 0.0 && sx <= width && sy >= 0.0 && sy <= height);
+        }
+        assert_eq!(projection.project(0.0, 0.0, 1.5).1, 0.0);
+    }
+
+    #[test]
+    fn rooms_are_walled_where_they_meet_rock() {
+        let cells = single_room(3, 3, &[(1, 1)]);
+        let faces = faces(
+            &cells,
+            &Edges::new(),
+            &Projection::new(3, 3, &IsoSettings::default()),
+        );
+
+        let mut edges: Vec<_> = faces.iter().map(|face| face.edge).collect();
+        edges.sort();
+        let mut expected = vec![
+            EdgeId::top(1, 1),
+            EdgeId::bottom(1, 1),
+            EdgeId::left(1, 1),
+            EdgeId::right(1, 1),
+        ];
+        expected.sort();
+        assert_eq!(edges, expected);
+    }
+
+    #[test]
+    fn doors_stand_in_walls_and_faces_go_back_to_front() {
+        let cells = single_room(2, 3, &[(0, 0), (0, 1), (0, 2), (1, 0), (1, 1), (1, 2)]);
+        let edges = Edges::new()
+            .set(EdgeId::left(0, 1), Some(Edge::Door))
+            .set(EdgeId::left(1, 1), Some(Edge::Wall));
+        let faces = faces(
+            &cells,
+            &edges,
+            &Projection::new(2, 3, &IsoSettings::default()),
+        );
+
+        // The outline of the map, plus the door and wall inside it
+        assert_eq!(faces.len(), 2 * 3 + 2 * 2 + 2);
+        let door = faces
+            .iter()
+            .find(|face| face.edge == EdgeId::left(0, 1))
+            .unwrap();
+        assert_eq!(door.fill, "saddlebrown");
+
+        let depths: Vec<_> = faces
+            .iter()
+            .map(|face| {
+                let ((x1, y1), (x2, y2)) = face.edge.line();
+                x1 + y1 + x2 + y2
+            })
+            .collect();
+        assert!(depths.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+}
@@ -0,0 +1,77 @@
+use yew::prelude::*;
+
+use crate::input::change_value;
+use crate::iso::IsoSettings;
+
+pub struct IsoPanel {
+    props: Props,
+    link: ComponentLink<Self>,
+}
+
+pub enum Msg {
+    Show(bool),
+    WallHeight(String),
+}
+
+#[derive(Clone, Properties)]
+pub struct Props {
+    pub settings: IsoSettings,
+    pub settings_changed: Callback<IsoSettings>,
+    /// Whether the map is on a square grid, which is all the isometric view can draw
+    pub square: bool,
+    pub export: Callback<()>,
+}
+
+fn height(value: &str) -> Option<f64> {
+    value.parse().ok().filter(|value: &f64| *value >= 0.0)
+}
+
+impl Component for IsoPanel {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        Self { props, link }
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        let mut settings = self.props.settings;
+
+        match msg {
+            Msg::Show(show) => settings.show = show,
+            Msg::WallHeight(value) => match height(&value) {
+                Some(wall_height) => settings.wall_height = wall_height,
+                None => return true,
+            },
+        }
+
+        self.props.settings_changed.emit(settings);
+
+        false
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.props = props;
+        true
+    }
+
+    fn view(&self) -> Html {
+        let settings = self.props.settings;
+        let square = self.props.square;
+
+        html!(
+            <fieldset>
+                <legend>{"Isometric"}</legend>
+                <label class="inline">
+                    <input type="checkbox" checked=settings.show && square disabled=!square onclick=self.link.callback(move |_| Msg::Show(!settings.show)) />
+                    {"Isometric View"}
+                </label>
+                <label>
+                    {"Wall height, in squares "}
+                    <input type="number" min="0" step="0.25" value=settings.wall_height.to_string() onchange=self.link.callback(|data| Msg::WallHeight(change_value(data))) />
+                </label>
+                <button disabled=!square onclick=self.props.export.reform(|_| ())>{"Export Isometric SVG"}</button>
+            </fieldset>
+        )
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::hex::GridShape;
+use crate::room::{RoomId, Rooms};
+use crate::Cells;
+
+pub struct Label {
+    pub text: String,
+    /// Centre of the label, in cells
+    pub x: f64,
+    pub y: f64,
+}
+
+/// How many steps each cell is from the nearest cell that belongs to a different room or to no room
+fn distance_to_boundary(cells: &Cells, shape: GridShape) -> HashMap<(usize, usize), usize> {
+    let mut distances = HashMap::new();
+    let mut queue = VecDeque::new();
+
+    for (row, col, cell) in cells.iter() {
+        if cell.is_none() {
+            continue;
+        }
+
+        // Cells along the top or left of the map have fewer neighbours, and are on the boundary too
+        let neighbours = shape.neighbours((row, col));
+        let on_boundary = neighbours.len() < shape.directions()
+            || neighbours
+                .iter()
+                .any(|&(row, col)| cells.get(row, col) != Some(cell));
+
+        if on_boundary {
+            distances.insert((row, col), 1);
+            queue.push_back((row, col));
+        }
+    }
+
+    while let Some((row, col)) = queue.pop_front() {
+        let distance = distances[&(row, col)];
+        let cell = cells.get(row, col);
+
+        for (row, col) in shape.neighbours((row, col)) {
+            if cells.get(row, col) == cell && !distances.contains_key(&(row, col)) {
+                distances.insert((row, col), distance + 1);
+                queue.push_back((row, col));
+            }
+        }
+    }
+
+    distances
+}
+
+/// Place one label per painted room at its most interior cell, preferring cells near the room's centroid.
+pub fn labels(cells: &Cells, rooms: &Rooms, shape: GridShape) -> Vec<Label> {
+    let distances = distance_to_boundary(cells, shape);
+
+    let mut sums: HashMap<RoomId, (f64, f64, f64)> = HashMap::new();
+    for (row, col, cell) in cells.iter() {
+        if let Some(room) = cell {
+            let sum = sums.entry(*room).or_insert((0.0, 0.0, 0.0));
+            sum.0 += col as f64 + 0.5;
+            sum.1 += row as f64 + 0.5;
+            sum.2 += 1.0;
+        }
+    }
+
+    let mut best: HashMap<RoomId, (usize, f64, usize, usize)> = HashMap::new();
+    for (row, col, cell) in cells.iter() {
+        if let Some(room) = cell {
+            let (sum_x, sum_y, count) = sums[room];
+            let dx = col as f64 + 0.5 - sum_x / count;
+            let dy = row as f64 + 0.5 - sum_y / count;
+            let centroid_distance = dx * dx + dy * dy;
+            let distance = distances.get(&(row, col)).copied().unwrap_or(0);
+
+            let better = match best.get(room) {
+                Some(&(best_distance, best_centroid_distance, _, _)) => {
+                    distance > best_distance
+                        || (distance == best_distance && centroid_distance < best_centroid_distance)
+                }
+                None => true,
+            };
+
+            if better {
+                best.insert(*room, (distance, centroid_distance, row, col));
+            }
+        }
+    }
+
+    rooms
+        .iter()
+        .filter_map(|(id, room)| {
+            let &(_, _, row, col) = best.get(&id)?;
+            let text = if room.label.is_empty() {
+                room.name.clone()
+            } else {
+                room.label.clone()
+            };
+
+            if text.is_empty() {
+                return None;
+            }
+
+            Some(Label {
+                text,
+                x: col as f64 + 0.5,
+                y: row as f64 + 0.5,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,117 @@
+//! The floors of a map, and the stairs, ladders and trapdoors between them.
+
+use crate::edges::Edges;
+use crate::fog::Fog;
+use crate::grid::Grid;
+use crate::lights::Lights;
+use crate::list::List;
+use crate::tokens::Tokens;
+use crate::Cells;
+
+/// Everything drawn on one floor. Rooms are shared by the whole map, so a room may spread across several levels.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Level {
+    pub name: String,
+    pub cells: Cells,
+    pub edges: Edges,
+    pub tokens: Tokens,
+    pub fog: Fog,
+    pub lights: Lights,
+}
+
+impl Level {
+    pub fn new(name: String, rows: usize, cols: usize) -> Self {
+        Self {
+            name,
+            cells: Grid::with_dimensions(rows, cols),
+            edges: Edges::new(),
+            tokens: Tokens::new(),
+            fog: Grid::with_dimensions(rows, cols),
+            lights: Lights::new(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum StairKind {
+    Stairs,
+    Ladder,
+    Trapdoor,
+}
+
+impl StairKind {
+    pub const ALL: [StairKind; 3] = [StairKind::Stairs, StairKind::Ladder, StairKind::Trapdoor];
+
+    /// The mark drawn on the map at either end
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            StairKind::Stairs => "≡",
+            StairKind::Ladder => "H",
+            StairKind::Trapdoor => "⊠",
+        }
+    }
+}
+
+impl std::fmt::Display for StairKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            StairKind::Stairs => "Stairs",
+            StairKind::Ladder => "Ladder",
+            StairKind::Trapdoor => "Trapdoor",
+        })
+    }
+}
+
+/// A cell on a level, with levels numbered from the top
+#[derive(
+    Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
+)]
+pub struct Place {
+    pub level: usize,
+    pub row: usize,
+    pub col: usize,
+}
+
+impl Place {
+    /// The place once level `removed` has gone, or `None` if it was on that level
+    pub fn without_level(&self, removed: usize) -> Option<Self> {
+        match self.level {
+            level if level == removed => None,
+            level if level > removed => Some(Self {
+                level: level - 1,
+                ..*self
+            }),
+            _ => Some(*self),
+        }
+    }
+}
+
+/// A way between a cell on one level and a cell on another, which can be taken in either direction
+#[derive(Copy, Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Stair {
+    pub kind: StairKind,
+    pub from: Place,
+    pub to: Place,
+}
+
+impl Stair {
+    /// The end of the stair on a level, if it has one there, and the place it leads to
+    pub fn ends_on(&self, level: usize) -> Vec<(Place, Place)> {
+        [(self.from, self.to), (self.to, self.from)]
+            .iter()
+            .copied()
+            .filter(|(end, _)| end.level == level)
+            .collect()
+    }
+
+    /// The stair once level `removed` has gone, or `None` if it led there
+    pub fn without_level(&self, removed: usize) -> Option<Self> {
+        Some(Self {
+            from: self.from.without_level(removed)?,
+            to: self.to.without_level(removed)?,
+            ..*self
+        })
+    }
+}
+
+pub type Stairs = List<Stair>;
@@ -0,0 +1,133 @@
+use yew::prelude::*;
+
+use crate::input::change_value;
+use crate::levels::{Level, Place, StairKind, Stairs};
+use crate::list::List;
+
+pub struct LevelsPanel {
+    props: Props,
+    link: ComponentLink<Self>,
+}
+
+pub enum Msg {
+    StairKind(String),
+}
+
+#[derive(Clone, Properties)]
+pub struct Props {
+    pub levels: List<Level>,
+    pub current: usize,
+    pub level_selected: Callback<usize>,
+    pub level_added: Callback<()>,
+    pub level_removed: Callback<usize>,
+    pub level_renamed: Callback<(usize, String)>,
+    pub show_below: bool,
+    pub show_below_changed: Callback<bool>,
+    pub stairs: Stairs,
+    pub stair_removed: Callback<usize>,
+    pub stair_kind: StairKind,
+    pub stair_kind_changed: Callback<StairKind>,
+    /// The first end of the stair being placed, once it's been clicked
+    pub stair_from: Option<Place>,
+    pub export: Callback<()>,
+}
+
+impl LevelsPanel {
+    fn level_name(&self, index: usize) -> String {
+        self.props
+            .levels
+            .get(index)
+            .map(|level| level.name.clone())
+            .unwrap_or_default()
+    }
+
+    fn place(&self, place: Place) -> String {
+        format!(
+            "{}, row {}, column {}",
+            self.level_name(place.level),
+            place.row + 1,
+            place.col + 1
+        )
+    }
+}
+
+impl Component for LevelsPanel {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        Self { props, link }
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        match msg {
+            Msg::StairKind(kind) => {
+                if let Some(kind) = StairKind::ALL.iter().find(|k| k.to_string() == kind) {
+                    self.props.stair_kind_changed.emit(*kind);
+                }
+            }
+        }
+
+        false
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.props = props;
+        true
+    }
+
+    fn view(&self) -> Html {
+        let show_below = self.props.show_below;
+        let single = self.props.levels.len() <= 1;
+        let status = match self.props.stair_from {
+            Some(from) => format!(
+                "From {}: click where it leads on another level",
+                self.place(from)
+            ),
+            None => String::from("With the Stairs tool, click one end and then the other"),
+        };
+
+        html!(
+            <fieldset>
+                <legend>{"Levels"}</legend>
+                <table>
+                    { for self.props.levels.iter().enumerate().map(|(index, level)| {
+                        let class = if index == self.props.current { Some("selected") } else { None };
+                        html!(
+                            <tr>
+                                <td><button class=class onclick=self.props.level_selected.reform(move |_| index)>{"Show"}</button></td>
+                                <td><input type="text" value=level.name.clone() onchange=self.props.level_renamed.reform(move |data| (index, change_value(data))) /></td>
+                                <td><button disabled=single onclick=self.props.level_removed.reform(move |_| index)>{"Remove"}</button></td>
+                            </tr>
+                        )
+                    }) }
+                </table>
+                <button onclick=self.props.level_added.reform(|_| ())>{"Add Level"}</button>
+                <button onclick=self.props.export.reform(|_| ())>{"Export Each Level"}</button>
+                <label class="inline">
+                    <input type="checkbox" checked=show_below onclick=self.props.show_below_changed.reform(move |_| !show_below) />
+                    {"Show the level below"}
+                </label>
+                <label>
+                    {"New stairs are "}
+                    <select onchange=self.link.callback(|data| Msg::StairKind(change_value(data)))>
+                        { for StairKind::ALL.iter().map(|kind| html!(
+                            <option selected=*kind == self.props.stair_kind>{ kind.to_string() }</option>
+                        )) }
+                    </select>
+                </label>
+                <p>{ status }</p>
+                <table>
+                    { for self.props.stairs.iter().enumerate().map(|(index, stair)| html!(
+                        <tr>
+                            <td>{ stair.kind.to_string() }</td>
+                            <td>{ self.place(stair.from) }</td>
+                            <td>{ self.place(stair.to) }</td>
+                            <td><button onclick=self.props.stair_removed.reform(move |_| index)>{"Remove"}</button></td>
+                        </tr>
+                    )) }
+                </table>
+            </fieldset>
+        )
+    }
+}
@@ -1,48 +1,221 @@
-#![recursion_limit = "1024"]
+#![recursion_limit = "2048"]
 
 use std::convert::TryFrom;
 use wasm_bindgen::prelude::*;
 use yew::prelude::*;
+use yew::services::reader::{FileData, ReaderService, ReaderTask};
 
+mod adjacency;
+mod booklet;
+mod bsp;
+mod campaign;
+mod campaign_panel;
+mod caves;
+mod corridor;
+mod corridor_panel;
+mod document;
+mod download;
+mod edges;
+mod export;
+mod fog;
+mod fog_panel;
+mod generate_dialog;
+mod graph_panel;
 mod grid;
+mod hex;
+mod input;
+mod iso;
+mod iso_panel;
+mod labels;
+mod levels;
+mod levels_panel;
+mod lights;
+mod lights_panel;
+mod lint;
 mod list;
+mod markdown;
+mod maze;
+mod maze_panel;
+mod measure;
+mod problems_panel;
+mod rng;
 mod room;
+mod room_graph;
 mod room_manager;
+mod scale;
+mod scale_settings;
+mod stats;
+mod stats_panel;
+mod stocking;
+mod stocking_panel;
+mod templates;
+mod templates_panel;
+#[cfg(test)]
+mod test_support;
 mod tile;
 mod tile_patterns;
+mod tokens;
+mod tokens_panel;
+mod vision;
+mod wfc;
 
+use campaign::{Campaign, Exit, Location, MapId};
+use campaign_panel::CampaignPanel;
+use corridor::{CorridorRoom, CorridorSettings};
+use corridor_panel::CorridorPanel;
+use document::Document;
+use edges::{Edge, EdgeId};
+use fog::FogMode;
+use fog_panel::FogPanel;
+use generate_dialog::GenerateDialog;
+use graph_panel::GraphPanel;
 use grid::Grid;
-use room::Rooms;
+use hex::GridShape;
+use iso::{IsoSettings, Projection};
+use iso_panel::IsoPanel;
+use levels::{Place, Stair, StairKind};
+use levels_panel::LevelsPanel;
+use lights::{Illumination, Light, LightSettings, Lights};
+use lights_panel::LightsPanel;
+use maze::MazeSettings;
+use maze_panel::MazePanel;
+use problems_panel::ProblemsPanel;
+use room::{RoomId, Rooms};
+use room_graph::RoomGraph;
 use room_manager::RoomManager;
+use scale_settings::ScaleSettings;
+use stats::Statistics;
+use stats_panel::StatsPanel;
+use stocking::StockingTables;
+use templates::{Template, TemplateSettings};
+use templates_panel::TemplatesPanel;
 use tile_patterns::TilePatterns;
+use tokens::{TokenDrag, Tokens};
+use tokens_panel::TokensPanel;
+use vision::VisionSettings;
 
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum ToolMode {
     Brush,
     Erasor,
     Fill,
+    Wall,
+    Door,
+    SecretDoor,
+    Fog,
+    Vision,
+    Light,
+    Measure,
+    Template,
+    Token,
+    Select,
+    Corridor,
+    Stairs,
+    Exit,
 }
 
-type Cells = Grid<Option<usize>>;
+impl ToolMode {
+    /// Tools that work between square cells or measure in squares, which hex maps can't use
+    fn square_only(self) -> bool {
+        matches!(
+            self,
+            ToolMode::Wall
+                | ToolMode::Door
+                | ToolMode::SecretDoor
+                | ToolMode::Vision
+                | ToolMode::Light
+                | ToolMode::Template
+                | ToolMode::Corridor
+        )
+    }
+}
+
+type Cells = Grid<Option<RoomId>>;
+
+/// The top, left, rows and columns of the rectangle with these opposite corners
+fn bounds(
+    ((row_a, col_a), (row_b, col_b)): ((usize, usize), (usize, usize)),
+) -> (usize, usize, usize, usize) {
+    let (top, left) = (row_a.min(row_b), col_a.min(col_b));
+    (
+        top,
+        left,
+        row_a.max(row_b) - top + 1,
+        col_a.max(col_b) - left + 1,
+    )
+}
+
+/// A cell drawn as a square or a hex, depending on the grid
+fn draw_cell(shape: GridShape, (row, col): (usize, usize), style: String) -> Html {
+    match shape.polygon((row, col)) {
+        Some(points) => html!(<polygon points=points style=style />),
+        None => html!(<rect width="1" height="1" x=col y=row style=style />),
+    }
+}
+
+/// A 16 by 16 map with one room, and that room's id
+fn new_document(tile_materials: &tile::Materials) -> (Document, RoomId) {
+    let first_room = room::Room::new(tile_materials.as_ref()[0].clone(), "1".into());
+    let (rooms, id) = Rooms::new().push(first_room);
+
+    (Document::new(16, 16, rooms, scale::Scale::default()), id)
+}
 
 pub struct App {
     link: ComponentLink<Self>,
     node_ref: NodeRef,
     grid_size: usize,
-    cells: Cells,
-    undo: Vec<Cells>,
-    redo: Vec<Cells>,
+    document: Document,
+    undo: Vec<Document>,
+    redo: Vec<Document>,
     cursor_position: Option<(usize, usize)>,
+    cursor_edge: Option<EdgeId>,
+    removing_edges: bool,
+    fog_mode: FogMode,
+    hiding_fog: bool,
+    /// Opposite corners of the rectangle being dragged out by the fog tool
+    fog_rectangle: Option<((usize, usize), (usize, usize))>,
+    /// Opposite corners of the selected rectangle, which generators use as a sample
+    selection: Option<((usize, usize), (usize, usize))>,
+    player_view: bool,
+    vision_settings: VisionSettings,
+    /// Where vision is seen from when no token is selected
+    vision_point: Option<(usize, usize)>,
+    light_settings: LightSettings,
+    show_lighting: bool,
+    iso_settings: IsoSettings,
+    show_level_below: bool,
+    stair_kind: StairKind,
+    /// The end of a stair clicked first with the stairs tool
+    stair_from: Option<Place>,
+    /// The other maps of the campaign, and the exits between them
+    campaign: Campaign,
+    /// Where an exit clicked first with the exit tool leads from
+    exit_from: Option<Location>,
+    /// A campaign file being read
+    reader_task: Option<ReaderTask>,
+    show_generator: bool,
+    maze_settings: MazeSettings,
+    corridor_settings: CorridorSettings,
+    /// The room clicked first with the corridor tool
+    corridor_from: Option<RoomId>,
+    highlighted_cell: Option<(usize, usize)>,
+    measurement: Option<measure::Measurement>,
+    template_settings: TemplateSettings,
+    templates: Vec<Template>,
+    selected_token: Option<usize>,
+    token_drag: Option<TokenDrag>,
     current_tool: ToolMode,
     tile_materials: tile::Materials,
-    rooms: Rooms,
-    selected_room: usize,
+    selected_room: RoomId,
+    show_labels: bool,
 }
 
 #[derive(Debug)]
 pub enum Msg {
     MouseLeave,
     MouseEvent(yew::events::MouseEvent),
+    MouseUp,
     MouseWheel(yew::events::WheelEvent),
     Clear,
     ToolSelected(ToolMode),
@@ -50,7 +223,53 @@ pub enum Msg {
     Undo,
     Redo,
     RoomsChanged(Rooms),
-    SelectedRoomChanged(usize),
+    SelectedRoomChanged(RoomId),
+    RoomDeleted(RoomId),
+    RoomsMerged(RoomId, RoomId),
+    RoomMoved(RoomId, usize),
+    ScaleChanged(scale::Scale),
+    ShowLabels(bool),
+    ExportSvg,
+    ExportBooklet,
+    ExportPlayerSvg,
+    ProblemSelected(lint::Problem),
+    TemplateSettingsChanged(TemplateSettings),
+    TemplateRotated(usize, f64),
+    TemplateRemoved(usize),
+    TokensChanged(Tokens),
+    SelectedTokenChanged(Option<usize>),
+    FogModeChanged(FogMode),
+    FogSet(bool),
+    PlayerView(bool),
+    VisionChanged(VisionSettings),
+    LightSettingsChanged(LightSettings),
+    LightsChanged(Lights),
+    ShowLighting(bool),
+    IsoSettingsChanged(IsoSettings),
+    ExportIsoSvg,
+    LevelSelected(usize),
+    LevelAdded,
+    LevelRemoved(usize),
+    LevelRenamed(usize, String),
+    ShowLevelBelow(bool),
+    StairKindChanged(StairKind),
+    StairRemoved(usize),
+    ExportLevels,
+    MapSelected(MapId),
+    MapAdded,
+    MapRemoved(MapId),
+    MapRenamed(MapId, String),
+    ExitRemoved(usize),
+    ExitFollowed(usize),
+    SaveCampaign,
+    LoadCampaign(web_sys::File),
+    CampaignLoaded(FileData),
+    ShowGenerator(bool),
+    Generated(Cells, Rooms),
+    MazeSettingsChanged(MazeSettings),
+    GenerateMaze,
+    CorridorSettingsChanged(CorridorSettings),
+    StockingTablesChanged(StockingTables),
 }
 
 #[derive(Clone, Properties)]
@@ -59,12 +278,194 @@ pub struct Props {
 }
 
 impl App {
-    fn set_cell(&mut self, row: usize, col: usize, cell: Option<usize>) {
-        if let Some(new_cells) = self.cells.set(row, col, cell) {
-            if self.cells != new_cells {
-                self.redo = Vec::new();
-                self.undo
-                    .push(std::mem::replace(&mut self.cells, new_cells));
+    fn apply(&mut self, new_document: Document) {
+        if self.document != new_document {
+            self.redo = Vec::new();
+            self.undo
+                .push(std::mem::replace(&mut self.document, new_document));
+        }
+    }
+
+    fn set_cells(&mut self, cells: Cells) {
+        self.apply(Document {
+            cells,
+            ..self.document.clone()
+        });
+    }
+
+    fn set_cell(&mut self, row: usize, col: usize, cell: Option<RoomId>) {
+        if let Some(new_cells) = self.document.cells.set(row, col, cell) {
+            self.set_cells(new_cells);
+        }
+    }
+
+    /// Place `edge` on the edge nearest the cursor, or remove it if the drag started on an edge that already had it
+    fn paint_edge(&mut self, id: EdgeId, edge: Edge, starting: bool) {
+        if starting {
+            self.removing_edges = self.document.edges.get(id) == Some(edge);
+        }
+
+        let edge = if self.removing_edges {
+            None
+        } else {
+            Some(edge)
+        };
+
+        self.apply(Document {
+            edges: self.document.edges.set(id, edge),
+            ..self.document.clone()
+        });
+    }
+
+    /// Pressing starts a measurement or adds a bend to it, and pressing on the last bend again clears it
+    fn measure(&mut self, row: usize, col: usize, starting: bool) {
+        let cell = (row, col);
+
+        match (&mut self.measurement, starting) {
+            (None, true) => self.measurement = Some(measure::Measurement::new(cell)),
+            (Some(measurement), true) => {
+                if measurement.last_waypoint() == cell {
+                    self.measurement = None;
+                } else {
+                    measurement.add_waypoint(cell);
+                }
+            }
+            (Some(measurement), false) => measurement.move_end(cell),
+            (None, false) => {}
+        }
+    }
+
+    fn set_fog(&mut self, fog: fog::Fog) {
+        self.apply(Document {
+            fog,
+            ..self.document.clone()
+        });
+    }
+
+    /// Reveal or hide the fog under the cursor, hiding if the drag started on a revealed cell
+    fn paint_fog(&mut self, row: usize, col: usize, starting: bool) {
+        let fog = &self.document.fog;
+
+        if starting {
+            self.hiding_fog = fog.get(row, col) == Some(&true);
+        }
+
+        let revealed = !self.hiding_fog;
+        let new_fog = match self.fog_mode {
+            FogMode::Room => self
+                .document
+                .cells
+                .get(row, col)
+                .copied()
+                .flatten()
+                .and_then(|room| fog::set_room(fog, &self.document.cells, room, revealed)),
+            FogMode::Brush => fog.set(row, col, revealed),
+            FogMode::Rectangle => {
+                let start = match self.fog_rectangle {
+                    Some((start, _)) if !starting => start,
+                    _ => (row, col),
+                };
+                self.fog_rectangle = Some((start, (row, col)));
+                None
+            }
+        };
+
+        if let Some(new_fog) = new_fog {
+            self.set_fog(new_fog);
+        }
+    }
+
+    fn finish_fog_rectangle(&mut self) {
+        if let Some((start, end)) = self.fog_rectangle.take() {
+            if let Some(new_fog) =
+                fog::set_rectangle(&self.document.fog, start, end, !self.hiding_fog)
+            {
+                self.set_fog(new_fog);
+            }
+        }
+    }
+
+    /// Pick up the topmost token under the cursor, or move the token already picked up
+    fn drag_token(&mut self, row: usize, col: usize, starting: bool) {
+        let tokens = &self.document.tokens;
+
+        if starting {
+            let player_view = self.player_view.then(|| self.document.player_view());
+            let index = (0..tokens.len()).rev().find(|index| {
+                tokens.get(*index).is_some_and(|token| {
+                    token.covers(row, col)
+                        && player_view.as_ref().is_none_or(|view| view.shows(token))
+                })
+            });
+            self.selected_token = index;
+            if index.is_some() {
+                self.vision_point = None;
+            }
+            self.token_drag =
+                index.and_then(|index| Some(TokenDrag::new(index, tokens.get(index)?, row, col)));
+        } else if let Some(drag) = &mut self.token_drag {
+            if let Some(token) = tokens.get(drag.index) {
+                let cells = &self.document.cells;
+                drag.move_to(token, row, col, cells.rows(), cells.cols());
+            }
+        }
+    }
+
+    /// The cell vision is seen from: the vision tool's point, or else the middle of the selected token
+    fn vision_origin(&self) -> Option<(usize, usize)> {
+        if let Some(point) = self.vision_point {
+            return Some(point);
+        }
+
+        let token = self.document.tokens.get(self.selected_token?)?;
+        let (row, col) = match self.token_drag {
+            Some(drag) if Some(drag.index) == self.selected_token => (drag.row, drag.col),
+            _ => (token.row, token.col),
+        };
+        let middle = (token.size.cells() - 1) / 2;
+        Some((row + middle, col + middle))
+    }
+
+    /// The cells seen from the vision origin. In the player view `document` leaves out what the players haven't
+    /// found, so vision doesn't trace the shape of hidden rooms.
+    fn visible_cells(&self, document: &Document) -> Option<Grid<bool>> {
+        let origin = self.vision_origin()?;
+        Some(vision::visible_cells(
+            &document.cells,
+            &document.edges,
+            origin,
+            self.vision_settings.open_doors,
+        ))
+    }
+
+    fn reveal_vision(&mut self) {
+        if !self.vision_settings.auto_reveal || !self.square() {
+            return;
+        }
+
+        if let Some(new_fog) = self
+            .visible_cells(&self.document)
+            .and_then(|visible| fog::reveal(&self.document.fog, &visible))
+        {
+            self.set_fog(new_fog);
+        }
+    }
+
+    /// Write the dragged token's new position to the document as a single undo step
+    fn drop_token(&mut self) {
+        if let Some(drag) = self.token_drag.take() {
+            if let Some(token) = self.document.tokens.get(drag.index) {
+                let token = tokens::Token {
+                    row: drag.row,
+                    col: drag.col,
+                    ..token.clone()
+                };
+                if let Some(tokens) = self.document.tokens.set(drag.index, token) {
+                    self.apply(Document {
+                        tokens,
+                        ..self.document.clone()
+                    });
+                }
             }
         }
     }
@@ -76,75 +477,773 @@ impl App {
             .unwrap()
             .get_bounding_client_rect();
 
-        let row = ((ev.y() - (elem.y() as i32)) / (self.grid_size as i32)) as usize;
-        let col = ((ev.x() - (elem.x() as i32)) / (self.grid_size as i32)) as usize;
+        let x = (ev.x() as f64 - elem.x()) / self.grid_size as f64;
+        let y = (ev.y() as f64 - elem.y()) / self.grid_size as f64;
+        // Tools work on the floor under the pointer
+        let (x, y) = match self.projection() {
+            Some(projection) => projection.unproject(x, y),
+            None => (x, y),
+        };
+
+        let shape = self.document.scale.shape;
+        // Walls and doors lie between square cells only
+        let square = shape == GridShape::Square;
+
+        self.cursor_position = shape.cell_at(x.max(0.0), y.max(0.0));
+        self.cursor_edge = Some(EdgeId::nearest(x, y)).filter(|_| square);
 
-        self.cursor_position = Some((row, col));
+        let (row, col) = match self.cursor_position {
+            Some(cell) => cell,
+            None => return,
+        };
+
+        let starting = ev.type_() == "mousedown";
+
+        // In play, clicking an exit the players can see goes where it leads instead of using the tool
+        if self.player_view
+            && starting
+            && (ev.buttons() & 1) != 0
+            && self.document.player_view().shows_cell(row, col)
+        {
+            if let Some(exit) = self.campaign.exit_at(self.here(row, col)).copied() {
+                self.go_to(exit.to);
+                return;
+            }
+        }
+
+        if self.current_tool == ToolMode::Measure {
+            self.measure(row, col, starting);
+            return;
+        }
 
         if (ev.buttons() & 1) == 0 {
             return;
         }
 
         match self.current_tool {
+            tool if !square && tool.square_only() => {}
+            ToolMode::Measure => {}
+            ToolMode::Template => {
+                if starting {
+                    self.templates
+                        .push(Template::new(&self.template_settings, x, y));
+                } else if let Some(template) = self.templates.last_mut() {
+                    template.point_towards(x, y);
+                }
+            }
+            ToolMode::Token => self.drag_token(row, col, starting),
+            ToolMode::Fog => self.paint_fog(row, col, starting),
+            ToolMode::Corridor => {
+                if starting {
+                    self.route_corridor(row, col);
+                }
+            }
+            ToolMode::Stairs => {
+                if starting {
+                    self.place_stair(row, col);
+                }
+            }
+            ToolMode::Exit => {
+                if starting {
+                    self.place_exit(row, col);
+                }
+            }
+            ToolMode::Select => {
+                let start = match self.selection {
+                    Some((start, _)) if !starting => start,
+                    _ => (row, col),
+                };
+                self.selection = Some((start, (row, col)));
+            }
+            ToolMode::Vision => self.vision_point = Some((row, col)),
+            ToolMode::Light => {
+                if starting && self.document.cells.get(row, col).is_some() {
+                    self.apply(Document {
+                        lights: self.document.lights.push_back(Light::new(
+                            &self.light_settings,
+                            row,
+                            col,
+                        )),
+                        ..self.document.clone()
+                    });
+                }
+            }
+            ToolMode::Wall => self.paint_edge(EdgeId::nearest(x, y), Edge::Wall, starting),
+            ToolMode::Door => self.paint_edge(EdgeId::nearest(x, y), Edge::Door, starting),
+            ToolMode::SecretDoor => {
+                self.paint_edge(EdgeId::nearest(x, y), Edge::SecretDoor, starting)
+            }
             ToolMode::Brush => self.set_cell(row, col, Some(self.selected_room)),
             ToolMode::Erasor => self.set_cell(row, col, None),
             ToolMode::Fill => {
-                if let Some(first_cell) = self.cells.get(row, col) {
-                    let mut new_cells = self.cells.clone();
+                if let Some(first_cell) = self.document.cells.get(row, col) {
+                    let mut new_cells = self.document.cells.clone();
 
                     let mut indices = vec![(row, col)];
 
                     while let Some((row, col)) = indices.pop() {
-                        if Some(first_cell) != self.cells.get(row, col) {
+                        if Some(first_cell) != self.document.cells.get(row, col) {
                             continue;
                         }
 
                         if let Some(next_cells) = new_cells.set(row, col, Some(self.selected_room))
                         {
                             if new_cells != next_cells {
-                                let left = col.checked_sub(1);
-                                let up = row.checked_sub(1);
-                                let right = col.checked_add(1);
-                                let down = row.checked_add(1);
-
-                                for coords in &[
-                                    (Some(row), left),
-                                    (Some(row), right),
-                                    (up, Some(col)),
-                                    (down, Some(col)),
-                                ] {
-                                    if let (Some(row), Some(col)) = coords {
-                                        indices.push((*row, *col));
-                                    }
-                                }
+                                indices.extend(shape.neighbours((row, col)));
                             }
                             new_cells = next_cells;
                         }
                     }
 
-                    if new_cells != self.cells {
-                        self.undo
-                            .push(std::mem::replace(&mut self.cells, new_cells))
-                    }
+                    self.set_cells(new_cells);
                 }
             }
         }
     }
 
+    /// The first click picks one end of the stair, and a click on another level places the other end
+    fn place_stair(&mut self, row: usize, col: usize) {
+        let here = Place {
+            level: self.document.level,
+            row,
+            col,
+        };
+
+        match self.stair_from.take() {
+            Some(from) if from.level != here.level => {
+                self.apply(self.document.add_stair(Stair {
+                    kind: self.stair_kind,
+                    from,
+                    to: here,
+                }));
+            }
+            _ => self.stair_from = Some(here),
+        }
+    }
+
+    fn here(&self, row: usize, col: usize) -> Location {
+        Location {
+            map: self.campaign.current(),
+            place: Place {
+                level: self.document.level,
+                row,
+                col,
+            },
+        }
+    }
+
+    /// The first click picks the cell the exit leads from, and the next, on any map, where it leads to
+    fn place_exit(&mut self, row: usize, col: usize) {
+        let here = self.here(row, col);
+
+        match self.exit_from.take() {
+            Some(from) if from != here => {
+                self.campaign = self.campaign.add_exit(Exit { from, to: here });
+            }
+            _ => self.exit_from = Some(here),
+        }
+    }
+
+    /// Show another map of the campaign. Each map keeps its own document, so the undo history starts afresh.
+    fn show_map(&mut self, id: MapId) {
+        if let Some((campaign, document)) = self.campaign.switch(&self.document, id) {
+            self.campaign = campaign;
+            self.document = document;
+            self.undo.clear();
+            self.redo.clear();
+            self.leave_level();
+            self.fix_selected_room();
+        }
+    }
+
+    /// Show the map and level of a location, with its cell highlighted
+    fn go_to(&mut self, location: Location) {
+        self.show_map(location.map);
+        if let Some(document) = self.document.switch_level(location.place.level) {
+            self.document = document;
+            self.leave_level();
+        }
+        self.highlighted_cell = Some((location.place.row, location.place.col));
+    }
+
+    /// Forget what was being done on the level that's no longer shown
+    fn leave_level(&mut self) {
+        self.selection = None;
+        self.measurement = None;
+        self.highlighted_cell = None;
+        self.vision_point = None;
+        self.selected_token = None;
+        self.token_drag = None;
+        self.corridor_from = None;
+    }
+
+    /// Catch up with a document brought back by undo or redo, which was showing `level`
+    fn restored(&mut self, level: usize) {
+        self.campaign = self.campaign.fitted(&self.document);
+        if self.document.level != level {
+            self.leave_level();
+        }
+        self.fix_selected_room();
+    }
+
+    /// The first click picks the room to start from, and the second joins it to the room clicked
+    fn route_corridor(&mut self, row: usize, col: usize) {
+        let room = match self.document.cells.get(row, col).copied().flatten() {
+            Some(room) => room,
+            None => {
+                self.corridor_from = None;
+                return;
+            }
+        };
+        let from = match self.corridor_from.take() {
+            Some(from) if from != room => from,
+            _ => {
+                self.corridor_from = Some(room);
+                return;
+            }
+        };
+
+        let document = &self.document;
+        let corridor = match corridor::route(&document.cells, from, room, &self.corridor_settings) {
+            Some(corridor) => corridor,
+            None => return,
+        };
+
+        let (rooms, id) = match self.corridor_settings.room {
+            CorridorRoom::New => {
+                let mut new_room = room::Room::new(
+                    self.tile_materials.as_ref()[0].clone(),
+                    (document.rooms.len() + 1).to_string(),
+                );
+                new_room.name = String::from("Corridor");
+                document.rooms.push(new_room)
+            }
+            CorridorRoom::Selected => (document.rooms.clone(), self.selected_room),
+        };
+        let cells = corridor
+            .cells
+            .iter()
+            .fold(document.cells.clone(), |cells, (row, col)| {
+                cells.set(*row, *col, Some(id)).unwrap_or(cells)
+            });
+        let edges = corridor
+            .doors
+            .iter()
+            .fold(document.edges.clone(), |edges, door| {
+                edges.set(*door, Some(Edge::Door))
+            });
+
+        self.apply(Document {
+            cells,
+            rooms,
+            edges,
+            ..document.clone()
+        });
+    }
+
     fn cursor(&self) -> Html {
+        if let (ToolMode::Wall, Some(id))
+        | (ToolMode::Door, Some(id))
+        | (ToolMode::SecretDoor, Some(id)) = (self.current_tool, self.cursor_edge)
+        {
+            let ((x1, y1), (x2, y2)) = id.line();
+            return html!(<line x1=x1 y1=y1 x2=x2 y2=y2 stroke="red" stroke-width="0.2" stroke-linecap="round" opacity="0.5" />);
+        }
+
+        let shape = self.document.scale.shape;
+
         if let Some((row, col)) = self.cursor_position {
-            if self.current_tool != ToolMode::Erasor {
-                if let Some(room) = self.rooms.get(self.selected_room) {
-                    return html!(<rect width="1" height="1" x=col y=row style=format!("fill:{}", room.tile_material.url_reference()) />);
+            if let ToolMode::Brush | ToolMode::Fill = self.current_tool {
+                if let Some(room) = self.document.rooms.get(self.selected_room) {
+                    return draw_cell(
+                        shape,
+                        (row, col),
+                        format!("fill:{}", room.tile_material.url_reference()),
+                    );
                 }
             }
 
-            html!(<rect width="1" height="1" x=col y=row style="fill:none;stroke:black;stroke-width:0.1" />)
+            let coordinates = match shape {
+                GridShape::Square => html!(),
+                GridShape::Hex(orientation) => {
+                    let (x, y) = shape.centre((row, col));
+                    let axial = orientation.axial((row, col));
+                    html!(
+                        <text x=x + 0.7 y=y - 0.4 font-family="sans-serif" font-size="0.4" fill="black" stroke="white"
+                            stroke-width="0.1" paint-order="stroke" pointer-events="none">
+                            { format!("offset {},{} axial {},{}", col, row, axial.q, axial.r) }
+                        </text>
+                    )
+                }
+            };
+
+            html!(
+                <>
+                    { draw_cell(shape, (row, col), String::from("fill:none;stroke:black;stroke-width:0.1")) }
+                    { coordinates }
+                </>
+            )
         } else {
             html!()
         }
     }
 
+    /// Keep the selected room pointing at a room that exists, e.g. after undoing the room's creation
+    fn fix_selected_room(&mut self) {
+        if !self.document.rooms.contains(self.selected_room) {
+            if let Some(id) = self.document.rooms.first_id() {
+                self.selected_room = id;
+            }
+        }
+    }
+
+    fn edges(document: &Document) -> Html {
+        let doors = |kind: Edge| {
+            html!({ for document.edges.iter().filter(|(_, edge)| *edge == kind).map(|(id, _)| {
+                let (x, y, width, height) = id.door_rect();
+                html!(<rect x=x y=y width=width height=height />)
+            }) })
+        };
+
+        html!(
+            <>
+                { export::group(
+                    export::WALL_ATTRIBUTES,
+                    html!({ for document.edges.iter().filter(|(_, edge)| *edge == Edge::Wall).map(|(id, _)| {
+                        let ((x1, y1), (x2, y2)) = id.line();
+                        html!(<line x1=x1 y1=y1 x2=x2 y2=y2 />)
+                    }) }),
+                ) }
+                { export::group(export::DOOR_ATTRIBUTES, doors(Edge::Door)) }
+                { export::group(export::SECRET_DOOR_ATTRIBUTES, doors(Edge::SecretDoor)) }
+            </>
+        )
+    }
+
+    fn templates(&self) -> Html {
+        if !self.square() {
+            return html!();
+        }
+
+        let feet_per_cell = self.document.scale.feet_per_cell();
+        let ruleset = self.template_settings.ruleset;
+        let (rows, cols) = (self.document.cells.rows(), self.document.cells.cols());
+
+        html!(
+            <g pointer-events="none">
+                { for self.templates.iter().map(|template| html!(
+                    <g>
+                        <g fill="orange" opacity="0.4">
+                            { for template.affected_cells(feet_per_cell, ruleset, rows, cols).into_iter().map(|(row, col)| html!(
+                                <rect x=col y=row width="1" height="1" />
+                            )) }
+                        </g>
+                        <g fill="none" stroke="darkorange" stroke-width="0.08">
+                            { template.outline(feet_per_cell, ruleset) }
+                        </g>
+                    </g>
+                )) }
+            </g>
+        )
+    }
+
+    /// Tokens are indexed by their place in the full document, so in the player view `document` only filters them
+    fn tokens(&self, document: &Document) -> Html {
+        let tokens = self.document.tokens.iter().enumerate();
+
+        html!(
+            <g pointer-events="none" font-family="sans-serif" font-weight="bold">
+                { for tokens.filter(|(_, token)| !self.player_view || document.shows(token)).map(|(index, token)| match self.token_drag {
+                    Some(drag) if drag.index == index => token.view(document.scale.shape, drag.row, drag.col),
+                    _ => token.view(document.scale.shape, token.row, token.col),
+                }) }
+            </g>
+        )
+    }
+
+    /// Darken the map outside the lights, with each light's glow clipped to the cells it reaches. In the player view
+    /// `document` leaves out hidden rooms and unrevealed cells, so no light shows their shape.
+    fn lighting(&self, document: &Document) -> Html {
+        if !self.show_lighting || !self.square() {
+            return html!();
+        }
+
+        let lit: Vec<_> = document
+            .lights
+            .iter()
+            .map(|light| {
+                light.illuminate(
+                    &document.cells,
+                    &document.edges,
+                    &document.scale,
+                    self.vision_settings.open_doors,
+                )
+            })
+            .collect();
+        let illumination = lights::illumination(&lit, document.cells.rows(), document.cells.cols());
+        let glows: Vec<_> = document.lights.iter().zip(&lit).enumerate().collect();
+
+        html!(
+            <g pointer-events="none">
+                <defs>
+                    { for glows.iter().map(|(index, (light, cells))| html!(
+                        <>
+                            <radialGradient id=format!("lightGlow{}", index) gradientUnits="userSpaceOnUse"
+                                cx=light.col as f64 + 0.5 cy=light.row as f64 + 0.5 r=light.radius(&document.scale)>
+                                <stop offset="0" stop-color=light.colour.clone() stop-opacity="0.5" />
+                                <stop offset="1" stop-color=light.colour.clone() stop-opacity="0" />
+                            </radialGradient>
+                            <clipPath id=format!("lightClip{}", index)>
+                                { for cells.iter().filter(|(_, _, lit)| **lit != Illumination::Dark).map(|(row, col, _)| html!(
+                                    <rect x=col y=row width="1" height="1" />
+                                )) }
+                            </clipPath>
+                        </>
+                    )) }
+                </defs>
+                <g fill="black">
+                    { for illumination.iter().filter_map(|(row, col, lit)| match lit {
+                        Illumination::Dark => Some((row, col, "0.75")),
+                        Illumination::Dim => Some((row, col, "0.4")),
+                        Illumination::Bright => None,
+                    }).map(|(row, col, opacity)| html!(
+                        <rect x=col y=row width="1" height="1" opacity=opacity />
+                    )) }
+                </g>
+                { for glows.iter().map(|(index, (light, _))| html!(
+                    <circle cx=light.col as f64 + 0.5 cy=light.row as f64 + 0.5 r=light.radius(&document.scale)
+                        fill=format!("url(#lightGlow{})", index) clip-path=format!("url(#lightClip{})", index) />
+                )) }
+            </g>
+        )
+    }
+
+    /// Where the lights are, for the GM
+    fn light_markers(&self) -> Html {
+        if self.player_view || !self.square() {
+            return html!();
+        }
+
+        html!(
+            <g pointer-events="none" stroke="black" stroke-width="0.05">
+                { for self.document.lights.iter().map(|light| html!(
+                    <circle cx=light.col as f64 + 0.5 cy=light.row as f64 + 0.5 r="0.2" fill=light.colour.clone() />
+                )) }
+            </g>
+        )
+    }
+
+    fn vision(&self, document: &Document) -> Html {
+        if !self.vision_settings.show || !self.square() {
+            return html!();
+        }
+
+        match self.visible_cells(document) {
+            Some(visible) => html!(
+                <path d=vision::outline(&visible) fill="yellow" fill-opacity="0.2" stroke="gold" stroke-width="0.08" pointer-events="none" />
+            ),
+            None => html!(),
+        }
+    }
+
+    /// Cover the cells the players haven't seen: shaded for the GM, opaque in the player view
+    fn fog(&self) -> Html {
+        let opacity = if self.player_view { "1" } else { "0.4" };
+
+        let rectangle = match self.fog_rectangle {
+            Some(corners) => {
+                let (top, left, rows, cols) = bounds(corners);
+                let (x, y, width, height) = self.document.scale.shape.bounds(top, left, rows, cols);
+                html!(<rect x=x y=y width=width height=height fill="none" stroke="royalblue" stroke-width="0.1" stroke-dasharray="0.2 0.1" />)
+            }
+            None => html!(),
+        };
+
+        html!(
+            <g pointer-events="none">
+                <g fill="black" opacity=opacity>
+                    { for self.document.fog.iter().filter(|(_, _, revealed)| !**revealed).map(|(row, col, _)| draw_cell(self.document.scale.shape, (row, col), String::new())) }
+                </g>
+                { rectangle }
+            </g>
+        )
+    }
+
+    fn selection(&self) -> Html {
+        match self.selection {
+            Some(corners) => {
+                let (top, left, rows, cols) = bounds(corners);
+                let (x, y, width, height) = self.document.scale.shape.bounds(top, left, rows, cols);
+                html!(<rect x=x y=y width=width height=height fill="none" stroke="seagreen" stroke-width="0.1" stroke-dasharray="0.2 0.1" pointer-events="none" />)
+            }
+            None => html!(),
+        }
+    }
+
+    /// The selected cells, or the whole map if nothing is selected
+    fn sample(&self) -> Cells {
+        match self.selection {
+            Some(corners) => {
+                let (top, left, rows, cols) = bounds(corners);
+                self.document.cells.crop(top, left, rows, cols)
+            }
+            None => self.document.cells.clone(),
+        }
+    }
+
+    fn highlight(&self) -> Html {
+        if let Some((row, col)) = self.highlighted_cell {
+            draw_cell(
+                self.document.scale.shape,
+                (row, col),
+                String::from("fill:none;stroke:red;stroke-width:0.15;pointer-events:none"),
+            )
+        } else {
+            html!()
+        }
+    }
+
+    fn labels(&self, document: &Document) -> Html {
+        if !self.show_labels {
+            return html!();
+        }
+
+        export::group(
+            export::LABEL_ATTRIBUTES,
+            html!(
+                { for labels::labels(&document.cells, &document.rooms, document.scale.shape).into_iter().map(|label| {
+                    let (x, y) = document.scale.shape.point(label.x, label.y);
+                    html!(<text x=x y=y>{ label.text }</text>)
+                }) }
+            ),
+        )
+    }
+
+    /// Panels that would give away GM-only information, left out of the player view
+    fn gm_panels(&self) -> Html {
+        if self.player_view {
+            return html!();
+        }
+
+        html!(
+            <>
+                { if self.current_tool == ToolMode::Corridor && self.square() {
+                    let from = self.corridor_from.and_then(|id| self.document.rooms.get(id)).map(|room| room.label.clone());
+                    html!(
+                        <CorridorPanel
+                            settings=self.corridor_settings
+                            settings_changed=self.link.callback(Msg::CorridorSettingsChanged)
+                            from=from
+                        />
+                    )
+                } else {
+                    html!()
+                } }
+                // Mazes and lights work on square cells only
+                { if self.square() {
+                    html!(
+                        <>
+                            <MazePanel
+                                settings=self.maze_settings
+                                settings_changed=self.link.callback(Msg::MazeSettingsChanged)
+                                selection=self.selection.is_some()
+                                generate=self.link.callback(|_| Msg::GenerateMaze)
+                            />
+                            <LightsPanel
+                                settings=self.light_settings.clone()
+                                settings_changed=self.link.callback(Msg::LightSettingsChanged)
+                                lights=self.document.lights.clone()
+                                lights_changed=self.link.callback(Msg::LightsChanged)
+                                show_lighting=self.show_lighting
+                                show_lighting_changed=self.link.callback(Msg::ShowLighting)
+                            />
+                        </>
+                    )
+                } else {
+                    html!()
+                } }
+                <TokensPanel
+                    tokens=self.document.tokens.clone()
+                    tokens_changed=self.link.callback(Msg::TokensChanged)
+                    rows=self.document.cells.rows()
+                    cols=self.document.cells.cols()
+                    selected_token=self.selected_token
+                    selected_token_changed=self.link.callback(Msg::SelectedTokenChanged)
+                />
+                <GraphPanel graph=RoomGraph::new(&self.document) />
+                <ProblemsPanel
+                    problems=lint::lint(&self.document)
+                    rooms=self.document.rooms.clone()
+                    problem_selected=self.link.callback(Msg::ProblemSelected)
+                />
+                <StatsPanel statistics=Statistics::new(&self.document) rooms=self.document.rooms.clone() scale=self.document.scale />
+            </>
+        )
+    }
+
+    /// The level below, faintly, to line this one up with
+    fn level_below(&self, document: &Document) -> Html {
+        let below = match document.level_below() {
+            Some(below) if self.show_level_below && !self.player_view => below,
+            _ => return html!(),
+        };
+        let shape = document.scale.shape;
+
+        html!(
+            <g opacity="0.25" pointer-events="none">
+                { for below.cells.iter().map(|(row, col, cell)| {
+                    match cell.and_then(|id| document.rooms.get(id)) {
+                        Some(room) => draw_cell(shape, (row, col), format!("fill:{}", room.tile_material.url_reference())),
+                        None => html!(),
+                    }
+                }) }
+            </g>
+        )
+    }
+
+    /// The ends of stairs on this level, pointing up or down to where they lead
+    fn stairs(&self, document: &Document) -> Html {
+        let levels = document.levels();
+        let ends: Vec<_> = document
+            .stairs
+            .iter()
+            .flat_map(|stair| {
+                stair
+                    .ends_on(document.level)
+                    .into_iter()
+                    .map(move |ends| (stair.kind, ends))
+            })
+            .collect();
+
+        html!(
+            <g pointer-events="none" font-family="sans-serif" font-size="0.6" font-weight="bold" text-anchor="middle"
+                dominant-baseline="central" fill="black" stroke="white" stroke-width="0.12" paint-order="stroke">
+                { for ends.into_iter().map(|(kind, (end, target))| {
+                    let (x, y) = document.scale.shape.centre((end.row, end.col));
+                    let arrow = if target.level < end.level { "▲" } else { "▼" };
+                    let title = format!("{} to {}", kind, levels.get(target.level).map(|level| level.name.clone()).unwrap_or_default());
+                    html!(
+                        <text x=x y=y>
+                            <title>{ title }</title>
+                            { format!("{}{}", kind.symbol(), arrow) }
+                        </text>
+                    )
+                }) }
+            </g>
+        )
+    }
+
+    /// Exits from this level, labelled with the map they lead to
+    fn exits(&self, document: &Document) -> Html {
+        let campaign = self.campaign.store(document);
+        let exits: Vec<_> = campaign
+            .exits
+            .iter()
+            .filter(|exit| {
+                exit.from.map == campaign.current() && exit.from.place.level == document.level
+            })
+            .filter(|exit| {
+                !self.player_view || document.shows_cell(exit.from.place.row, exit.from.place.col)
+            })
+            .collect();
+
+        html!(
+            <g pointer-events="none" font-family="sans-serif" font-size="0.45" font-weight="bold" dominant-baseline="central"
+                fill="darkgreen" stroke="white" stroke-width="0.12" paint-order="stroke">
+                { for exits.into_iter().map(|exit| {
+                    let (x, y) = document.scale.shape.centre((exit.from.place.row, exit.from.place.col));
+                    let target = campaign.get(exit.to.map).map(|map| map.name.clone()).unwrap_or_default();
+                    html!(<text x=x - 0.35 y=y>{ format!("⇨ {}", target) }</text>)
+                }) }
+            </g>
+        )
+    }
+
+    /// The map seen from above, with every layer
+    fn plan(&self, document: &Document) -> Html {
+        let shape = document.scale.shape;
+
+        html!(
+            <>
+                { self.level_below(document) }
+                { for document.cells.iter().map(|(row, col, cell)| {
+                    if let Some(material) = cell.and_then(|id| document.rooms.get(id).map(|room| &room.tile_material)) {
+                        draw_cell(shape, (row, col), format!("fill:{}", material.url_reference()))
+                    } else {
+                        html!()
+                    }
+                })}
+                { Self::edges(document) }
+                { self.stairs(document) }
+                { self.exits(document) }
+                { self.labels(document) }
+                { self.templates() }
+                { self.tokens(document) }
+                { self.lighting(document) }
+                { self.light_markers() }
+                { self.fog() }
+                { self.vision(document) }
+                { self.selection() }
+                { self.highlight() }
+                { self.cursor() }
+                { for self.measurement.iter().map(|measurement| measurement.view(&self.document.scale)) }
+            </>
+        )
+    }
+
+    /// The isometric projection, when the map is shown in one
+    fn projection(&self) -> Option<Projection> {
+        let cells = &self.document.cells;
+        Some(Projection::new(
+            cells.rows(),
+            cells.cols(),
+            &self.iso_settings,
+        ))
+        .filter(|_| self.iso_settings.show && self.document.scale.shape == GridShape::Square)
+    }
+
+    /// The floor, walls and labels in isometric projection, with the cursor and selection on the floor
+    fn isometric(&self, document: &Document, projection: &Projection) -> Html {
+        let faces = iso::faces(&document.cells, &document.edges, projection);
+        let labels = if self.show_labels {
+            labels::labels(&document.cells, &document.rooms, document.scale.shape)
+        } else {
+            Vec::new()
+        };
+
+        html!(
+            <>
+                <g transform=projection.floor_transform()>
+                    { for document.cells.iter().map(|(row, col, cell)| {
+                        match cell.and_then(|id| document.rooms.get(id)) {
+                            Some(room) => html!(<rect width="1" height="1" x=col y=row style=format!("fill:{}", room.tile_material.url_reference()) />),
+                            None => html!(),
+                        }
+                    }) }
+                    { self.selection() }
+                    { self.highlight() }
+                    { self.cursor() }
+                </g>
+                { export::group(
+                    export::FACE_ATTRIBUTES,
+                    html!({ for faces.into_iter().map(|face| html!(<polygon points=face.points fill=face.fill />)) }),
+                ) }
+                { export::group(
+                    export::LABEL_ATTRIBUTES,
+                    html!({ for labels.into_iter().map(|label| {
+                        let (x, y) = projection.project(label.x, label.y, 0.0);
+                        html!(<text x=x y=y>{ label.text }</text>)
+                    }) }),
+                ) }
+            </>
+        )
+    }
+
+    /// Whether the map is on a square grid, which some tools need
+    fn square(&self) -> bool {
+        self.document.scale.shape == GridShape::Square
+    }
+
     fn button_class(&self, tool: ToolMode) -> Option<&'static str> {
         if self.current_tool == tool {
             Some("selected")
@@ -159,22 +1258,48 @@ impl Component for App {
     type Properties = Props;
 
     fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
-        let first_room = room::Room {
-            tile_material: props.tile_materials.as_ref()[0].clone(),
-        };
+        let (document, selected_room) = new_document(&props.tile_materials);
 
         Self {
             link,
             node_ref: NodeRef::default(),
             grid_size: 16,
-            cells: Grid::with_dimensions(16, 16),
+            campaign: Campaign::new(document.clone()),
+            exit_from: None,
+            reader_task: None,
+            document,
             undo: Vec::new(),
             redo: Vec::new(),
             cursor_position: None,
+            cursor_edge: None,
+            removing_edges: false,
+            fog_mode: FogMode::Room,
+            hiding_fog: false,
+            fog_rectangle: None,
+            selection: None,
+            player_view: false,
+            vision_settings: VisionSettings::default(),
+            vision_point: None,
+            light_settings: LightSettings::default(),
+            show_lighting: false,
+            iso_settings: IsoSettings::default(),
+            show_level_below: true,
+            stair_kind: StairKind::Stairs,
+            stair_from: None,
+            show_generator: false,
+            maze_settings: MazeSettings::default(),
+            corridor_settings: CorridorSettings::default(),
+            corridor_from: None,
+            highlighted_cell: None,
+            measurement: None,
+            template_settings: TemplateSettings::default(),
+            templates: Vec::new(),
+            selected_token: None,
+            token_drag: None,
             current_tool: ToolMode::Brush,
             tile_materials: props.tile_materials,
-            rooms: vec![first_room].into(),
-            selected_room: 0,
+            selected_room,
+            show_labels: true,
         }
     }
 
@@ -182,12 +1307,25 @@ impl Component for App {
         match msg {
             Msg::MouseLeave => {
                 self.cursor_position = None;
+                self.cursor_edge = None;
+                self.token_drag = None;
+                self.fog_rectangle = None;
                 true
             }
             Msg::MouseEvent(ev) => {
                 self.mouse_event(ev);
                 true
             }
+            Msg::MouseUp => {
+                self.drop_token();
+                self.finish_fog_rectangle();
+                // A click without a drag clears the selection
+                if self.selection.is_some_and(|(start, end)| start == end) {
+                    self.selection = None;
+                }
+                self.reveal_vision();
+                true
+            }
             Msg::MouseWheel(ev) => {
                 ev.prevent_default();
                 self.grid_size =
@@ -195,10 +1333,9 @@ impl Component for App {
                 true
             }
             Msg::Clear => {
-                if self.cells.iter().any(|(_, _, cell)| cell.is_some()) {
-                    let new_cells = Grid::with_dimensions(self.cells.rows(), self.cells.cols());
-                    self.undo
-                        .push(std::mem::replace(&mut self.cells, new_cells));
+                let cells = &self.document.cells;
+                if cells.iter().any(|(_, _, cell)| cell.is_some()) {
+                    self.set_cells(Grid::with_dimensions(cells.rows(), cells.cols()));
                     true
                 } else {
                     false
@@ -206,6 +1343,10 @@ impl Component for App {
             }
             Msg::ToolSelected(tool) => {
                 self.current_tool = tool;
+                self.measurement = None;
+                self.corridor_from = None;
+                self.stair_from = None;
+                self.exit_from = None;
                 true
             }
             Msg::NewTiles(tiles) => {
@@ -213,25 +1354,400 @@ impl Component for App {
                 true
             }
             Msg::Undo => {
-                if let Some(cells) = self.undo.pop() {
-                    self.redo.push(std::mem::replace(&mut self.cells, cells));
+                if let Some(document) = self.undo.pop() {
+                    let level = self.document.level;
+                    self.redo
+                        .push(std::mem::replace(&mut self.document, document));
+                    self.restored(level);
                 }
                 true
             }
             Msg::Redo => {
-                if let Some(cells) = self.redo.pop() {
-                    self.undo.push(std::mem::replace(&mut self.cells, cells));
+                if let Some(document) = self.redo.pop() {
+                    let level = self.document.level;
+                    self.undo
+                        .push(std::mem::replace(&mut self.document, document));
+                    self.restored(level);
                 }
                 true
             }
             Msg::RoomsChanged(rooms) => {
-                self.rooms = rooms;
+                self.apply(Document {
+                    rooms,
+                    ..self.document.clone()
+                });
                 true
             }
             Msg::SelectedRoomChanged(room) => {
                 self.selected_room = room;
                 true
             }
+            Msg::RoomDeleted(id) => {
+                let index = self.document.rooms.index_of(id);
+                if let Some(new_document) = self.document.delete_room(id) {
+                    self.apply(new_document);
+                    if self.selected_room == id {
+                        let rooms = &self.document.rooms;
+                        self.selected_room = index
+                            .and_then(|index| rooms.ids().nth(index.min(rooms.len() - 1)))
+                            .unwrap_or(self.selected_room);
+                    }
+                }
+                true
+            }
+            Msg::RoomsMerged(from, into) => {
+                if let Some(new_document) = self.document.merge_rooms(from, into) {
+                    self.apply(new_document);
+                    self.selected_room = into;
+                }
+                true
+            }
+            Msg::RoomMoved(id, index) => {
+                if let Some(new_document) = self.document.move_room(id, index) {
+                    self.apply(new_document);
+                }
+                true
+            }
+            Msg::ScaleChanged(scale) => {
+                self.apply(Document {
+                    scale,
+                    ..self.document.clone()
+                });
+                true
+            }
+            Msg::ShowLabels(show_labels) => {
+                self.show_labels = show_labels;
+                true
+            }
+            Msg::ExportSvg => {
+                let svg = export::svg(
+                    &self.document,
+                    &self.tile_materials,
+                    &export::SvgOptions {
+                        show_labels: self.show_labels,
+                    },
+                );
+                if let Err(err) = download::download("map.svg", "image/svg+xml", &svg) {
+                    log::error!("Failed to export map: {:?}", err);
+                }
+                false
+            }
+            Msg::ExportBooklet => {
+                let booklet = booklet::html(
+                    &self.document,
+                    &self.tile_materials,
+                    &export::SvgOptions {
+                        show_labels: self.show_labels,
+                    },
+                );
+                if let Err(err) = download::download("room-key.html", "text/html", &booklet) {
+                    log::error!("Failed to export booklet: {:?}", err);
+                }
+                false
+            }
+            Msg::ExportPlayerSvg => {
+                let svg = export::svg(
+                    &self.document.player_view(),
+                    &self.tile_materials,
+                    &export::SvgOptions {
+                        show_labels: self.show_labels,
+                    },
+                );
+                if let Err(err) = download::download("player-map.svg", "image/svg+xml", &svg) {
+                    log::error!("Failed to export player map: {:?}", err);
+                }
+                false
+            }
+            Msg::TemplateSettingsChanged(settings) => {
+                self.template_settings = settings;
+                true
+            }
+            Msg::TemplateRotated(index, direction) => {
+                if let Some(template) = self.templates.get_mut(index) {
+                    template.direction = direction;
+                }
+                true
+            }
+            Msg::TemplateRemoved(index) => {
+                if index < self.templates.len() {
+                    self.templates.remove(index);
+                }
+                true
+            }
+            Msg::TokensChanged(tokens) => {
+                self.apply(Document {
+                    tokens,
+                    ..self.document.clone()
+                });
+                true
+            }
+            Msg::SelectedTokenChanged(token) => {
+                self.selected_token = token;
+                self.vision_point = None;
+                self.reveal_vision();
+                true
+            }
+            Msg::FogModeChanged(mode) => {
+                self.fog_mode = mode;
+                true
+            }
+            Msg::FogSet(revealed) => {
+                if let Some(new_fog) = fog::set_all(&self.document.fog, revealed) {
+                    self.set_fog(new_fog);
+                }
+                true
+            }
+            Msg::VisionChanged(settings) => {
+                self.vision_settings = settings;
+                self.reveal_vision();
+                true
+            }
+            Msg::LightSettingsChanged(settings) => {
+                self.light_settings = settings;
+                true
+            }
+            Msg::LightsChanged(lights) => {
+                self.apply(Document {
+                    lights,
+                    ..self.document.clone()
+                });
+                true
+            }
+            Msg::ShowLighting(show_lighting) => {
+                self.show_lighting = show_lighting;
+                true
+            }
+            Msg::IsoSettingsChanged(settings) => {
+                self.iso_settings = settings;
+                true
+            }
+            Msg::ExportIsoSvg => {
+                let document = if self.player_view {
+                    self.document.player_view()
+                } else {
+                    self.document.clone()
+                };
+                let svg = export::iso_svg(
+                    &document,
+                    &self.tile_materials,
+                    &self.iso_settings,
+                    &export::SvgOptions {
+                        show_labels: self.show_labels,
+                    },
+                );
+                if let Err(err) = download::download("isometric-map.svg", "image/svg+xml", &svg) {
+                    log::error!("Failed to export isometric map: {:?}", err);
+                }
+                false
+            }
+            Msg::LevelSelected(index) => {
+                // Moving between levels isn't an edit, so it isn't undone
+                if let Some(document) = self.document.switch_level(index) {
+                    self.document = document;
+                    self.leave_level();
+                }
+                true
+            }
+            Msg::LevelAdded => {
+                self.apply(self.document.add_level());
+                true
+            }
+            Msg::LevelRemoved(index) => {
+                if let Some(document) = self.document.remove_level(index) {
+                    self.apply(document);
+                    self.campaign = self.campaign.without_level(self.campaign.current(), index);
+                    self.leave_level();
+                }
+                true
+            }
+            Msg::LevelRenamed(index, name) => {
+                if let Some(document) = self.document.rename_level(index, name) {
+                    self.apply(document);
+                }
+                true
+            }
+            Msg::ShowLevelBelow(show) => {
+                self.show_level_below = show;
+                true
+            }
+            Msg::StairKindChanged(kind) => {
+                self.stair_kind = kind;
+                true
+            }
+            Msg::StairRemoved(index) => {
+                if let Some(stairs) = self.document.stairs.remove(index) {
+                    self.apply(Document {
+                        stairs,
+                        ..self.document.clone()
+                    });
+                }
+                true
+            }
+            Msg::ExportLevels => {
+                let options = export::SvgOptions {
+                    show_labels: self.show_labels,
+                };
+                for (index, level) in self.document.levels().iter().enumerate() {
+                    let document = self
+                        .document
+                        .switch_level(index)
+                        .unwrap_or_else(|| self.document.clone());
+                    let svg = export::svg(&document, &self.tile_materials, &options);
+                    let name = format!("map-{}.svg", index + 1);
+                    if let Err(err) = download::download(&name, "image/svg+xml", &svg) {
+                        log::error!("Failed to export {}: {:?}", level.name, err);
+                    }
+                }
+                false
+            }
+            Msg::MapSelected(id) => {
+                self.show_map(id);
+                true
+            }
+            Msg::MapAdded => {
+                let (document, _) = new_document(&self.tile_materials);
+                let (campaign, id) = self.campaign.add_map(document);
+                self.campaign = campaign;
+                self.show_map(id);
+                true
+            }
+            Msg::MapRemoved(id) => {
+                if let Some(campaign) = self.campaign.remove_map(id) {
+                    self.campaign = campaign;
+                }
+                true
+            }
+            Msg::MapRenamed(id, name) => {
+                if let Some(campaign) = self.campaign.rename_map(id, name) {
+                    self.campaign = campaign;
+                }
+                true
+            }
+            Msg::ExitRemoved(index) => {
+                if let Some(exits) = self.campaign.exits.remove(index) {
+                    self.campaign.exits = exits;
+                }
+                true
+            }
+            Msg::ExitFollowed(index) => {
+                if let Some(exit) = self.campaign.exits.get(index).copied() {
+                    self.go_to(exit.to);
+                }
+                true
+            }
+            Msg::SaveCampaign => {
+                let saved = self
+                    .campaign
+                    .to_json(&self.document)
+                    .map_err(|err| JsValue::from_str(&err))
+                    .and_then(|json| {
+                        download::download("campaign.json", "application/json", &json)
+                    });
+                if let Err(err) = saved {
+                    log::error!("Failed to save campaign: {:?}", err);
+                }
+                false
+            }
+            Msg::LoadCampaign(file) => {
+                let callback = self.link.callback(Msg::CampaignLoaded);
+                match ReaderService::new().read_file(file, callback) {
+                    Ok(task) => self.reader_task = Some(task),
+                    Err(err) => log::error!("Failed to read campaign: {:?}", err),
+                }
+                false
+            }
+            Msg::CampaignLoaded(file) => {
+                self.reader_task = None;
+                let json = String::from_utf8_lossy(&file.content);
+                match Campaign::from_json(&json, &self.tile_materials) {
+                    Ok((campaign, document)) => {
+                        self.campaign = campaign;
+                        self.document = document;
+                        self.undo.clear();
+                        self.redo.clear();
+                        self.exit_from = None;
+                        self.stair_from = None;
+                        self.leave_level();
+                        self.fix_selected_room();
+                        true
+                    }
+                    Err(err) => {
+                        log::error!("Failed to load campaign {}: {}", file.name, err);
+                        false
+                    }
+                }
+            }
+            Msg::ShowGenerator(show_generator) => {
+                self.show_generator = show_generator;
+                true
+            }
+            Msg::Generated(cells, rooms) => {
+                self.apply(self.document.generated(cells, rooms));
+                self.campaign = self.campaign.fitted(&self.document);
+                self.fix_selected_room();
+                self.selected_token = None;
+                self.vision_point = None;
+                true
+            }
+            Msg::MazeSettingsChanged(settings) => {
+                self.maze_settings = settings;
+                true
+            }
+            Msg::GenerateMaze => {
+                let document = &self.document;
+                if let Some((cells, edges)) =
+                    self.selection
+                        .filter(|_| self.square())
+                        .and_then(|corners| {
+                            maze::fill(
+                                &self.maze_settings,
+                                bounds(corners),
+                                &document.cells,
+                                &document.edges,
+                                self.selected_room,
+                            )
+                        })
+                {
+                    self.apply(Document {
+                        cells,
+                        edges,
+                        ..self.document.clone()
+                    });
+                }
+                true
+            }
+            Msg::CorridorSettingsChanged(settings) => {
+                self.corridor_settings = settings;
+                true
+            }
+            Msg::StockingTablesChanged(tables) => {
+                self.campaign.stocking_tables = tables;
+                true
+            }
+            Msg::PlayerView(player_view) => {
+                self.player_view = player_view;
+                true
+            }
+            Msg::ProblemSelected(problem) => {
+                if let Some(room) = problem
+                    .room()
+                    .filter(|room| self.document.rooms.contains(*room))
+                {
+                    self.selected_room = room;
+                }
+                let place = problem.place();
+                if let Some(document) =
+                    place.and_then(|place| self.document.switch_level(place.level))
+                {
+                    self.document = document;
+                    self.leave_level();
+                }
+                self.highlighted_cell = place.map(|place| (place.row, place.col));
+                if let Some(element) = self.node_ref.cast::<web_sys::Element>() {
+                    element.scroll_into_view();
+                }
+                true
+            }
         }
     }
 
@@ -240,50 +1756,163 @@ impl Component for App {
     }
 
     fn view(&self) -> Html {
-        let width = self.cells.cols();
-        let height = self.cells.rows();
+        let shape = self.document.scale.shape;
+        let projection = self.projection();
+        let (width, height) = match &projection {
+            Some(projection) => projection.size(),
+            None => shape.size(self.document.cells.rows(), self.document.cells.cols()),
+        };
+        let (width, height) = (
+            self.grid_size as f64 * width,
+            self.grid_size as f64 * height,
+        );
+        let show_labels = self.show_labels;
+        let show_generator = self.show_generator;
+        let square = self.square();
+        let document = if self.player_view {
+            self.document.player_view()
+        } else {
+            self.document.clone()
+        };
 
         html!(
             <>
                 <fieldset id="tools">
                     <legend>{"Tools"}</legend>
                     <button onclick=self.link.callback(|_| Msg::Clear)>{"Clear"}</button>
+                    <button onclick=self.link.callback(move |_| Msg::ShowGenerator(!show_generator))>{"Generate..."}</button>
                     <button class=self.button_class(ToolMode::Brush) onclick=self.link.callback(|_| Msg::ToolSelected(ToolMode::Brush))>{"Brush"}</button>
                     <button class=self.button_class(ToolMode::Erasor) onclick=self.link.callback(|_| Msg::ToolSelected(ToolMode::Erasor))>{"Erasor"}</button>
                     <button class=self.button_class(ToolMode::Fill) onclick=self.link.callback(|_| Msg::ToolSelected(ToolMode::Fill))>{"Fill"}</button>
+                    <button class=self.button_class(ToolMode::Wall) disabled=!square onclick=self.link.callback(|_| Msg::ToolSelected(ToolMode::Wall))>{"Wall"}</button>
+                    <button class=self.button_class(ToolMode::Door) disabled=!square onclick=self.link.callback(|_| Msg::ToolSelected(ToolMode::Door))>{"Door"}</button>
+                    <button class=self.button_class(ToolMode::SecretDoor) disabled=!square onclick=self.link.callback(|_| Msg::ToolSelected(ToolMode::SecretDoor))>{"Secret Door"}</button>
+                    <button class=self.button_class(ToolMode::Fog) onclick=self.link.callback(|_| Msg::ToolSelected(ToolMode::Fog))>{"Fog"}</button>
+                    <button class=self.button_class(ToolMode::Vision) disabled=!square onclick=self.link.callback(|_| Msg::ToolSelected(ToolMode::Vision))>{"Vision"}</button>
+                    <button class=self.button_class(ToolMode::Light) disabled=!square onclick=self.link.callback(|_| Msg::ToolSelected(ToolMode::Light))>{"Light"}</button>
+                    <button class=self.button_class(ToolMode::Measure) onclick=self.link.callback(|_| Msg::ToolSelected(ToolMode::Measure))>{"Measure"}</button>
+                    <button class=self.button_class(ToolMode::Template) disabled=!square onclick=self.link.callback(|_| Msg::ToolSelected(ToolMode::Template))>{"Template"}</button>
+                    <button class=self.button_class(ToolMode::Token) onclick=self.link.callback(|_| Msg::ToolSelected(ToolMode::Token))>{"Token"}</button>
+                    <button class=self.button_class(ToolMode::Select) onclick=self.link.callback(|_| Msg::ToolSelected(ToolMode::Select))>{"Select"}</button>
+                    <button class=self.button_class(ToolMode::Corridor) disabled=!square onclick=self.link.callback(|_| Msg::ToolSelected(ToolMode::Corridor))>{"Corridor"}</button>
+                    <button class=self.button_class(ToolMode::Stairs) onclick=self.link.callback(|_| Msg::ToolSelected(ToolMode::Stairs))>{"Stairs"}</button>
+                    <button class=self.button_class(ToolMode::Exit) onclick=self.link.callback(|_| Msg::ToolSelected(ToolMode::Exit))>{"Exit"}</button>
                     <button onclick=self.link.callback(|_| Msg::Undo)>{"Undo"}</button>
                     <button onclick=self.link.callback(|_| Msg::Redo)>{"Redo"}</button>
+                    <label><input type="checkbox" checked=self.show_labels onclick=self.link.callback(move |_| Msg::ShowLabels(!show_labels)) />{"Room Labels"}</label>
+                    <button onclick=self.link.callback(|_| Msg::ExportSvg)>{"Export SVG"}</button>
+                    <button onclick=self.link.callback(|_| Msg::ExportBooklet)>{"Export Room Key"}</button>
+                    <button onclick=self.link.callback(|_| Msg::ExportPlayerSvg)>{"Export Player SVG"}</button>
                 </fieldset>
-                <RoomManager
-                    tile_materials=self.tile_materials.clone()
-                    rooms=self.rooms.clone()
-                    rooms_changed=self.link.callback(Msg::RoomsChanged)
-                    selected_room=self.selected_room
-                    selected_room_changed=self.link.callback(Msg::SelectedRoomChanged)
+                { if self.show_generator {
+                    html!(
+                        <GenerateDialog
+                            tile_materials=self.tile_materials.clone()
+                            sample=self.sample()
+                            rooms=self.document.rooms.clone()
+                            generated=self.link.callback(|(cells, rooms)| Msg::Generated(cells, rooms))
+                            closed=self.link.callback(|_| Msg::ShowGenerator(false))
+                        />
+                    )
+                } else {
+                    html!()
+                } }
+                <ScaleSettings scale=self.document.scale scale_changed=self.link.callback(Msg::ScaleChanged) />
+                <CampaignPanel
+                    campaign=self.campaign.store(&self.document)
+                    map_selected=self.link.callback(Msg::MapSelected)
+                    map_added=self.link.callback(|_| Msg::MapAdded)
+                    map_removed=self.link.callback(Msg::MapRemoved)
+                    map_renamed=self.link.callback(|(id, name)| Msg::MapRenamed(id, name))
+                    exit_removed=self.link.callback(Msg::ExitRemoved)
+                    exit_followed=self.link.callback(Msg::ExitFollowed)
+                    exit_from=self.exit_from
+                    save=self.link.callback(|_| Msg::SaveCampaign)
+                    load=self.link.callback(Msg::LoadCampaign)
                 />
-                <svg width={self.grid_size * width} height={self.grid_size * height} ref=self.node_ref.clone()
+                <LevelsPanel
+                    levels=self.document.levels()
+                    current=self.document.level
+                    level_selected=self.link.callback(Msg::LevelSelected)
+                    level_added=self.link.callback(|_| Msg::LevelAdded)
+                    level_removed=self.link.callback(Msg::LevelRemoved)
+                    level_renamed=self.link.callback(|(index, name)| Msg::LevelRenamed(index, name))
+                    show_below=self.show_level_below
+                    show_below_changed=self.link.callback(Msg::ShowLevelBelow)
+                    stairs=self.document.stairs.clone()
+                    stair_removed=self.link.callback(Msg::StairRemoved)
+                    stair_kind=self.stair_kind
+                    stair_kind_changed=self.link.callback(Msg::StairKindChanged)
+                    stair_from=self.stair_from
+                    export=self.link.callback(|_| Msg::ExportLevels)
+                />
+                <IsoPanel
+                    settings=self.iso_settings
+                    settings_changed=self.link.callback(Msg::IsoSettingsChanged)
+                    square=shape == GridShape::Square
+                    export=self.link.callback(|_| Msg::ExportIsoSvg)
+                />
+                <FogPanel
+                    mode=self.fog_mode
+                    mode_changed=self.link.callback(Msg::FogModeChanged)
+                    player_view=self.player_view
+                    player_view_changed=self.link.callback(Msg::PlayerView)
+                    fog_set=self.link.callback(Msg::FogSet)
+                    vision=self.vision_settings
+                    vision_changed=self.link.callback(Msg::VisionChanged)
+                />
+                { if self.player_view {
+                    html!()
+                } else {
+                    html!(
+                        <RoomManager
+                            tile_materials=self.tile_materials.clone()
+                            rooms=self.document.rooms.clone()
+                            rooms_changed=self.link.callback(Msg::RoomsChanged)
+                            selected_room=self.selected_room
+                            selected_room_changed=self.link.callback(Msg::SelectedRoomChanged)
+                            room_deleted=self.link.callback(Msg::RoomDeleted)
+                            rooms_merged=self.link.callback(|(from, into)| Msg::RoomsMerged(from, into))
+                            room_moved=self.link.callback(|(from, to)| Msg::RoomMoved(from, to))
+                            stocking_tables=self.campaign.stocking_tables.clone()
+                            stocking_tables_changed=self.link.callback(Msg::StockingTablesChanged)
+                        />
+                    )
+                } }
+                <svg width=width height=height ref=self.node_ref.clone()
                     onmouseleave=self.link.callback(|_| Msg::MouseLeave)
                     onmousemove=self.link.callback(Msg::MouseEvent)
                     onmousewheel=self.link.callback(Msg::MouseWheel)
-                    onmousedown=self.link.callback(Msg::MouseEvent)>
+                    onmousedown=self.link.callback(Msg::MouseEvent)
+                    onmouseup=self.link.callback(|_| Msg::MouseUp)>
                     <defs>
                         <TilePatterns tiles=self.tile_materials.clone() />
-                        <filter id="cellText" filterUnits="objectBoundingBox" primitiveUnits="userSpaceOnUse">
-                            <feColorMatrix type="hueRotate" values="180" in="BackgroundImage" result="colormatrix"/>
-                            <feColorMatrix type="saturate" values="50" in="colormatrix" />
-                        </filter>
+                        <clipPath id="tokenClip" clipPathUnits="objectBoundingBox">
+                            <circle cx="0.5" cy="0.5" r="0.5" />
+                        </clipPath>
                     </defs>
                     <g transform=format!("scale({})", self.grid_size)>
-                        { for self.cells.iter().map(|(row, col, cell)| {
-                            if let Some((index, material)) = cell.and_then(|index| self.rooms.get(index).map(|room| (index, &room.tile_material) )) {
-                                html!(<rect width="1" height="1" x=col y=row style=format!("fill:{}", material.url_reference()) />)
-                            } else {
-                                html!()
-                            }
-                        })}
-                        { self.cursor() }
+                        { match &projection {
+                            Some(projection) => self.isometric(&document, projection),
+                            None => self.plan(&document),
+                        } }
                     </g>
                 </svg>
+                { if square {
+                    html!(
+                        <TemplatesPanel
+                            settings=self.template_settings
+                            settings_changed=self.link.callback(Msg::TemplateSettingsChanged)
+                            max_feet=(self.document.cells.rows() as f64).hypot(self.document.cells.cols() as f64) * self.document.scale.feet_per_cell()
+                            templates=self.templates.clone()
+                            template_rotated=self.link.callback(|(index, direction)| Msg::TemplateRotated(index, direction))
+                            template_removed=self.link.callback(Msg::TemplateRemoved)
+                        />
+                    )
+                } else {
+                    html!()
+                } }
+                { self.gm_panels() }
             </>
         )
     }
@@ -0,0 +1,197 @@
+use crate::edges::Edges;
+use crate::grid::Grid;
+use crate::list::List;
+use crate::scale::Scale;
+use crate::vision;
+use crate::Cells;
+
+#[derive(Copy, Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum LightKind {
+    Torch,
+    Lantern,
+    Daylight,
+    Custom,
+}
+
+impl LightKind {
+    pub const ALL: [LightKind; 4] = [
+        LightKind::Torch,
+        LightKind::Lantern,
+        LightKind::Daylight,
+        LightKind::Custom,
+    ];
+}
+
+impl std::fmt::Display for LightKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self, f)
+    }
+}
+
+/// How brightly a cell is lit
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Illumination {
+    #[default]
+    Dark,
+    Dim,
+    Bright,
+}
+
+/// The light to place next
+#[derive(Clone, Debug, PartialEq)]
+pub struct LightSettings {
+    pub kind: LightKind,
+    /// Radius of bright light
+    pub bright_feet: f64,
+    /// How far dim light reaches beyond the bright light
+    pub dim_feet: f64,
+    pub colour: String,
+}
+
+impl LightSettings {
+    pub fn preset(kind: LightKind) -> Self {
+        let (bright_feet, dim_feet, colour) = match kind {
+            LightKind::Torch => (20.0, 20.0, "#ffb347"),
+            LightKind::Lantern => (30.0, 30.0, "#ffd27f"),
+            LightKind::Daylight => (60.0, 60.0, "#fffbe6"),
+            LightKind::Custom => (10.0, 10.0, "#ffffff"),
+        };
+
+        Self {
+            kind,
+            bright_feet,
+            dim_feet,
+            colour: String::from(colour),
+        }
+    }
+}
+
+impl Default for LightSettings {
+    fn default() -> Self {
+        Self::preset(LightKind::Torch)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Light {
+    pub kind: LightKind,
+    pub bright_feet: f64,
+    pub dim_feet: f64,
+    pub colour: String,
+    pub row: usize,
+    pub col: usize,
+}
+
+impl Light {
+    pub fn new(settings: &LightSettings, row: usize, col: usize) -> Self {
+        Self {
+            kind: settings.kind,
+            bright_feet: settings.bright_feet,
+            dim_feet: settings.dim_feet,
+            colour: settings.colour.clone(),
+            row,
+            col,
+        }
+    }
+
+    /// The furthest the light reaches, in cells
+    pub fn radius(&self, scale: &Scale) -> f64 {
+        (self.bright_feet + self.dim_feet) / scale.feet_per_cell()
+    }
+
+    /// How brightly the light shines on each cell, measuring distance with the scale's diagonal rule
+    pub fn illuminate(
+        &self,
+        cells: &Cells,
+        edges: &Edges,
+        scale: &Scale,
+        open_doors: bool,
+    ) -> Grid<Illumination> {
+        let visible = vision::visible_cells(cells, edges, (self.row, self.col), open_doors);
+        let feet_per_cell = scale.feet_per_cell();
+
+        visible.map_indexed(|row, col, visible| {
+            let feet = scale.path_squares(&[(self.row, self.col), (row, col)]) * feet_per_cell;
+            if !visible {
+                Illumination::Dark
+            } else if feet <= self.bright_feet {
+                Illumination::Bright
+            } else if feet <= self.bright_feet + self.dim_feet {
+                Illumination::Dim
+            } else {
+                Illumination::Dark
+            }
+        })
+    }
+}
+
+pub type Lights = List<Light>;
+
+/// The brightest light falling on each cell
+pub fn illumination(lights: &[Grid<Illumination>], rows: usize, cols: usize) -> Grid<Illumination> {
+    Grid::with_dimensions(rows, cols).map_indexed(|row, col, _: &Illumination| {
+        lights
+            .iter()
+            .filter_map(|light| light.get(row, col).copied())
+            .max()
+            .unwrap_or_default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edges::{Edge, EdgeId};
+    use crate::room::RoomIdGenerator;
+
+    /// A corridor one cell high, all of one room
+    fn corridor(cols: usize) -> Cells {
+        let id = RoomIdGenerator::default().generate();
+        Cells::with_dimensions(1, cols).map(|_| Some(id))
+    }
+
+    fn light(col: usize) -> Light {
+        let settings = LightSettings {
+            bright_feet: 10.0,
+            dim_feet: 10.0,
+            ..LightSettings::preset(LightKind::Custom)
+        };
+        Light::new(&settings, 0, col)
+    }
+
+    fn row(grid: &Grid<Illumination>) -> Vec<Illumination> {
+        grid.iter()
+            .map(|(_, _, illumination)| *illumination)
+            .collect()
+    }
+
+    #[test]
+    fn dim_light_reaches_beyond_the_bright_light() {
+        use Illumination::*;
+
+        let scale = Scale::default();
+        let lit = light(0).illuminate(&corridor(7), &Edges::new(), &scale, false);
+        assert_eq!(
+            row(&lit),
+            vec![Bright, Bright, Bright, Dim, Dim, Dark, Dark]
+        );
+        assert_eq!(light(0).radius(&scale), 4.0);
+    }
+
+    #[test]
+    fn walls_stop_the_light_and_the_brightest_light_wins() {
+        use Illumination::*;
+
+        let scale = Scale::default();
+        let cells = corridor(7);
+        let edges = Edges::new().set(EdgeId::left(0, 2), Some(Edge::Wall));
+        let lights = [
+            light(0).illuminate(&cells, &edges, &scale, false),
+            light(6).illuminate(&cells, &edges, &scale, false),
+        ];
+        assert_eq!(
+            row(&illumination(&lights, 1, 7)),
+            vec![Bright, Bright, Dim, Dim, Bright, Bright, Bright]
+        );
+    }
+}
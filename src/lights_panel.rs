@@ -0,0 +1,111 @@
+use yew::prelude::*;
+
+use crate::input::change_value;
+use crate::lights::{LightKind, LightSettings, Lights};
+
+pub struct LightsPanel {
+    props: Props,
+    link: ComponentLink<Self>,
+}
+
+pub enum Msg {
+    Kind(String),
+    Bright(String),
+    Dim(String),
+    Colour(String),
+}
+
+#[derive(Clone, Properties)]
+pub struct Props {
+    pub settings: LightSettings,
+    pub settings_changed: Callback<LightSettings>,
+    pub lights: Lights,
+    pub lights_changed: Callback<Lights>,
+    pub show_lighting: bool,
+    pub show_lighting_changed: Callback<bool>,
+}
+
+fn feet(value: &str) -> Option<f64> {
+    value.parse().ok().filter(|value: &f64| *value >= 0.0)
+}
+
+impl Component for LightsPanel {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        Self { props, link }
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        let mut settings = self.props.settings.clone();
+
+        match msg {
+            Msg::Kind(kind) => {
+                if let Some(kind) = LightKind::ALL.iter().find(|k| k.to_string() == kind) {
+                    settings = LightSettings::preset(*kind);
+                }
+            }
+            Msg::Bright(value) => match feet(&value) {
+                Some(bright_feet) => settings.bright_feet = bright_feet,
+                None => return true,
+            },
+            Msg::Dim(value) => match feet(&value) {
+                Some(dim_feet) => settings.dim_feet = dim_feet,
+                None => return true,
+            },
+            Msg::Colour(colour) => settings.colour = colour,
+        }
+
+        self.props.settings_changed.emit(settings);
+
+        false
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.props = props;
+        true
+    }
+
+    fn view(&self) -> Html {
+        let settings = &self.props.settings;
+        let preset = settings.kind != LightKind::Custom;
+        let show_lighting = self.props.show_lighting;
+
+        html!(
+            <fieldset>
+                <legend>{"Lights"}</legend>
+                <label class="inline">
+                    <input type="checkbox" checked=show_lighting onclick=self.props.show_lighting_changed.reform(move |_| !show_lighting) />
+                    {"Show Lighting"}
+                </label>
+                <div>
+                    <select onchange=self.link.callback(|data| Msg::Kind(change_value(data)))>
+                        { for LightKind::ALL.iter().map(|kind| html!(
+                            <option selected=*kind == settings.kind>{ kind.to_string() }</option>
+                        )) }
+                    </select>
+                    {" bright "}
+                    <input type="number" min="0" step="5" value=settings.bright_feet.to_string() disabled=preset onchange=self.link.callback(|data| Msg::Bright(change_value(data))) />
+                    {" ft, dim "}
+                    <input type="number" min="0" step="5" value=settings.dim_feet.to_string() disabled=preset onchange=self.link.callback(|data| Msg::Dim(change_value(data))) />
+                    {" ft more "}
+                    <input type="color" value=settings.colour.clone() onchange=self.link.callback(|data| Msg::Colour(change_value(data))) />
+                </div>
+                <table>
+                    { for self.props.lights.iter().enumerate().map(|(index, light)| {
+                        let lights = self.props.lights.clone();
+                        html!(
+                            <tr>
+                                <td style=format!("border-left:0.5em solid {}", light.colour)>{ light.kind.to_string() }</td>
+                                <td>{ format!("{}/{} ft", light.bright_feet, light.dim_feet) }</td>
+                                <td>{ format!("row {}, column {}", light.row + 1, light.col + 1) }</td>
+                                <td><button onclick=self.props.lights_changed.reform(move |_| lights.remove(index).unwrap_or_else(|| lights.clone()))>{"Remove"}</button></td>
+                            </tr>
+                        )
+                    }) }
+                </table>
+            </fieldset>
+        )
+    }
+}
@@ -0,0 +1,392 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::document::Document;
+use crate::edges::Edge;
+use crate::levels::Place;
+use crate::room::{RoomId, Rooms};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Problem {
+    /// The room's cells form more than one separate area
+    DisconnectedRoom {
+        room: RoomId,
+        regions: usize,
+        place: Place,
+    },
+    EmptyRoom {
+        room: RoomId,
+    },
+    NoEntrance,
+    UnreachableRoom {
+        room: RoomId,
+        place: Place,
+    },
+    DoorToNowhere {
+        place: Place,
+    },
+    MissingRoom {
+        place: Place,
+    },
+    IsolatedCell {
+        room: RoomId,
+        place: Place,
+    },
+}
+
+fn room_name(rooms: &Rooms, id: RoomId) -> String {
+    match rooms.get(id) {
+        Some(room) if !room.name.is_empty() => format!("Room {} ({})", room.label, room.name),
+        Some(room) => format!("Room {}", room.label),
+        None => String::from("Unknown room"),
+    }
+}
+
+impl Problem {
+    /// The level and cell to show when the problem is selected
+    pub fn place(&self) -> Option<Place> {
+        match self {
+            Problem::DisconnectedRoom { place, .. }
+            | Problem::UnreachableRoom { place, .. }
+            | Problem::DoorToNowhere { place }
+            | Problem::MissingRoom { place }
+            | Problem::IsolatedCell { place, .. } => Some(*place),
+            Problem::EmptyRoom { .. } | Problem::NoEntrance => None,
+        }
+    }
+
+    pub fn room(&self) -> Option<RoomId> {
+        match self {
+            Problem::DisconnectedRoom { room, .. }
+            | Problem::EmptyRoom { room }
+            | Problem::UnreachableRoom { room, .. }
+            | Problem::IsolatedCell { room, .. } => Some(*room),
+            Problem::NoEntrance | Problem::DoorToNowhere { .. } | Problem::MissingRoom { .. } => {
+                None
+            }
+        }
+    }
+
+    pub fn describe(&self, rooms: &Rooms) -> String {
+        match self {
+            Problem::DisconnectedRoom { room, regions, .. } => format!(
+                "{} is split into {} separate areas",
+                room_name(rooms, *room),
+                regions
+            ),
+            Problem::EmptyRoom { room } => format!("{} has no cells", room_name(rooms, *room)),
+            Problem::NoEntrance => String::from("No room is marked as an entrance"),
+            Problem::UnreachableRoom { room, .. } => format!(
+                "{} cannot be reached from an entrance",
+                room_name(rooms, *room)
+            ),
+            Problem::DoorToNowhere { .. } => String::from("Door opens onto empty space"),
+            Problem::MissingRoom { .. } => {
+                String::from("Cell belongs to a room that does not exist")
+            }
+            Problem::IsolatedCell { room, .. } => {
+                format!("{} has an isolated single cell", room_name(rooms, *room))
+            }
+        }
+    }
+}
+
+pub fn lint(document: &Document) -> Vec<Problem> {
+    let levels = document.levels();
+    let rooms = &document.rooms;
+    let shape = document.scale.shape;
+    let mut problems = Vec::new();
+
+    let cell_room = |place: Place| {
+        levels
+            .get(place.level)
+            .and_then(|level| level.cells.get(place.row, place.col).copied().flatten())
+    };
+    let neighbours = |place: Place| {
+        shape
+            .neighbours((place.row, place.col))
+            .into_iter()
+            .map(move |(row, col)| Place { row, col, ..place })
+    };
+
+    // Where the stairs from each end lead
+    let mut stairs: BTreeMap<Place, Vec<Place>> = BTreeMap::new();
+    for stair in document.stairs.iter() {
+        stairs.entry(stair.from).or_default().push(stair.to);
+        stairs.entry(stair.to).or_default().push(stair.from);
+    }
+    let stairs_from = |place: Place| stairs.get(&place).into_iter().flatten().copied();
+
+    // Connected areas of each room, ignoring walls
+    let mut regions: BTreeMap<RoomId, Vec<Vec<Place>>> = BTreeMap::new();
+    let mut visited = BTreeSet::new();
+    for (index, level) in levels.iter().enumerate() {
+        for (row, col, cell) in level.cells.iter() {
+            let room = match cell {
+                Some(room) => *room,
+                None => continue,
+            };
+            let place = Place {
+                level: index,
+                row,
+                col,
+            };
+
+            if !rooms.contains(room) {
+                problems.push(Problem::MissingRoom { place });
+            }
+
+            if !visited.insert(place) {
+                continue;
+            }
+
+            let mut region = Vec::new();
+            let mut stack = vec![place];
+            while let Some(place) = stack.pop() {
+                region.push(place);
+                for next in neighbours(place).chain(stairs_from(place)) {
+                    if cell_room(next) == Some(room) && visited.insert(next) {
+                        stack.push(next);
+                    }
+                }
+            }
+
+            regions.entry(room).or_default().push(region);
+        }
+    }
+
+    for (id, _) in rooms.iter() {
+        match regions.get(&id) {
+            None => problems.push(Problem::EmptyRoom { room: id }),
+            Some(room_regions) => {
+                if room_regions.len() > 1 {
+                    problems.push(Problem::DisconnectedRoom {
+                        room: id,
+                        regions: room_regions.len(),
+                        place: room_regions[1][0],
+                    });
+                }
+
+                // A stray cell split off from the rest of its room, or a lone cell with nothing painted around it
+                for region in room_regions.iter().filter(|region| region.len() == 1) {
+                    let place = region[0];
+                    if room_regions.len() > 1
+                        || neighbours(place)
+                            .chain(stairs_from(place))
+                            .all(|next| cell_room(next).is_none())
+                    {
+                        problems.push(Problem::IsolatedCell { room: id, place });
+                    }
+                }
+            }
+        }
+    }
+
+    // Reachability from entrances, through doors, open boundaries and stairs but not walls
+    let entrances = rooms
+        .iter()
+        .filter(|(_, room)| room.entrance)
+        .map(|(id, _)| id)
+        .collect::<BTreeSet<_>>();
+
+    if entrances.is_empty() {
+        if !regions.is_empty() {
+            problems.push(Problem::NoEntrance);
+        }
+    } else {
+        let mut reached = BTreeSet::new();
+        let mut stack = regions
+            .iter()
+            .filter(|(room, _)| entrances.contains(room))
+            .flat_map(|(_, room_regions)| room_regions.iter().flatten().copied())
+            .collect::<Vec<_>>();
+        reached.extend(stack.iter().copied());
+
+        while let Some(place) = stack.pop() {
+            let edges = match levels.get(place.level) {
+                Some(level) => &level.edges,
+                None => continue,
+            };
+            let open = neighbours(place)
+                .filter(|next| !edges.is_walled((place.row, place.col), (next.row, next.col)));
+            for next in open.chain(stairs_from(place)) {
+                if cell_room(next).is_some() && reached.insert(next) {
+                    stack.push(next);
+                }
+            }
+        }
+
+        for (id, _) in rooms.iter() {
+            if let Some(room_regions) = regions.get(&id) {
+                let places = room_regions.iter().flatten();
+                if !places.clone().any(|place| reached.contains(place)) {
+                    if let Some(place) = places.copied().next() {
+                        problems.push(Problem::UnreachableRoom { room: id, place });
+                    }
+                }
+            }
+        }
+    }
+
+    for (index, level) in levels.iter().enumerate() {
+        let place = |(row, col): (usize, usize)| Place {
+            level: index,
+            row,
+            col,
+        };
+        for (id, edge) in level.edges.iter() {
+            if edge == Edge::Wall {
+                continue;
+            }
+
+            let (before, after) = id.cells();
+            if before.map(place).and_then(cell_room).is_none() || cell_room(place(after)).is_none()
+            {
+                problems.push(Problem::DoorToNowhere {
+                    place: place(before.unwrap_or(after)),
+                });
+            }
+        }
+    }
+
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edges::EdgeId;
+    use crate::hex::{GridShape, Orientation};
+    use crate::levels::{Stair, StairKind};
+    use crate::room::Room;
+    use crate::scale::Scale;
+
+    /// A 3 by 3 document with an entrance room and a second room, each painted on the given cells
+    fn document(
+        shape: GridShape,
+        entrance: &[(usize, usize)],
+        other: &[(usize, usize)],
+    ) -> Document {
+        let material = crate::test_support::materials().as_ref()[0].clone();
+        let (rooms, a) = Rooms::new().push(Room {
+            entrance: true,
+            ..Room::new(material.clone(), "1".into())
+        });
+        let (rooms, b) = rooms.push(Room::new(material, "2".into()));
+        let scale = Scale {
+            shape,
+            ..Scale::default()
+        };
+
+        let mut document = Document::new(3, 3, rooms, scale);
+        for (&(row, col), id) in entrance
+            .iter()
+            .map(|cell| (cell, a))
+            .chain(other.iter().map(|cell| (cell, b)))
+        {
+            document.cells = document.cells.set(row, col, Some(id)).unwrap();
+        }
+        document
+    }
+
+    fn place(row: usize, col: usize) -> Place {
+        Place { level: 0, row, col }
+    }
+
+    #[test]
+    fn rooms_joined_only_at_a_corner_are_split_on_squares_but_not_on_hexes() {
+        let square = document(GridShape::Square, &[(0, 1), (1, 0)], &[(0, 0)]);
+        assert!(lint(&square)
+            .iter()
+            .any(|problem| matches!(problem, Problem::DisconnectedRoom { regions: 2, .. })));
+
+        // Odd rows of pointy-top hexes sit half a hex to the right, between the two cells above them
+        let hex = document(
+            GridShape::Hex(Orientation::PointyTop),
+            &[(0, 1), (1, 0)],
+            &[(0, 0)],
+        );
+        assert_eq!(lint(&hex), vec![]);
+    }
+
+    #[test]
+    fn walls_cut_rooms_off_from_the_entrance_but_doors_do_not() {
+        let document = document(GridShape::Square, &[(0, 0), (1, 0)], &[(0, 1), (1, 1)]);
+        let walled = Document {
+            edges: document
+                .edges
+                .set(EdgeId::left(0, 1), Some(Edge::Wall))
+                .set(EdgeId::left(1, 1), Some(Edge::Wall)),
+            ..document.clone()
+        };
+        let other = document.rooms.ids().nth(1).unwrap();
+        assert_eq!(
+            lint(&walled),
+            vec![Problem::UnreachableRoom {
+                room: other,
+                place: place(0, 1)
+            }]
+        );
+
+        let door = Document {
+            edges: walled.edges.set(EdgeId::left(1, 1), Some(Edge::Door)),
+            ..walled
+        };
+        assert_eq!(lint(&door), vec![]);
+    }
+
+    #[test]
+    fn doors_must_open_onto_rooms_on_both_sides() {
+        let document = document(GridShape::Square, &[(0, 0), (1, 0)], &[]);
+        let document = Document {
+            edges: document.edges.set(EdgeId::left(0, 1), Some(Edge::Door)),
+            ..document
+        };
+        let empty = document.rooms.ids().nth(1).unwrap();
+        assert_eq!(
+            lint(&document),
+            vec![
+                Problem::EmptyRoom { room: empty },
+                Problem::DoorToNowhere { place: place(0, 0) }
+            ]
+        );
+    }
+
+    #[test]
+    fn stairs_lead_to_rooms_on_other_levels() {
+        let document = document(GridShape::Square, &[(0, 0)], &[]);
+        let other = document.rooms.ids().nth(1).unwrap();
+        let mut lower = document.add_level().switch_level(1).unwrap();
+        lower.cells = lower.cells.set(2, 2, Some(other)).unwrap();
+        let document = lower.switch_level(0).unwrap();
+
+        let lower_place = Place {
+            level: 1,
+            row: 2,
+            col: 2,
+        };
+        assert_eq!(
+            lint(&document),
+            vec![
+                Problem::IsolatedCell {
+                    room: document.rooms.ids().next().unwrap(),
+                    place: place(0, 0)
+                },
+                Problem::IsolatedCell {
+                    room: other,
+                    place: lower_place
+                },
+                Problem::UnreachableRoom {
+                    room: other,
+                    place: lower_place
+                }
+            ]
+        );
+
+        let document = document.add_stair(Stair {
+            kind: StairKind::Ladder,
+            from: place(0, 0),
+            to: lower_place,
+        });
+        assert_eq!(lint(&document), vec![]);
+    }
+}
@@ -3,7 +3,8 @@
 use std::fmt::Debug;
 use std::rc::Rc;
 
-#[derive(Clone)]
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
 pub struct List<T>(Rc<Vec<T>>);
 
 impl<T> List<T> {
@@ -11,6 +12,14 @@ impl<T> List<T> {
         Self(Rc::new(Vec::new()))
     }
 
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = &T> {
         self.0.iter()
     }
@@ -36,6 +45,25 @@ impl<T: Clone> List<T> {
 
         Self(Rc::new(new_items))
     }
+
+    pub fn insert(&self, index: usize, value: T) -> Option<Self> {
+        let mut new_items = Vec::with_capacity(self.0.len() + 1);
+
+        new_items.extend_from_slice(self.0.get(0..index)?);
+        new_items.push(value);
+        new_items.extend_from_slice(self.0.get(index..self.0.len())?);
+
+        Some(Self(Rc::new(new_items)))
+    }
+
+    pub fn remove(&self, index: usize) -> Option<Self> {
+        let mut new_items = Vec::with_capacity(self.0.len());
+
+        new_items.extend_from_slice(self.0.get(0..index)?);
+        new_items.extend_from_slice(self.0.get((index + 1)..self.0.len())?);
+
+        Some(Self(Rc::new(new_items)))
+    }
 }
 
 impl<T: Clone + PartialEq> List<T> {
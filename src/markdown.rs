@@ -0,0 +1,96 @@
+use pulldown_cmark::{html, Event, Parser, Tag};
+use yew::prelude::*;
+
+/// Schemes links and images may use. Destinations without a scheme are relative, and also allowed.
+const SAFE_SCHEMES: [&str; 3] = ["http", "https", "mailto"];
+
+/// Whether a link or image destination is safe to put in the page
+fn is_safe_url(url: &str) -> bool {
+    // Browsers ignore whitespace and control characters in URLs, so "java\tscript:" is still a script
+    let url: String = url
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .collect();
+
+    match url.find(&[':', '/', '?', '#'][..]) {
+        Some(end) if url[end..].starts_with(':') => SAFE_SCHEMES
+            .iter()
+            .any(|scheme| url[..end].eq_ignore_ascii_case(scheme)),
+        _ => true,
+    }
+}
+
+/// Render Markdown to an HTML string. Raw HTML in the source is escaped rather than passed through, and links and
+/// images with unsafe URLs, such as `javascript:`, are dropped, keeping their text.
+pub fn to_html(source: &str) -> String {
+    let parser = Parser::new(source).filter_map(|event| match event {
+        Event::Html(text) => Some(Event::Text(text)),
+        Event::Start(Tag::Link(_, ref url, _))
+        | Event::End(Tag::Link(_, ref url, _))
+        | Event::Start(Tag::Image(_, ref url, _))
+        | Event::End(Tag::Image(_, ref url, _))
+            if !is_safe_url(url) =>
+        {
+            None
+        }
+        event => Some(event),
+    });
+
+    let mut output = String::new();
+    html::push_html(&mut output, parser);
+    output
+}
+
+pub fn render(source: &str) -> Html {
+    let element = web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.create_element("div").ok());
+
+    if let Some(element) = element {
+        element.set_class_name("markdown");
+        element.set_inner_html(&to_html(source));
+        Html::VRef(element.into())
+    } else {
+        html!(<pre>{ source }</pre>)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn raw_html_is_escaped() {
+        let html = to_html("<script>alert(1)</script> and <img src=x onerror=alert(1)>");
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("<img"));
+        assert!(html.contains("&lt;script&gt;"));
+    }
+
+    #[test]
+    fn unsafe_links_and_images_are_dropped() {
+        for source in &[
+            "[x](javascript:alert(1))",
+            "[x](JavaScript:alert(1))",
+            "[x](<java\tscript:alert(1)>)",
+            "[x](data:text/html,<script>alert(1)</script>)",
+            "![x](javascript:alert(1))",
+            "[x]\n\n[x]: vbscript:msgbox(1)",
+        ] {
+            let html = to_html(source);
+            assert!(!html.contains("href"), "{}", html);
+            assert!(!html.contains("src"), "{}", html);
+            assert!(html.contains('x'), "{}", html);
+        }
+    }
+
+    #[test]
+    fn safe_links_and_images_are_kept() {
+        assert!(to_html("[x](https://example.com/a?b#c)")
+            .contains("href=\"https://example.com/a?b#c\""));
+        assert!(to_html("[x](mailto:gm@example.com)").contains("href=\"mailto:gm@example.com\""));
+        assert!(to_html("[x](rooms/2.html)").contains("href=\"rooms/2.html\""));
+        assert!(to_html("[x](#room-2)").contains("href=\"#room-2\""));
+        assert!(to_html("![map](/maps/a:b.png)").contains("src=\"/maps/a:b.png\""));
+    }
+}
@@ -0,0 +1,589 @@
+//! Maze generation into a rectangle of the map, with a choice of algorithms that give mazes of different character.
+
+use crate::edges::{Edge, EdgeId, Edges};
+use crate::hex::GridShape;
+use crate::rng::Rng;
+use crate::room::RoomId;
+use crate::Cells;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MazeAlgorithm {
+    /// Long winding passages with few branches
+    Backtracker,
+    /// Lots of short dead ends branching off
+    Prim,
+    /// Evenly spread branches
+    Kruskal,
+    /// Built a row at a time, so it works for mazes of any length
+    Eller,
+}
+
+impl MazeAlgorithm {
+    pub const ALL: [MazeAlgorithm; 4] = [
+        MazeAlgorithm::Backtracker,
+        MazeAlgorithm::Prim,
+        MazeAlgorithm::Kruskal,
+        MazeAlgorithm::Eller,
+    ];
+}
+
+impl std::fmt::Display for MazeAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            MazeAlgorithm::Backtracker => "Recursive backtracker",
+            MazeAlgorithm::Prim => "Prim's",
+            MazeAlgorithm::Kruskal => "Kruskal's",
+            MazeAlgorithm::Eller => "Eller's",
+        })
+    }
+}
+
+/// How the maze's walls are drawn
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MazeWalls {
+    /// Walls are empty cells between the corridors
+    Cells,
+    /// Every cell is corridor, with walls on the edges between them
+    Edges,
+}
+
+impl MazeWalls {
+    pub const ALL: [MazeWalls; 2] = [MazeWalls::Cells, MazeWalls::Edges];
+}
+
+impl std::fmt::Display for MazeWalls {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            MazeWalls::Cells => "Empty cells",
+            MazeWalls::Edges => "Walls on edges",
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MazeSettings {
+    pub seed: u64,
+    pub algorithm: MazeAlgorithm,
+    /// Percentage of dead ends to open up into loops
+    pub braid_percent: usize,
+    pub walls: MazeWalls,
+}
+
+impl Default for MazeSettings {
+    fn default() -> Self {
+        Self {
+            seed: 1,
+            algorithm: MazeAlgorithm::Backtracker,
+            braid_percent: 0,
+            walls: MazeWalls::Cells,
+        }
+    }
+}
+
+/// Which maze cells have passages between them
+struct Maze {
+    rows: usize,
+    cols: usize,
+    /// Passage to the cell on the right, for each cell
+    right: Vec<bool>,
+    /// Passage to the cell below, for each cell
+    down: Vec<bool>,
+}
+
+impl Maze {
+    fn new(rows: usize, cols: usize) -> Self {
+        Self {
+            rows,
+            cols,
+            right: vec![false; rows * cols],
+            down: vec![false; rows * cols],
+        }
+    }
+
+    fn index(&self, (row, col): (usize, usize)) -> usize {
+        row * self.cols + col
+    }
+
+    /// Mazes are carved between square cells, so only squares' neighbours are used
+    fn neighbours(&self, cell: (usize, usize)) -> Vec<(usize, usize)> {
+        GridShape::Square
+            .neighbours(cell)
+            .into_iter()
+            .filter(|&(row, col)| row < self.rows && col < self.cols)
+            .collect()
+    }
+
+    /// The passage flag between two adjacent cells
+    fn passage(&mut self, a: (usize, usize), b: (usize, usize)) -> &mut bool {
+        let first = a.min(b);
+        let index = self.index(first);
+        if a.0 == b.0 {
+            &mut self.right[index]
+        } else {
+            &mut self.down[index]
+        }
+    }
+
+    fn connect(&mut self, a: (usize, usize), b: (usize, usize)) {
+        *self.passage(a, b) = true;
+    }
+
+    fn is_connected(&self, a: (usize, usize), b: (usize, usize)) -> bool {
+        let index = self.index(a.min(b));
+        if a.0 == b.0 {
+            self.right[index]
+        } else {
+            self.down[index]
+        }
+    }
+
+    fn exits(&self, cell: (usize, usize)) -> usize {
+        self.neighbours(cell)
+            .into_iter()
+            .filter(|neighbour| self.is_connected(cell, *neighbour))
+            .count()
+    }
+
+    fn cells(&self) -> impl Iterator<Item = (usize, usize)> {
+        let cols = self.cols;
+        (0..self.rows * cols).map(move |index| (index / cols, index % cols))
+    }
+
+    fn random_cell(&self, rng: &mut Rng) -> (usize, usize) {
+        (rng.below(self.rows), rng.below(self.cols))
+    }
+
+    /// Wander from cell to unvisited cell, going back the way it came when stuck
+    fn backtracker(&mut self, rng: &mut Rng) {
+        let mut visited = vec![false; self.rows * self.cols];
+        let start = self.random_cell(rng);
+        visited[self.index(start)] = true;
+        let mut stack = vec![start];
+
+        while let Some(&cell) = stack.last() {
+            let unvisited: Vec<_> = self
+                .neighbours(cell)
+                .into_iter()
+                .filter(|neighbour| !visited[self.index(*neighbour)])
+                .collect();
+
+            match rng.choose(&unvisited) {
+                Some(&next) => {
+                    self.connect(cell, next);
+                    visited[self.index(next)] = true;
+                    stack.push(next);
+                }
+                None => {
+                    stack.pop();
+                }
+            }
+        }
+    }
+
+    /// Grow the maze outwards from a cell, joining a random cell on its edge each time
+    fn prim(&mut self, rng: &mut Rng) {
+        let mut in_maze = vec![false; self.rows * self.cols];
+        let mut in_frontier = vec![false; self.rows * self.cols];
+        let mut frontier = vec![self.random_cell(rng)];
+
+        while !frontier.is_empty() {
+            let cell = frontier.swap_remove(rng.below(frontier.len()));
+            let neighbours = self.neighbours(cell);
+
+            let inside: Vec<_> = neighbours
+                .iter()
+                .copied()
+                .filter(|neighbour| in_maze[self.index(*neighbour)])
+                .collect();
+            if let Some(&joined) = rng.choose(&inside) {
+                self.connect(cell, joined);
+            }
+            in_maze[self.index(cell)] = true;
+
+            for neighbour in neighbours {
+                let index = self.index(neighbour);
+                if !in_maze[index] && !in_frontier[index] {
+                    in_frontier[index] = true;
+                    frontier.push(neighbour);
+                }
+            }
+        }
+    }
+
+    /// Knock down walls in a random order, as long as they separate parts that aren't joined yet
+    fn kruskal(&mut self, rng: &mut Rng) {
+        let mut walls: Vec<_> = self
+            .cells()
+            .flat_map(|(row, col)| vec![((row, col), (row, col + 1)), ((row, col), (row + 1, col))])
+            .filter(|(_, (row, col))| *row < self.rows && *col < self.cols)
+            .collect();
+        rng.shuffle(&mut walls);
+
+        let mut parents: Vec<usize> = (0..self.rows * self.cols).collect();
+        fn root(parents: &mut [usize], mut index: usize) -> usize {
+            while parents[index] != index {
+                parents[index] = parents[parents[index]];
+                index = parents[index];
+            }
+            index
+        }
+
+        for (a, b) in walls {
+            let (root_a, root_b) = (
+                root(&mut parents, self.index(a)),
+                root(&mut parents, self.index(b)),
+            );
+            if root_a != root_b {
+                parents[root_a] = root_b;
+                self.connect(a, b);
+            }
+        }
+    }
+
+    /// Join cells along each row into sets, then carry every set down to the next row at least once
+    fn eller(&mut self, rng: &mut Rng) {
+        let mut sets: Vec<Option<usize>> = vec![None; self.cols];
+        let mut next_set = 0;
+
+        for row in 0..self.rows {
+            for set in sets.iter_mut().filter(|set| set.is_none()) {
+                *set = Some(next_set);
+                next_set += 1;
+            }
+
+            // The last row joins everything left apart
+            let last = row + 1 == self.rows;
+            for col in 0..self.cols - 1 {
+                if sets[col] != sets[col + 1] && (last || rng.chance(0.5)) {
+                    self.connect((row, col), (row, col + 1));
+                    let (from, into) = (sets[col + 1], sets[col]);
+                    for set in sets.iter_mut().filter(|set| **set == from) {
+                        *set = into;
+                    }
+                }
+            }
+
+            if last {
+                break;
+            }
+
+            let mut below = vec![None; self.cols];
+            let mut seen = Vec::new();
+            for set in sets.iter().copied() {
+                if seen.contains(&set) {
+                    continue;
+                }
+                seen.push(set);
+
+                let members: Vec<_> = (0..self.cols).filter(|col| sets[*col] == set).collect();
+                let certain = *rng.choose(&members).expect("every set has a member");
+                for col in members {
+                    if col == certain || rng.chance(0.5) {
+                        self.connect((row, col), (row + 1, col));
+                        below[col] = set;
+                    }
+                }
+            }
+            sets = below;
+        }
+    }
+
+    /// Open up a share of the dead ends into loops, preferring to join two dead ends together
+    fn braid(&mut self, percent: usize, rng: &mut Rng) {
+        let mut dead_ends: Vec<_> = self.cells().filter(|cell| self.exits(*cell) == 1).collect();
+        rng.shuffle(&mut dead_ends);
+
+        let chance = percent.min(100) as f64 / 100.0;
+        for cell in dead_ends {
+            // An earlier dead end may have been joined to this one
+            if self.exits(cell) != 1 || !rng.chance(chance) {
+                continue;
+            }
+
+            let closed: Vec<_> = self
+                .neighbours(cell)
+                .into_iter()
+                .filter(|neighbour| !self.is_connected(cell, *neighbour))
+                .collect();
+            let dead_ends: Vec<_> = closed
+                .iter()
+                .copied()
+                .filter(|neighbour| self.exits(*neighbour) == 1)
+                .collect();
+            let options = if dead_ends.is_empty() {
+                closed
+            } else {
+                dead_ends
+            };
+
+            if let Some(&neighbour) = rng.choose(&options) {
+                self.connect(cell, neighbour);
+            }
+        }
+    }
+
+    fn generate(rows: usize, cols: usize, settings: &MazeSettings) -> Self {
+        let mut maze = Self::new(rows, cols);
+        let mut rng = Rng::new(settings.seed);
+
+        match settings.algorithm {
+            MazeAlgorithm::Backtracker => maze.backtracker(&mut rng),
+            MazeAlgorithm::Prim => maze.prim(&mut rng),
+            MazeAlgorithm::Kruskal => maze.kruskal(&mut rng),
+            MazeAlgorithm::Eller => maze.eller(&mut rng),
+        }
+        maze.braid(settings.braid_percent, &mut rng);
+
+        maze
+    }
+}
+
+/// Fill the `rows` by `cols` rectangle at `top`, `left` with a maze of `room` corridors, replacing the cells and
+/// edges inside it. Returns `None` if the rectangle is too small to hold a maze.
+pub fn fill(
+    settings: &MazeSettings,
+    (top, left, rows, cols): (usize, usize, usize, usize),
+    cells: &Cells,
+    edges: &Edges,
+    room: RoomId,
+) -> Option<(Cells, Edges)> {
+    let (rows, cols) = (
+        rows.min(cells.rows().saturating_sub(top)),
+        cols.min(cells.cols().saturating_sub(left)),
+    );
+    let inside = |(row, col): (usize, usize)| {
+        (top..top + rows).contains(&row) && (left..left + cols).contains(&col)
+    };
+
+    // Edges between two cells of the rectangle are replaced, those around it are kept
+    let kept = edges.iter().filter(|(id, _)| match id.cells() {
+        (Some(a), b) => !inside(a) || !inside(b),
+        (None, _) => true,
+    });
+
+    match settings.walls {
+        MazeWalls::Cells => {
+            // Corridors run through every other cell, with a wall cell between and around them
+            let (maze_rows, maze_cols) = (rows.saturating_sub(1) / 2, cols.saturating_sub(1) / 2);
+            if maze_rows == 0 || maze_cols == 0 {
+                return None;
+            }
+            let maze = Maze::generate(maze_rows, maze_cols, settings);
+
+            let open = |row: usize, col: usize| {
+                let (maze_row, maze_col) = (row / 2, col / 2);
+                if maze_row >= maze_rows || maze_col >= maze_cols {
+                    return false;
+                }
+                match (row % 2, col % 2) {
+                    (1, 1) => true,
+                    (1, 0) => {
+                        maze_col > 0
+                            && maze.is_connected((maze_row, maze_col - 1), (maze_row, maze_col))
+                    }
+                    (0, 1) => {
+                        maze_row > 0
+                            && maze.is_connected((maze_row - 1, maze_col), (maze_row, maze_col))
+                    }
+                    _ => false,
+                }
+            };
+
+            let cells = cells.map_indexed(|row, col, cell| {
+                if inside((row, col)) {
+                    Some(room).filter(|_| open(row - top, col - left))
+                } else {
+                    *cell
+                }
+            });
+            Some((cells, kept.collect()))
+        }
+        MazeWalls::Edges => {
+            if rows == 0 || cols == 0 {
+                return None;
+            }
+            let maze = Maze::generate(rows, cols, settings);
+
+            let mut walls = Vec::new();
+            for (row, col) in maze.cells() {
+                let cell = (top + row, left + col);
+                if row == 0 {
+                    walls.push(EdgeId::top(cell.0, cell.1));
+                }
+                if col == 0 {
+                    walls.push(EdgeId::left(cell.0, cell.1));
+                }
+                if row + 1 == rows || !maze.is_connected((row, col), (row + 1, col)) {
+                    walls.push(EdgeId::bottom(cell.0, cell.1));
+                }
+                if col + 1 == cols || !maze.is_connected((row, col), (row, col + 1)) {
+                    walls.push(EdgeId::right(cell.0, cell.1));
+                }
+            }
+
+            let cells = cells.map_indexed(|row, col, cell| {
+                if inside((row, col)) {
+                    Some(room)
+                } else {
+                    *cell
+                }
+            });
+            let edges = kept
+                .chain(walls.into_iter().map(|id| (id, Edge::Wall)))
+                .collect();
+            Some((cells, edges))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::room::{Room, Rooms};
+    use crate::test_support::*;
+
+    /// Whether every cell can be reached from the first, going through passages
+    fn is_perfect(maze: &Maze) -> bool {
+        let mut reached = vec![false; maze.rows * maze.cols];
+        let mut stack = vec![(0, 0)];
+        while let Some(cell) = stack.pop() {
+            if std::mem::replace(&mut reached[maze.index(cell)], true) {
+                continue;
+            }
+            stack.extend(
+                maze.neighbours(cell)
+                    .into_iter()
+                    .filter(|neighbour| maze.is_connected(cell, *neighbour)),
+            );
+        }
+
+        let passages = maze
+            .right
+            .iter()
+            .chain(&maze.down)
+            .filter(|open| **open)
+            .count();
+        reached.iter().all(|reached| *reached) && passages == maze.rows * maze.cols - 1
+    }
+
+    fn room() -> RoomId {
+        let material = materials().as_ref()[0].clone();
+        Rooms::new().push(Room::new(material, String::from("1"))).1
+    }
+
+    #[test]
+    fn every_algorithm_makes_a_perfect_maze() {
+        for algorithm in MazeAlgorithm::ALL.iter().copied() {
+            for seed in 0..10 {
+                let settings = MazeSettings {
+                    seed,
+                    algorithm,
+                    ..MazeSettings::default()
+                };
+                for &(rows, cols) in &[(1, 1), (1, 7), (6, 1), (9, 13)] {
+                    let maze = Maze::generate(rows, cols, &settings);
+                    assert!(
+                        is_perfect(&maze),
+                        "{} seed {} {}x{}",
+                        algorithm,
+                        seed,
+                        rows,
+                        cols
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn same_seed_gives_same_maze() {
+        for algorithm in MazeAlgorithm::ALL.iter().copied() {
+            let settings = MazeSettings {
+                seed: 5,
+                algorithm,
+                braid_percent: 50,
+                ..MazeSettings::default()
+            };
+            let (a, b) = (
+                Maze::generate(8, 8, &settings),
+                Maze::generate(8, 8, &settings),
+            );
+            assert_eq!((a.right, a.down), (b.right, b.down), "{}", algorithm);
+        }
+    }
+
+    #[test]
+    fn full_braiding_removes_every_dead_end() {
+        for algorithm in MazeAlgorithm::ALL.iter().copied() {
+            let settings = MazeSettings {
+                algorithm,
+                braid_percent: 100,
+                ..MazeSettings::default()
+            };
+            let maze = Maze::generate(10, 10, &settings);
+            assert!(
+                maze.cells().all(|cell| maze.exits(cell) > 1),
+                "{}",
+                algorithm
+            );
+        }
+    }
+
+    #[test]
+    fn fills_only_the_rectangle() {
+        let cells = Cells::with_dimensions(12, 12);
+        let room = room();
+
+        let (filled, _) = fill(
+            &MazeSettings::default(),
+            (2, 3, 7, 9),
+            &cells,
+            &Edges::new(),
+            room,
+        )
+        .unwrap();
+        assert!(is_connected(&filled));
+        for (row, col, cell) in filled.iter().filter(|(_, _, cell)| cell.is_some()) {
+            assert_eq!(*cell, Some(room));
+            assert!(
+                (3..8).contains(&row) && (4..11).contains(&col),
+                "{} {}",
+                row,
+                col
+            );
+        }
+        // A 3 by 4 maze of corridors
+        assert_eq!(
+            filled.iter().filter(|(_, _, cell)| cell.is_some()).count(),
+            3 * 4 + 3 * 4 - 1
+        );
+
+        let (filled, edges) = fill(
+            &MazeSettings {
+                walls: MazeWalls::Edges,
+                ..MazeSettings::default()
+            },
+            (2, 3, 4, 5),
+            &cells,
+            &Edges::new(),
+            room,
+        )
+        .unwrap();
+        assert_eq!(
+            filled.iter().filter(|(_, _, cell)| cell.is_some()).count(),
+            20
+        );
+        // The outline, plus the inner edges that aren't passages
+        let inner_edges = 3 * 5 + 4 * 4;
+        assert_eq!(edges.iter().count(), 2 * (4 + 5) + inner_edges - (20 - 1));
+
+        assert!(fill(
+            &MazeSettings::default(),
+            (0, 0, 2, 9),
+            &cells,
+            &Edges::new(),
+            room
+        )
+        .is_none());
+    }
+}
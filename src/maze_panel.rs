@@ -0,0 +1,106 @@
+use yew::prelude::*;
+
+use crate::input::change_value;
+use crate::maze::{MazeAlgorithm, MazeSettings, MazeWalls};
+use crate::rng;
+
+pub struct MazePanel {
+    props: Props,
+    link: ComponentLink<Self>,
+}
+
+pub enum Msg {
+    Algorithm(String),
+    Walls(String),
+    Braid(String),
+    Seed(String),
+    RandomSeed,
+}
+
+#[derive(Clone, Properties)]
+pub struct Props {
+    pub settings: MazeSettings,
+    pub settings_changed: Callback<MazeSettings>,
+    /// Whether there's a selection to fill
+    pub selection: bool,
+    pub generate: Callback<()>,
+}
+
+impl Component for MazePanel {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        Self { props, link }
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        let mut settings = self.props.settings;
+
+        match msg {
+            Msg::Algorithm(algorithm) => {
+                if let Some(algorithm) = MazeAlgorithm::ALL
+                    .iter()
+                    .find(|a| a.to_string() == algorithm)
+                {
+                    settings.algorithm = *algorithm;
+                }
+            }
+            Msg::Walls(walls) => {
+                if let Some(walls) = MazeWalls::ALL.iter().find(|w| w.to_string() == walls) {
+                    settings.walls = *walls;
+                }
+            }
+            Msg::Braid(value) => match value.parse().ok().filter(|percent| *percent <= 100) {
+                Some(braid_percent) => settings.braid_percent = braid_percent,
+                None => return true,
+            },
+            Msg::Seed(seed) => settings.seed = rng::parse_seed(&seed),
+            Msg::RandomSeed => settings.seed = (js_sys::Math::random() * u32::MAX as f64) as u64,
+        }
+
+        self.props.settings_changed.emit(settings);
+
+        false
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.props = props;
+        true
+    }
+
+    fn view(&self) -> Html {
+        let settings = self.props.settings;
+
+        html!(
+            <fieldset>
+                <legend>{"Maze"}</legend>
+                <div>
+                    <select onchange=self.link.callback(|data| Msg::Algorithm(change_value(data)))>
+                        { for MazeAlgorithm::ALL.iter().map(|algorithm| html!(
+                            <option selected=*algorithm == settings.algorithm>{ algorithm.to_string() }</option>
+                        )) }
+                    </select>
+                    <select onchange=self.link.callback(|data| Msg::Walls(change_value(data)))>
+                        { for MazeWalls::ALL.iter().map(|walls| html!(
+                            <option selected=*walls == settings.walls>{ walls.to_string() }</option>
+                        )) }
+                    </select>
+                </div>
+                <label>
+                    {"Dead ends opened into loops "}
+                    <input type="number" min="0" max="100" step="10" value=settings.braid_percent.to_string() onchange=self.link.callback(|data| Msg::Braid(change_value(data))) />
+                    {"%"}
+                </label>
+                <label>
+                    {"Seed"}
+                    <input type="text" value=settings.seed.to_string() onchange=self.link.callback(|data| Msg::Seed(change_value(data))) />
+                    <button onclick=self.link.callback(|_| Msg::RandomSeed)>{"Random"}</button>
+                </label>
+                <div>
+                    <button disabled=!self.props.selection onclick=self.props.generate.reform(|_| ())>{"Fill Selection With Maze"}</button>
+                </div>
+            </fieldset>
+        )
+    }
+}
@@ -0,0 +1,63 @@
+use yew::prelude::*;
+
+use crate::scale::{self, Scale};
+
+/// A ruler path from cell to cell, as (row, col). The last point follows the cursor.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Measurement {
+    waypoints: Vec<(usize, usize)>,
+    end: (usize, usize),
+}
+
+impl Measurement {
+    pub fn new(start: (usize, usize)) -> Self {
+        Self {
+            waypoints: vec![start],
+            end: start,
+        }
+    }
+
+    pub fn last_waypoint(&self) -> (usize, usize) {
+        self.waypoints[self.waypoints.len() - 1]
+    }
+
+    pub fn add_waypoint(&mut self, cell: (usize, usize)) {
+        self.waypoints.push(cell);
+        self.end = cell;
+    }
+
+    pub fn move_end(&mut self, cell: (usize, usize)) {
+        self.end = cell;
+    }
+
+    pub fn points(&self) -> Vec<(usize, usize)> {
+        let mut points = self.waypoints.clone();
+        if self.end != self.last_waypoint() {
+            points.push(self.end);
+        }
+        points
+    }
+
+    pub fn view(&self, scale: &Scale) -> Html {
+        let points = self.points();
+        let squares = scale.path_squares(&points);
+        let centre = |cell: (usize, usize)| scale.shape.centre(cell);
+        let (end_x, end_y) = centre(self.end);
+
+        html!(
+            <g pointer-events="none">
+                <polyline
+                    points=points.iter().map(|point| { let (x, y) = centre(*point); format!("{},{}", x, y) }).collect::<Vec<_>>().join(" ")
+                    fill="none" stroke="crimson" stroke-width="0.1" stroke-linejoin="round" />
+                { for self.waypoints.iter().map(|point| {
+                    let (x, y) = centre(*point);
+                    html!(<circle cx=x cy=y r="0.15" fill="crimson" />)
+                }) }
+                <text x=end_x + 0.6 y=end_y - 0.3 font-family="sans-serif" font-size="0.5" font-weight="bold"
+                    fill="crimson" stroke="white" stroke-width="0.12" paint-order="stroke">
+                    { format!("{} {} ({})", scale::format_number(squares), scale.shape.cell_name(), scale.format_length(squares)) }
+                </text>
+            </g>
+        )
+    }
+}
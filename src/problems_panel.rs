@@ -0,0 +1,53 @@
+use yew::prelude::*;
+
+use crate::lint::Problem;
+use crate::room::Rooms;
+
+pub struct ProblemsPanel {
+    props: Props,
+}
+
+pub enum Msg {}
+
+#[derive(Clone, Properties)]
+pub struct Props {
+    pub problems: Vec<Problem>,
+    pub rooms: Rooms,
+    pub problem_selected: Callback<Problem>,
+}
+
+impl Component for ProblemsPanel {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(props: Self::Properties, _link: ComponentLink<Self>) -> Self {
+        Self { props }
+    }
+
+    fn update(&mut self, _msg: Self::Message) -> ShouldRender {
+        false
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.props = props;
+        true
+    }
+
+    fn view(&self) -> Html {
+        html!(
+            <fieldset>
+                <legend>{ format!("Problems ({})", self.props.problems.len()) }</legend>
+                <ul class="problems">
+                    { for self.props.problems.iter().map(|problem| {
+                        let selected = problem.clone();
+                        html!(
+                            <li onclick=self.props.problem_selected.reform(move |_| selected.clone())>
+                                { problem.describe(&self.props.rooms) }
+                            </li>
+                        )
+                    }) }
+                </ul>
+            </fieldset>
+        )
+    }
+}
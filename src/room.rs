@@ -1,8 +1,172 @@
+use crate::list::List;
 use crate::tile;
 
-#[derive(Clone, Debug, PartialEq)]
+/// Identifies a room independently of its position in the room list.
+#[derive(
+    Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize,
+)]
+#[serde(transparent)]
+pub struct RoomId(usize);
+
+impl std::fmt::Display for RoomId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl std::str::FromStr for RoomId {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Self)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct RoomIdGenerator {
+    next: usize,
+}
+
+impl RoomIdGenerator {
+    pub fn generate(&mut self) -> RoomId {
+        let id = RoomId(self.next);
+        self.next += 1;
+        id
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct Room {
     pub tile_material: tile::Material,
+    pub name: String,
+    pub label: String,
+    pub colour: String,
+    pub description: String,
+    pub gm_notes: String,
+    pub entrance: bool,
+    /// Left out of the player view even where the fog has been revealed
+    pub hidden: bool,
+}
+
+impl Room {
+    pub fn new(tile_material: tile::Material, label: String) -> Self {
+        Self {
+            tile_material,
+            name: String::new(),
+            label,
+            colour: String::from("#ffffff"),
+            description: String::new(),
+            gm_notes: String::new(),
+            entrance: false,
+            hidden: false,
+        }
+    }
+
+    /// The label and name together, as the room is headed in the room key
+    pub(crate) fn heading(&self) -> String {
+        match (self.label.is_empty(), self.name.is_empty()) {
+            (false, false) => format!("{}. {}", self.label, self.name),
+            (false, true) => self.label.clone(),
+            (true, false) => self.name.clone(),
+            (true, true) => String::from("Unnamed room"),
+        }
+    }
 }
 
-pub type Rooms = crate::list::List<Room>;
+/// The rooms of a map, keyed by [`RoomId`] and kept in display order.
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Rooms {
+    ids: RoomIdGenerator,
+    rooms: List<(RoomId, Room)>,
+}
+
+impl Rooms {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.rooms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rooms.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (RoomId, &Room)> {
+        self.rooms.iter().map(|(id, room)| (*id, room))
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = RoomId> + '_ {
+        self.rooms.iter().map(|(id, _)| *id)
+    }
+
+    pub fn first_id(&self) -> Option<RoomId> {
+        self.ids().next()
+    }
+
+    pub fn contains(&self, id: RoomId) -> bool {
+        self.index_of(id).is_some()
+    }
+
+    pub fn index_of(&self, id: RoomId) -> Option<usize> {
+        self.ids().position(|room_id| room_id == id)
+    }
+
+    /// Whether every room has its own id, and one the generator has already handed out, as rooms loaded from a file
+    /// might not
+    pub fn has_consistent_ids(&self) -> bool {
+        let mut seen = std::collections::BTreeSet::new();
+        self.ids().all(|id| id.0 < self.ids.next && seen.insert(id))
+    }
+
+    pub fn get(&self, id: RoomId) -> Option<&Room> {
+        self.rooms
+            .iter()
+            .find(|(room_id, _)| *room_id == id)
+            .map(|(_, room)| room)
+    }
+
+    /// Append a room, returning the new rooms and the id of the added room
+    pub fn push(&self, room: Room) -> (Self, RoomId) {
+        let mut ids = self.ids.clone();
+        let id = ids.generate();
+
+        (
+            Self {
+                ids,
+                rooms: self.rooms.push_back((id, room)),
+            },
+            id,
+        )
+    }
+
+    pub fn set(&self, id: RoomId, room: Room) -> Option<Self> {
+        let index = self.index_of(id)?;
+
+        Some(Self {
+            ids: self.ids.clone(),
+            rooms: self.rooms.set(index, (id, room))?,
+        })
+    }
+
+    pub fn remove(&self, id: RoomId) -> Option<Self> {
+        let index = self.index_of(id)?;
+
+        Some(Self {
+            ids: self.ids.clone(),
+            rooms: self.rooms.remove(index)?,
+        })
+    }
+
+    /// Move a room so that it ends up at position `index`
+    pub fn move_to(&self, id: RoomId, index: usize) -> Option<Self> {
+        let from = self.index_of(id)?;
+        let entry = self.rooms.get(from)?.clone();
+
+        Some(Self {
+            ids: self.ids.clone(),
+            rooms: self.rooms.remove(from)?.insert(index, entry)?,
+        })
+    }
+}
@@ -0,0 +1,360 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::adjacency;
+use crate::document::Document;
+use crate::room::RoomId;
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct Node {
+    pub id: RoomId,
+    pub label: String,
+    pub name: String,
+    pub cells: usize,
+    /// The label and name together, as in the room key
+    #[serde(skip)]
+    pub heading: String,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct Edge {
+    pub from: RoomId,
+    pub to: RoomId,
+    pub shared_edges: usize,
+}
+
+/// Rooms as nodes, joined by an edge wherever two rooms share a boundary that is not walled off, or a stair
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize)]
+pub struct RoomGraph {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+}
+
+impl RoomGraph {
+    pub fn new(document: &Document) -> Self {
+        let rooms = &document.rooms;
+        let cell_counts = adjacency::cell_counts(document);
+
+        let nodes = rooms
+            .iter()
+            .map(|(id, room)| Node {
+                id,
+                label: room.label.clone(),
+                name: room.name.clone(),
+                cells: cell_counts.get(&id).copied().unwrap_or(0),
+                heading: room.heading(),
+            })
+            .collect();
+
+        let edges = adjacency::shared_boundaries(document)
+            .into_iter()
+            .filter(|((from, to), _)| rooms.contains(*from) && rooms.contains(*to))
+            .map(|((from, to), shared_edges)| Edge {
+                from,
+                to,
+                shared_edges,
+            })
+            .collect();
+
+        Self { nodes, edges }
+    }
+
+    fn adjacency_lists(&self) -> Vec<Vec<usize>> {
+        let indices = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| (node.id, index))
+            .collect::<BTreeMap<_, _>>();
+
+        let mut lists = vec![Vec::new(); self.nodes.len()];
+        for edge in self.edges.iter() {
+            if let (Some(&from), Some(&to)) = (indices.get(&edge.from), indices.get(&edge.to)) {
+                lists[from].push(to);
+                lists[to].push(from);
+            }
+        }
+
+        lists
+    }
+
+    pub fn degree(&self, id: RoomId) -> usize {
+        self.edges
+            .iter()
+            .filter(|edge| edge.from == id || edge.to == id)
+            .count()
+    }
+
+    /// Rooms with exactly one neighbour
+    pub fn dead_ends(&self) -> Vec<RoomId> {
+        self.nodes
+            .iter()
+            .map(|node| node.id)
+            .filter(|id| self.degree(*id) == 1)
+            .collect()
+    }
+
+    /// The number of independent loops, i.e. edges minus nodes plus connected components
+    pub fn loops(&self) -> usize {
+        let lists = self.adjacency_lists();
+        let mut visited = vec![false; lists.len()];
+        let mut components = 0;
+
+        for start in 0..lists.len() {
+            if visited[start] {
+                continue;
+            }
+
+            components += 1;
+            visited[start] = true;
+            let mut stack = vec![start];
+            while let Some(node) = stack.pop() {
+                for &next in lists[node].iter() {
+                    if !visited[next] {
+                        visited[next] = true;
+                        stack.push(next);
+                    }
+                }
+            }
+        }
+
+        (self.edges.len() + components).saturating_sub(self.nodes.len())
+    }
+
+    /// Rooms whose removal would split the dungeon into more pieces
+    pub fn choke_points(&self) -> Vec<RoomId> {
+        struct Search<'a> {
+            lists: &'a [Vec<usize>],
+            discovered: Vec<Option<usize>>,
+            low: Vec<usize>,
+            is_choke_point: Vec<bool>,
+            time: usize,
+        }
+
+        impl Search<'_> {
+            fn visit(&mut self, node: usize, parent: Option<usize>) {
+                self.discovered[node] = Some(self.time);
+                self.low[node] = self.time;
+                self.time += 1;
+
+                let mut children = 0;
+                for &next in self.lists[node].iter() {
+                    if Some(next) == parent {
+                        continue;
+                    }
+
+                    if let Some(discovered) = self.discovered[next] {
+                        self.low[node] = self.low[node].min(discovered);
+                    } else {
+                        children += 1;
+                        self.visit(next, Some(node));
+                        self.low[node] = self.low[node].min(self.low[next]);
+
+                        if parent.is_some() && Some(self.low[next]) >= self.discovered[node] {
+                            self.is_choke_point[node] = true;
+                        }
+                    }
+                }
+
+                if parent.is_none() && children > 1 {
+                    self.is_choke_point[node] = true;
+                }
+            }
+        }
+
+        let lists = self.adjacency_lists();
+        let mut search = Search {
+            lists: &lists,
+            discovered: vec![None; lists.len()],
+            low: vec![0; lists.len()],
+            is_choke_point: vec![false; lists.len()],
+            time: 0,
+        };
+
+        for node in 0..lists.len() {
+            if search.discovered[node].is_none() {
+                search.visit(node, None);
+            }
+        }
+
+        self.nodes
+            .iter()
+            .zip(search.is_choke_point)
+            .filter(|(_, is_choke_point)| *is_choke_point)
+            .map(|(node, _)| node.id)
+            .collect()
+    }
+
+    /// Render as a Graphviz DOT undirected graph
+    pub fn to_dot(&self) -> String {
+        fn quote(text: &str) -> String {
+            format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+        }
+
+        let mut output = String::from("graph rooms {\n    node [shape=box];\n");
+
+        // Writing to a String never fails
+        for node in self.nodes.iter() {
+            let _ = writeln!(output, "    {} [label={}];", node.id, quote(&node.heading));
+        }
+
+        for edge in self.edges.iter() {
+            let _ = writeln!(
+                output,
+                "    {} -- {} [weight={}];",
+                edge.from, edge.to, edge.shared_edges
+            );
+        }
+
+        output.push_str("}\n");
+
+        output
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::levels::{Place, Stair, StairKind};
+    use crate::room::{Room, RoomIdGenerator, Rooms};
+    use crate::scale::Scale;
+
+    /// A graph of `count` rooms with the given edges between them, by index
+    fn graph(count: usize, edges: &[(usize, usize)]) -> (RoomGraph, Vec<RoomId>) {
+        let mut generator = RoomIdGenerator::default();
+        let ids: Vec<_> = (0..count).map(|_| generator.generate()).collect();
+
+        let graph = RoomGraph {
+            nodes: ids
+                .iter()
+                .map(|&id| Node {
+                    id,
+                    label: id.to_string(),
+                    name: String::new(),
+                    cells: 1,
+                    heading: id.to_string(),
+                })
+                .collect(),
+            edges: edges
+                .iter()
+                .map(|&(from, to)| Edge {
+                    from: ids[from],
+                    to: ids[to],
+                    shared_edges: 1,
+                })
+                .collect(),
+        };
+        (graph, ids)
+    }
+
+    #[test]
+    fn every_inner_room_of_a_chain_is_a_choke_point() {
+        let (chain, ids) = graph(4, &[(0, 1), (1, 2), (2, 3)]);
+        assert_eq!(chain.choke_points(), vec![ids[1], ids[2]]);
+        assert_eq!(chain.dead_ends(), vec![ids[0], ids[3]]);
+        assert_eq!(chain.loops(), 0);
+    }
+
+    #[test]
+    fn closing_a_loop_removes_its_choke_points() {
+        let (ring, _) = graph(4, &[(0, 1), (1, 2), (2, 3), (3, 0)]);
+        assert_eq!(ring.choke_points(), vec![]);
+        assert_eq!(ring.dead_ends(), vec![]);
+        assert_eq!(ring.loops(), 1);
+
+        // Two separate triangles joined through one room
+        let (bow_tie, ids) = graph(5, &[(0, 1), (1, 2), (2, 0), (2, 3), (3, 4), (4, 2)]);
+        assert_eq!(bow_tie.choke_points(), vec![ids[2]]);
+        assert_eq!(bow_tie.loops(), 2);
+    }
+
+    #[test]
+    fn separate_pieces_do_not_count_as_loops() {
+        let (pieces, _) = graph(4, &[(0, 1), (2, 3)]);
+        assert_eq!(pieces.loops(), 0);
+        assert_eq!(pieces.choke_points(), vec![]);
+    }
+
+    #[test]
+    fn dot_labels_are_room_key_headings() {
+        let material = crate::test_support::materials().as_ref()[0].clone();
+        let (rooms, red) = Rooms::new().push(Room {
+            name: String::from("The \"Red\" Room"),
+            ..Room::new(material.clone(), "1".into())
+        });
+        let (rooms, unnamed) = rooms.push(Room::new(material, String::new()));
+        let document = Document::new(1, 2, rooms, Scale::default());
+        let document = Document {
+            cells: document
+                .cells
+                .set(0, 0, Some(red))
+                .and_then(|cells| cells.set(0, 1, Some(unnamed)))
+                .unwrap(),
+            ..document
+        };
+
+        assert_eq!(
+            RoomGraph::new(&document).to_dot(),
+            format!(
+                concat!(
+                    "graph rooms {{\n",
+                    "    node [shape=box];\n",
+                    "    {0} [label=\"1. The \\\"Red\\\" Room\"];\n",
+                    "    {1} [label=\"Unnamed room\"];\n",
+                    "    {0} -- {1} [weight=1];\n",
+                    "}}\n"
+                ),
+                red, unnamed
+            )
+        );
+    }
+
+    #[test]
+    fn stairs_join_rooms_on_different_levels() {
+        let material = crate::test_support::materials().as_ref()[0].clone();
+        let (rooms, hall) = Rooms::new().push(Room::new(material.clone(), "1".into()));
+        let (rooms, cellar) = rooms.push(Room::new(material, "2".into()));
+        let document = Document::new(1, 2, rooms, Scale::default());
+        let upper = Document {
+            cells: document.cells.map(|_| Some(hall)),
+            ..document
+        };
+        let mut lower = upper.add_level().switch_level(1).unwrap();
+        lower.cells = lower.cells.set(0, 0, Some(cellar)).unwrap();
+        let document = lower.add_stair(Stair {
+            kind: StairKind::Stairs,
+            from: Place {
+                level: 0,
+                row: 0,
+                col: 1,
+            },
+            to: Place {
+                level: 1,
+                row: 0,
+                col: 0,
+            },
+        });
+
+        let graph = RoomGraph::new(&document);
+        assert_eq!(
+            graph
+                .nodes
+                .iter()
+                .map(|node| (node.id, node.cells))
+                .collect::<Vec<_>>(),
+            vec![(hall, 2), (cellar, 1)]
+        );
+        assert_eq!(
+            graph.edges,
+            vec![Edge {
+                from: hall,
+                to: cellar,
+                shared_edges: 1
+            }]
+        );
+    }
+}
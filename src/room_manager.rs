@@ -1,17 +1,35 @@
 use yew::prelude::*;
 use yew_components::Select;
 
-use crate::room::{Room, Rooms};
+use crate::input::change_value;
+use crate::markdown;
+use crate::room::{Room, RoomId, Rooms};
+use crate::stocking::StockingTables;
+use crate::stocking_panel::StockingPanel;
 use crate::tile;
 
 pub struct RoomManager {
     props: Props,
     link: ComponentLink<Self>,
+    dragging: Option<RoomId>,
+    merge_target: Option<RoomId>,
 }
 
 pub enum Msg {
     NewRoom,
-    RoomMaterialChanged(usize, tile::Material),
+    RoomMaterialChanged(RoomId, tile::Material),
+    RoomNameChanged(RoomId, String),
+    RoomLabelChanged(RoomId, String),
+    RoomColourChanged(RoomId, String),
+    RoomDescriptionChanged(RoomId, String),
+    RoomGmNotesChanged(RoomId, String),
+    RoomEntranceChanged(RoomId, bool),
+    RoomHiddenChanged(RoomId, bool),
+    DeleteRoom(RoomId),
+    MergeTargetChanged(Option<RoomId>),
+    MergeRoom(RoomId),
+    DragStart(RoomId),
+    Drop(usize),
 }
 
 #[derive(Clone, Properties)]
@@ -19,8 +37,60 @@ pub struct Props {
     pub tile_materials: tile::Materials,
     pub rooms: Rooms,
     pub rooms_changed: Callback<Rooms>,
-    pub selected_room: usize,
-    pub selected_room_changed: Callback<usize>,
+    pub selected_room: RoomId,
+    pub selected_room_changed: Callback<RoomId>,
+    pub room_deleted: Callback<RoomId>,
+    pub rooms_merged: Callback<(RoomId, RoomId)>,
+    pub room_moved: Callback<(RoomId, usize)>,
+    pub stocking_tables: StockingTables,
+    pub stocking_tables_changed: Callback<StockingTables>,
+}
+
+impl RoomManager {
+    fn update_room(&self, id: RoomId, f: impl FnOnce(&mut Room)) {
+        if let Some(mut room) = self.props.rooms.get(id).cloned() {
+            f(&mut room);
+            if let Some(new_rooms) = self.props.rooms.set(id, room) {
+                self.props.rooms_changed.emit(new_rooms);
+            }
+        }
+    }
+
+    fn details(&self) -> Html {
+        let id = self.props.selected_room;
+
+        let room = if let Some(room) = self.props.rooms.get(id) {
+            room
+        } else {
+            return html!();
+        };
+
+        let entrance = room.entrance;
+        let hidden = room.hidden;
+
+        html!(
+            <div class="room-details">
+                <label>{"Name"}<input type="text" value=room.name.clone() onchange=self.link.callback(move |data| Msg::RoomNameChanged(id, change_value(data))) /></label>
+                <label>{"Label"}<input type="text" value=room.label.clone() onchange=self.link.callback(move |data| Msg::RoomLabelChanged(id, change_value(data))) /></label>
+                <label>{"Colour"}<input type="color" value=room.colour.clone() onchange=self.link.callback(move |data| Msg::RoomColourChanged(id, change_value(data))) /></label>
+                <label class="inline"><input type="checkbox" checked=room.entrance onclick=self.link.callback(move |_| Msg::RoomEntranceChanged(id, !entrance)) />{"Entrance"}</label>
+                <label class="inline"><input type="checkbox" checked=room.hidden onclick=self.link.callback(move |_| Msg::RoomHiddenChanged(id, !hidden)) />{"Hidden from players"}</label>
+                <label>{"Description"}<textarea value=room.description.clone() onchange=self.link.callback(move |data| Msg::RoomDescriptionChanged(id, change_value(data))) /></label>
+                { markdown::render(&room.description) }
+                <label>{"GM Notes"}<textarea value=room.gm_notes.clone() onchange=self.link.callback(move |data| Msg::RoomGmNotesChanged(id, change_value(data))) /></label>
+                { markdown::render(&room.gm_notes) }
+                <div>
+                    <select onchange=self.link.callback(|data| Msg::MergeTargetChanged(change_value(data).parse().ok()))>
+                        <option value="" selected=self.merge_target.is_none()>{"Merge into..."}</option>
+                        { for self.props.rooms.iter().filter(|(other, _)| *other != id).map(|(other, room)| html!(
+                            <option value=other.to_string() selected=self.merge_target == Some(other)>{ format!("{} {}", room.label, room.name) }</option>
+                        )) }
+                    </select>
+                    <button disabled=self.merge_target.is_none() onclick=self.link.callback(move |_| Msg::MergeRoom(id))>{"Merge"}</button>
+                </div>
+            </div>
+        )
+    }
 }
 
 impl Component for RoomManager {
@@ -28,23 +98,50 @@ impl Component for RoomManager {
     type Properties = Props;
 
     fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
-        Self { props, link }
+        Self {
+            props,
+            link,
+            dragging: None,
+            merge_target: None,
+        }
     }
 
     fn update(&mut self, msg: Self::Message) -> ShouldRender {
         match msg {
-            Msg::NewRoom => self
-                .props
-                .rooms_changed
-                .emit(self.props.rooms.push_back(Room {
-                    tile_material: self.props.tile_materials.as_ref()[0].clone(),
-                })),
-            Msg::RoomMaterialChanged(index, material) => {
-                if let Some(mut room) = self.props.rooms.get(index).cloned() {
-                    room.tile_material = material;
-                    if let Some(new_rooms) = self.props.rooms.set(index, room) {
-                        self.props.rooms_changed.emit(new_rooms);
-                    }
+            Msg::NewRoom => {
+                let (new_rooms, _) = self.props.rooms.push(Room::new(
+                    self.props.tile_materials.as_ref()[0].clone(),
+                    (self.props.rooms.len() + 1).to_string(),
+                ));
+                self.props.rooms_changed.emit(new_rooms);
+            }
+            Msg::RoomMaterialChanged(id, material) => {
+                self.update_room(id, |room| room.tile_material = material)
+            }
+            Msg::RoomNameChanged(id, name) => self.update_room(id, |room| room.name = name),
+            Msg::RoomLabelChanged(id, label) => self.update_room(id, |room| room.label = label),
+            Msg::RoomColourChanged(id, colour) => self.update_room(id, |room| room.colour = colour),
+            Msg::RoomDescriptionChanged(id, description) => {
+                self.update_room(id, |room| room.description = description)
+            }
+            Msg::RoomGmNotesChanged(id, gm_notes) => {
+                self.update_room(id, |room| room.gm_notes = gm_notes)
+            }
+            Msg::RoomEntranceChanged(id, entrance) => {
+                self.update_room(id, |room| room.entrance = entrance)
+            }
+            Msg::RoomHiddenChanged(id, hidden) => self.update_room(id, |room| room.hidden = hidden),
+            Msg::DeleteRoom(id) => self.props.room_deleted.emit(id),
+            Msg::MergeTargetChanged(target) => self.merge_target = target,
+            Msg::MergeRoom(id) => {
+                if let Some(target) = self.merge_target.take() {
+                    self.props.rooms_merged.emit((id, target));
+                }
+            }
+            Msg::DragStart(id) => self.dragging = Some(id),
+            Msg::Drop(index) => {
+                if let Some(dragging) = self.dragging.take() {
+                    self.props.room_moved.emit((dragging, index));
                 }
             }
         }
@@ -53,11 +150,18 @@ impl Component for RoomManager {
     }
 
     fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        if props.selected_room != self.props.selected_room
+            || props.rooms.len() != self.props.rooms.len()
+        {
+            self.merge_target = None;
+        }
         self.props = props;
         true
     }
 
     fn view(&self) -> Html {
+        let can_delete = self.props.rooms.len() > 1;
+
         html!(
             <fieldset>
                 <legend>{"Rooms"}</legend>
@@ -65,28 +169,49 @@ impl Component for RoomManager {
                     <thead>
                         <tr>
                             <th/>
+                            <th>{"Name"}</th>
                             <th>{"Tile Material"}</th>
                             <th/>
+                            <th/>
                         </tr>
                     </thead>
                     <tbody>
-                        { for self.props.rooms.iter().enumerate().map(|(index, room)| {
-                            let class = if index == self.props.selected_room {
+                        { for self.props.rooms.iter().enumerate().map(|(index, (id, room))| {
+                            let class = if id == self.props.selected_room {
                                 "selected"
                             } else {
                                 ""
                             };
+                            let ondragstart = self.link.callback(move |ev: DragEvent| {
+                                if let Some(data) = ev.data_transfer() {
+                                    let _ = data.set_data("text/plain", &id.to_string());
+                                }
+                                Msg::DragStart(id)
+                            });
+                            let ondrop = self.link.callback(move |ev: DragEvent| {
+                                ev.prevent_default();
+                                Msg::Drop(index)
+                            });
                             html!(
-                                <tr class=class>
-                                    <td>{ index + 1 }</td>
-                                    <td><Select<tile::Material> selected=room.tile_material.clone() options=self.props.tile_materials.as_vec() on_change=self.link.callback(move |material| Msg::RoomMaterialChanged(index, material))/></td>
-                                    <td><button onclick=self.props.selected_room_changed.reform(move |_| index)>{"Select"}</button></td>
+                                <tr class=class draggable="true" ondragstart=ondragstart ondragover=Callback::from(|ev: DragEvent| ev.prevent_default()) ondrop=ondrop>
+                                    <td style=format!("border-left:0.5em solid {}", room.colour)>{ &room.label }</td>
+                                    <td>{ &room.name }</td>
+                                    <td><Select<tile::Material> selected=room.tile_material.clone() options=self.props.tile_materials.as_vec() on_change=self.link.callback(move |material| Msg::RoomMaterialChanged(id, material))/></td>
+                                    <td><button onclick=self.props.selected_room_changed.reform(move |_| id)>{"Select"}</button></td>
+                                    <td><button disabled=!can_delete onclick=self.link.callback(move |_| Msg::DeleteRoom(id))>{"Delete"}</button></td>
                                 </tr>
                             )
                         }) }
                     </tbody>
                 </table>
                 <button onclick=self.link.callback(|_| Msg::NewRoom)>{"Add Room"}</button>
+                <StockingPanel
+                    rooms=self.props.rooms.clone()
+                    rooms_changed=self.props.rooms_changed.clone()
+                    tables=self.props.stocking_tables.clone()
+                    tables_changed=self.props.stocking_tables_changed.clone()
+                />
+                { self.details() }
             </fieldset>
         )
     }
@@ -0,0 +1,218 @@
+use crate::hex::GridShape;
+
+#[derive(Copy, Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum Unit {
+    Feet,
+    Metres,
+}
+
+impl Unit {
+    pub fn abbreviation(&self) -> &'static str {
+        match self {
+            Unit::Feet => "ft",
+            Unit::Metres => "m",
+        }
+    }
+}
+
+/// How moving diagonally between cells is counted
+#[derive(Copy, Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum DiagonalRule {
+    /// Diagonals alternately cost one and two squares
+    Alternating,
+    Euclidean,
+    /// Diagonals cost one square
+    Chebyshev,
+}
+
+impl DiagonalRule {
+    pub const ALL: [DiagonalRule; 3] = [
+        DiagonalRule::Alternating,
+        DiagonalRule::Euclidean,
+        DiagonalRule::Chebyshev,
+    ];
+
+    /// The number of squares moved along a path through the given (row, col) cells.
+    /// Under the alternating rule the count of diagonals carries on from one segment to the next.
+    pub fn path_squares(&self, points: &[(usize, usize)]) -> f64 {
+        let mut straight = 0;
+        let mut diagonal = 0;
+        let mut euclidean = 0.0;
+
+        for segment in points.windows(2) {
+            let dy = (segment[0].0 as isize - segment[1].0 as isize).unsigned_abs();
+            let dx = (segment[0].1 as isize - segment[1].1 as isize).unsigned_abs();
+            let (long, short) = (dx.max(dy), dx.min(dy));
+            straight += long - short;
+            diagonal += short;
+            euclidean += ((dx * dx + dy * dy) as f64).sqrt();
+        }
+
+        match self {
+            DiagonalRule::Alternating => (straight + diagonal + diagonal / 2) as f64,
+            DiagonalRule::Euclidean => euclidean,
+            DiagonalRule::Chebyshev => (straight + diagonal) as f64,
+        }
+    }
+}
+
+impl std::fmt::Display for DiagonalRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            DiagonalRule::Alternating => "5-10-5",
+            DiagonalRule::Euclidean => "Euclidean",
+            DiagonalRule::Chebyshev => "Chebyshev",
+        })
+    }
+}
+
+/// The real-world size and shape of the map's cells. Settings missing from a saved campaign take their defaults.
+#[derive(Copy, Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Scale {
+    pub shape: GridShape,
+    pub unit: Unit,
+    pub per_square: f64,
+    /// For overland maps, the distance across one hex in miles. Overrides `unit` and `per_square` when set.
+    pub miles_per_hex: Option<f64>,
+    pub diagonal_rule: DiagonalRule,
+}
+
+impl Default for Scale {
+    fn default() -> Self {
+        Self {
+            shape: GridShape::Square,
+            unit: Unit::Feet,
+            per_square: 5.0,
+            miles_per_hex: None,
+            diagonal_rule: DiagonalRule::Alternating,
+        }
+    }
+}
+
+/// Format a measurement to at most one decimal place
+pub fn format_number(value: f64) -> String {
+    if (value - value.round()).abs() < 0.05 {
+        format!("{:.0}", value)
+    } else {
+        format!("{:.1}", value)
+    }
+}
+
+impl Scale {
+    /// The length of `squares` cells, in the map's units
+    pub fn length(&self, squares: f64) -> f64 {
+        squares * self.miles_per_hex.unwrap_or(self.per_square)
+    }
+
+    /// The area of `cells` cells, in the map's units squared
+    pub fn area(&self, cells: usize) -> f64 {
+        match self.miles_per_hex {
+            // The area of a hex measured across its flat sides
+            Some(miles) => cells as f64 * 3.0f64.sqrt() / 2.0 * miles * miles,
+            None => cells as f64 * self.per_square * self.per_square,
+        }
+    }
+
+    /// The length of one cell in feet, ignoring any overland scale
+    pub fn feet_per_cell(&self) -> f64 {
+        match self.unit {
+            Unit::Feet => self.per_square,
+            Unit::Metres => self.per_square / 0.3048,
+        }
+    }
+
+    pub fn unit_name(&self) -> &'static str {
+        if self.miles_per_hex.is_some() {
+            "mi"
+        } else {
+            self.unit.abbreviation()
+        }
+    }
+
+    /// The number of cells moved along a path of (row, col) cells: hex steps on a hex grid, or squares under the
+    /// map's diagonal rule
+    pub fn path_squares(&self, points: &[(usize, usize)]) -> f64 {
+        match self.shape {
+            GridShape::Square => self.diagonal_rule.path_squares(points),
+            GridShape::Hex(orientation) => points
+                .windows(2)
+                .map(|segment| orientation.distance(segment[0], segment[1]))
+                .sum::<usize>() as f64,
+        }
+    }
+
+    pub fn format_length(&self, squares: f64) -> String {
+        format!(
+            "{} {}",
+            format_number(self.length(squares)),
+            self.unit_name()
+        )
+    }
+
+    pub fn format_area(&self, cells: usize) -> String {
+        format!(
+            "{} sq {}",
+            format_number(self.area(cells)),
+            self.unit_name()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diagonal_rules_count_a_diagonal_line_differently() {
+        let line = [(0, 0), (3, 3)];
+        assert_eq!(DiagonalRule::Alternating.path_squares(&line), 4.0);
+        assert_eq!(DiagonalRule::Chebyshev.path_squares(&line), 3.0);
+        assert_eq!(DiagonalRule::Euclidean.path_squares(&line), 18.0f64.sqrt());
+
+        // Straight moves cost the same under every rule
+        for rule in DiagonalRule::ALL.iter() {
+            assert_eq!(rule.path_squares(&[(2, 0), (2, 5)]), 5.0);
+        }
+    }
+
+    #[test]
+    fn lengths_and_areas_are_given_in_the_map_units() {
+        let metres = Scale {
+            unit: Unit::Metres,
+            per_square: 1.5,
+            ..Scale::default()
+        };
+        assert_eq!(metres.format_length(3.0), "4.5 m");
+        assert_eq!(metres.format_area(4), "9 sq m");
+
+        let overland = Scale {
+            miles_per_hex: Some(6.0),
+            ..metres
+        };
+        assert_eq!(overland.format_length(2.0), "12 mi");
+        assert_eq!(overland.format_area(1), "31.2 sq mi");
+    }
+
+    #[test]
+    fn alternating_diagonals_carry_on_across_waypoints() {
+        let scale = Scale::default();
+
+        // One diagonal square per waypoint costs 5, then 10, then 5 feet
+        let path = [(0, 0), (1, 1), (2, 2), (3, 3)];
+        let lengths: Vec<_> = (2..=path.len())
+            .map(|end| scale.length(scale.path_squares(&path[..end])))
+            .collect();
+        assert_eq!(lengths, vec![5.0, 15.0, 20.0]);
+        assert_eq!(scale.format_length(scale.path_squares(&path)), "20 ft");
+    }
+
+    #[test]
+    fn hex_paths_count_steps_between_hexes() {
+        let scale = Scale {
+            shape: GridShape::Hex(crate::hex::Orientation::PointyTop),
+            ..Scale::default()
+        };
+        assert_eq!(scale.path_squares(&[(0, 0), (0, 3), (3, 3)]), 6.0);
+    }
+}
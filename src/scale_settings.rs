@@ -0,0 +1,125 @@
+use yew::prelude::*;
+
+use crate::hex::GridShape;
+use crate::input::change_value;
+use crate::scale::{DiagonalRule, Scale, Unit};
+
+pub struct ScaleSettings {
+    props: Props,
+    link: ComponentLink<Self>,
+}
+
+pub enum Msg {
+    Shape(String),
+    Unit(String),
+    PerSquare(String),
+    Overland(bool),
+    MilesPerHex(String),
+    DiagonalRule(String),
+}
+
+#[derive(Clone, Properties)]
+pub struct Props {
+    pub scale: Scale,
+    pub scale_changed: Callback<Scale>,
+}
+
+fn positive(value: &str) -> Option<f64> {
+    value.parse().ok().filter(|value: &f64| *value > 0.0)
+}
+
+impl Component for ScaleSettings {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        Self { props, link }
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        let mut scale = self.props.scale;
+
+        match msg {
+            Msg::Shape(shape) => {
+                if let Some(shape) = GridShape::ALL.iter().find(|s| s.to_string() == shape) {
+                    scale.shape = *shape;
+                }
+            }
+            Msg::Unit(unit) => {
+                scale.unit = if unit == "m" {
+                    Unit::Metres
+                } else {
+                    Unit::Feet
+                }
+            }
+            Msg::PerSquare(value) => match positive(&value) {
+                Some(per_square) => scale.per_square = per_square,
+                None => return true,
+            },
+            Msg::Overland(overland) => {
+                scale.miles_per_hex = if overland { Some(6.0) } else { None }
+            }
+            Msg::MilesPerHex(value) => match positive(&value) {
+                Some(miles) => scale.miles_per_hex = Some(miles),
+                None => return true,
+            },
+            Msg::DiagonalRule(rule) => {
+                if let Some(rule) = DiagonalRule::ALL
+                    .iter()
+                    .find(|candidate| candidate.to_string() == rule)
+                {
+                    scale.diagonal_rule = *rule;
+                }
+            }
+        }
+
+        self.props.scale_changed.emit(scale);
+
+        false
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.props = props;
+        true
+    }
+
+    fn view(&self) -> Html {
+        let scale = self.props.scale;
+        let overland = scale.miles_per_hex.is_some();
+
+        html!(
+            <fieldset>
+                <legend>{"Scale"}</legend>
+                <label>
+                    {"Grid "}
+                    <select onchange=self.link.callback(|data| Msg::Shape(change_value(data)))>
+                        { for GridShape::ALL.iter().map(|shape| html!(
+                            <option selected=*shape == scale.shape>{ shape.to_string() }</option>
+                        )) }
+                    </select>
+                </label>
+                <label>
+                    {"Square size "}
+                    <input type="number" min="0" step="any" value=scale.per_square.to_string() disabled=overland onchange=self.link.callback(|data| Msg::PerSquare(change_value(data))) />
+                    <select disabled=overland onchange=self.link.callback(|data| Msg::Unit(change_value(data)))>
+                        <option value="ft" selected=scale.unit == Unit::Feet>{"feet"}</option>
+                        <option value="m" selected=scale.unit == Unit::Metres>{"metres"}</option>
+                    </select>
+                </label>
+                <label>
+                    <input type="checkbox" checked=overland onclick=self.link.callback(move |_| Msg::Overland(!overland)) />
+                    {"Overland, miles per hex "}
+                    <input type="number" min="0" step="any" value=scale.miles_per_hex.map(|miles| miles.to_string()).unwrap_or_default() disabled=!overland onchange=self.link.callback(|data| Msg::MilesPerHex(change_value(data))) />
+                </label>
+                <label>
+                    {"Diagonals "}
+                    <select disabled=scale.shape != GridShape::Square onchange=self.link.callback(|data| Msg::DiagonalRule(change_value(data)))>
+                        { for DiagonalRule::ALL.iter().map(|rule| html!(
+                            <option selected=*rule == scale.diagonal_rule>{ rule.to_string() }</option>
+                        )) }
+                    </select>
+                </label>
+            </fieldset>
+        )
+    }
+}
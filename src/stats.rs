@@ -0,0 +1,170 @@
+use std::collections::BTreeMap;
+
+use crate::document::Document;
+use crate::room::RoomId;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RoomStatistics {
+    pub cells: usize,
+    /// Number of cell edges between this room and anything else
+    pub perimeter: usize,
+    /// Inclusive bounds, as ((min row, min col), (max row, max col))
+    pub bounds: ((usize, usize), (usize, usize)),
+    /// Mean cell centre, as (x, y) in cells
+    pub centroid: (f64, f64),
+}
+
+impl RoomStatistics {
+    pub fn width(&self) -> usize {
+        (self.bounds.1).1 - (self.bounds.0).1 + 1
+    }
+
+    pub fn height(&self) -> usize {
+        (self.bounds.1).0 - (self.bounds.0).0 + 1
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Statistics {
+    pub rooms: BTreeMap<RoomId, RoomStatistics>,
+    pub painted: usize,
+    pub total: usize,
+    /// Cells painted with each material, by material name, in material order
+    pub materials: Vec<(String, usize)>,
+}
+
+impl Statistics {
+    /// Gather statistics in a single pass over the grid of each level. Bounds and centroids are taken over the cells on
+    /// every level, as if they were stacked.
+    pub fn new(document: &Document) -> Self {
+        let shape = document.scale.shape;
+        let mut rooms: BTreeMap<RoomId, RoomStatistics> = BTreeMap::new();
+        let mut sums: BTreeMap<RoomId, (f64, f64)> = BTreeMap::new();
+        let mut total = 0;
+        let mut painted = 0;
+
+        for level in document.levels().iter() {
+            let cells = &level.cells;
+            for (row, col, cell) in cells.iter() {
+                total += 1;
+
+                let room = match cell {
+                    Some(room) => *room,
+                    None => continue,
+                };
+
+                painted += 1;
+
+                let shared = shape
+                    .neighbours((row, col))
+                    .into_iter()
+                    .filter(|&(row, col)| cells.get(row, col) == Some(cell))
+                    .count();
+                let perimeter = shape.directions() - shared;
+
+                let statistics = rooms.entry(room).or_insert(RoomStatistics {
+                    cells: 0,
+                    perimeter: 0,
+                    bounds: ((row, col), (row, col)),
+                    centroid: (0.0, 0.0),
+                });
+                statistics.cells += 1;
+                statistics.perimeter += perimeter;
+                let ((min_row, min_col), (max_row, max_col)) = statistics.bounds;
+                statistics.bounds = (
+                    (min_row.min(row), min_col.min(col)),
+                    (max_row.max(row), max_col.max(col)),
+                );
+
+                let sum = sums.entry(room).or_insert((0.0, 0.0));
+                sum.0 += col as f64 + 0.5;
+                sum.1 += row as f64 + 0.5;
+            }
+        }
+
+        for (room, statistics) in rooms.iter_mut() {
+            let (x, y) = sums[room];
+            let count = statistics.cells as f64;
+            statistics.centroid = (x / count, y / count);
+        }
+
+        let mut materials: Vec<(String, usize)> = Vec::new();
+        for (id, room) in document.rooms.iter() {
+            let count = rooms.get(&id).map_or(0, |statistics| statistics.cells);
+            let name = room.tile_material.to_string();
+            match materials.iter_mut().find(|(material, _)| *material == name) {
+                Some((_, total)) => *total += count,
+                None => materials.push((name, count)),
+            }
+        }
+
+        Self {
+            rooms,
+            painted,
+            total,
+            materials,
+        }
+    }
+
+    pub fn empty_percentage(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            100.0 * (self.total - self.painted) as f64 / self.total as f64
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hex::{GridShape, Orientation};
+    use crate::room::{Room, Rooms};
+    use crate::scale::Scale;
+
+    fn document(shape: GridShape, cells: &[(usize, usize)]) -> (Document, RoomId) {
+        let materials = crate::test_support::materials();
+        let (rooms, id) = Rooms::new().push(Room::new(materials.as_ref()[0].clone(), "1".into()));
+        let (rooms, _) = rooms.push(Room::new(materials.as_ref()[1].clone(), "2".into()));
+        let scale = Scale {
+            shape,
+            ..Scale::default()
+        };
+
+        let mut document = Document::new(4, 4, rooms, scale);
+        for &(row, col) in cells {
+            document.cells = document.cells.set(row, col, Some(id)).unwrap();
+        }
+        (document, id)
+    }
+
+    #[test]
+    fn statistics_of_an_l_shaped_room() {
+        let (document, id) = document(GridShape::Square, &[(1, 1), (1, 2), (2, 1)]);
+        let statistics = Statistics::new(&document);
+
+        let room = &statistics.rooms[&id];
+        assert_eq!(room.cells, 3);
+        assert_eq!(room.perimeter, 8);
+        assert_eq!(room.bounds, ((1, 1), (2, 2)));
+        assert_eq!((room.width(), room.height()), (2, 2));
+        assert_eq!(room.centroid, (5.5 / 3.0, 5.5 / 3.0));
+
+        assert_eq!((statistics.painted, statistics.total), (3, 16));
+        assert_eq!(statistics.empty_percentage(), 81.25);
+        assert_eq!(
+            statistics.materials,
+            vec![(String::from("a"), 3), (String::from("b"), 0)]
+        );
+    }
+
+    #[test]
+    fn hexes_have_six_sides() {
+        let shape = GridShape::Hex(Orientation::FlatTop);
+        let (single, id) = document(shape, &[(1, 1)]);
+        assert_eq!(Statistics::new(&single).rooms[&id].perimeter, 6);
+
+        let (pair, id) = document(shape, &[(1, 1), (2, 1)]);
+        assert_eq!(Statistics::new(&pair).rooms[&id].perimeter, 10);
+    }
+}
@@ -0,0 +1,111 @@
+use yew::prelude::*;
+
+use crate::room::Rooms;
+use crate::scale::Scale;
+use crate::stats::Statistics;
+
+pub struct StatsPanel {
+    props: Props,
+}
+
+pub enum Msg {}
+
+#[derive(Clone, Properties)]
+pub struct Props {
+    pub statistics: Statistics,
+    pub rooms: Rooms,
+    pub scale: Scale,
+}
+
+impl StatsPanel {
+    fn materials(&self) -> Html {
+        let statistics = &self.props.statistics;
+        let most = statistics
+            .materials
+            .iter()
+            .map(|(_, count)| *count)
+            .max()
+            .unwrap_or(0)
+            .max(1);
+
+        html!(
+            <table>
+                { for statistics.materials.iter().map(|(material, count)| html!(
+                    <tr>
+                        <td>{ material }</td>
+                        <td><div class="bar" style=format!("width:{}em", 10 * count / most) /></td>
+                        <td>{ count }</td>
+                    </tr>
+                )) }
+            </table>
+        )
+    }
+}
+
+impl Component for StatsPanel {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(props: Self::Properties, _link: ComponentLink<Self>) -> Self {
+        Self { props }
+    }
+
+    fn update(&mut self, _msg: Self::Message) -> ShouldRender {
+        false
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.props = props;
+        true
+    }
+
+    fn view(&self) -> Html {
+        let statistics = &self.props.statistics;
+        let scale = &self.props.scale;
+
+        html!(
+            <fieldset>
+                <legend>{"Statistics"}</legend>
+                <table>
+                    <thead>
+                        <tr>
+                            <th/>
+                            <th>{"Cells"}</th>
+                            <th>{"Area"}</th>
+                            <th>{"Perimeter"}</th>
+                            <th>{"Bounds"}</th>
+                            <th>{"Centroid"}</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        { for self.props.rooms.iter().map(|(id, room)| {
+                            if let Some(room_statistics) = statistics.rooms.get(&id) {
+                                let ((row, col), _) = room_statistics.bounds;
+                                let (x, y) = room_statistics.centroid;
+                                html!(
+                                    <tr>
+                                        <td>{ &room.label }</td>
+                                        <td>{ room_statistics.cells }</td>
+                                        <td>{ scale.format_area(room_statistics.cells) }</td>
+                                        <td>{ scale.format_length(room_statistics.perimeter as f64) }</td>
+                                        <td>{ format!("{}×{} at ({}, {})", room_statistics.width(), room_statistics.height(), col, row) }</td>
+                                        <td>{ format!("({:.1}, {:.1})", x, y) }</td>
+                                    </tr>
+                                )
+                            } else {
+                                html!(
+                                    <tr>
+                                        <td>{ &room.label }</td>
+                                        <td>{ 0 }</td>
+                                    </tr>
+                                )
+                            }
+                        }) }
+                    </tbody>
+                </table>
+                <p>{ format!("Painted: {} cells ({}), {:.0}% empty", statistics.painted, scale.format_area(statistics.painted), statistics.empty_percentage()) }</p>
+                { self.materials() }
+            </fieldset>
+        )
+    }
+}
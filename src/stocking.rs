@@ -0,0 +1,462 @@
+//! Dungeon stocking in the style of the 1981 Basic rules: each room rolls for a monster, trap, special feature or
+//! nothing, then for treasure, with the details rolled on editable random tables.
+
+use crate::rng::Rng;
+use crate::room::{Room, Rooms};
+use crate::{bsp, caves};
+
+/// A dice expression such as `2d6`, `d20+1` or `3`, or a range such as `1-4` that gives each number an equal chance
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Dice {
+    Roll {
+        count: u32,
+        sides: u32,
+        modifier: i64,
+    },
+    Range(i64, i64),
+}
+
+impl std::str::FromStr for Dice {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s: String = s.chars().filter(|c| !c.is_whitespace()).collect();
+        let s = s.to_lowercase();
+
+        if let Some((count, rest)) = s.split_once('d') {
+            let count = if count.is_empty() {
+                1
+            } else {
+                count.parse().map_err(|_| ())?
+            };
+            let (sides, modifier) = match rest.find(['+', '-']) {
+                Some(index) => {
+                    let (sides, modifier) = rest.split_at(index);
+                    let modifier = modifier.trim_start_matches('+');
+                    (sides, modifier.parse().map_err(|_| ())?)
+                }
+                None => (rest, 0),
+            };
+            let sides = sides.parse().map_err(|_| ())?;
+
+            if count == 0 || sides == 0 || count > 100 {
+                return Err(());
+            }
+            return Ok(Dice::Roll {
+                count,
+                sides,
+                modifier,
+            });
+        }
+
+        let (low, high) = range(&s).ok_or(())?;
+        if high == i64::MAX {
+            return Err(());
+        }
+        Ok(Dice::Range(low, high))
+    }
+}
+
+impl Dice {
+    pub fn roll(&self, rng: &mut Rng) -> i64 {
+        match *self {
+            Dice::Roll {
+                count,
+                sides,
+                modifier,
+            } => {
+                let total: i64 = (0..count)
+                    .map(|_| rng.range(1, sides as usize) as i64)
+                    .sum();
+                total + modifier
+            }
+            Dice::Range(low, high) => low + rng.below((high - low + 1) as usize) as i64,
+        }
+    }
+}
+
+/// The numbers covered by a table entry's roll: `3`, `1-2`, or `12+` for 12 and up
+fn range(s: &str) -> Option<(i64, i64)> {
+    let s = s.trim();
+    if let Some(low) = s.strip_suffix('+') {
+        return Some((low.trim().parse().ok()?, i64::MAX));
+    }
+    // Skip the first character so that negative numbers aren't taken for ranges
+    match s.char_indices().skip(1).find(|(_, c)| *c == '-') {
+        Some((index, _)) => {
+            let (low, high) = (
+                s[..index].trim().parse().ok()?,
+                s[index + 1..].trim().parse().ok()?,
+            );
+            Some((low, high)).filter(|(low, high)| low <= high)
+        }
+        None => s.parse().ok().map(|value| (value, value)),
+    }
+}
+
+/// Roll any dice written in braces, so `{2d6} goblins` gives something like `7 goblins`
+fn roll_inline(text: &str, rng: &mut Rng) -> String {
+    let mut output = String::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find('{') {
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+        output.push_str(&rest[..start]);
+        match rest[start + 1..end].parse::<Dice>() {
+            Ok(dice) => output.push_str(&dice.roll(rng).to_string()),
+            Err(()) => output.push_str(&rest[start..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+
+    output.push_str(rest);
+    output
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TableEntry {
+    /// The numbers this entry is picked on, such as `3` or `1-2`
+    pub roll: String,
+    pub text: String,
+}
+
+impl TableEntry {
+    pub fn new(roll: &str, text: &str) -> Self {
+        Self {
+            roll: String::from(roll),
+            text: String::from(text),
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        range(&self.roll).is_some()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct RandomTable {
+    pub dice: String,
+    pub entries: Vec<TableEntry>,
+}
+
+impl RandomTable {
+    pub fn is_valid(&self) -> bool {
+        self.dice.parse::<Dice>().is_ok()
+    }
+
+    /// Roll on the table, with any dice in the result rolled too. Returns `None` if nothing on the table matches.
+    pub fn roll(&self, rng: &mut Rng) -> Option<String> {
+        let roll = self.dice.parse::<Dice>().ok()?.roll(rng);
+        let entry = self.entries.iter().find(|entry| {
+            range(&entry.roll).is_some_and(|(low, high)| (low..=high).contains(&roll))
+        })?;
+        Some(roll_inline(&entry.text, rng))
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TableKind {
+    Monsters,
+    Treasure,
+    Traps,
+    Dressing,
+}
+
+impl TableKind {
+    pub const ALL: [TableKind; 4] = [
+        TableKind::Monsters,
+        TableKind::Treasure,
+        TableKind::Traps,
+        TableKind::Dressing,
+    ];
+}
+
+impl std::fmt::Display for TableKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self, f)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct StockingTables {
+    pub monsters: RandomTable,
+    pub treasure: RandomTable,
+    pub traps: RandomTable,
+    pub dressing: RandomTable,
+}
+
+impl StockingTables {
+    pub fn get(&self, kind: TableKind) -> &RandomTable {
+        match kind {
+            TableKind::Monsters => &self.monsters,
+            TableKind::Treasure => &self.treasure,
+            TableKind::Traps => &self.traps,
+            TableKind::Dressing => &self.dressing,
+        }
+    }
+
+    pub fn get_mut(&mut self, kind: TableKind) -> &mut RandomTable {
+        match kind {
+            TableKind::Monsters => &mut self.monsters,
+            TableKind::Treasure => &mut self.treasure,
+            TableKind::Traps => &mut self.traps,
+            TableKind::Dressing => &mut self.dressing,
+        }
+    }
+}
+
+fn table(dice: &str, entries: &[(&str, &str)]) -> RandomTable {
+    RandomTable {
+        dice: String::from(dice),
+        entries: entries
+            .iter()
+            .map(|(roll, text)| TableEntry::new(roll, text))
+            .collect(),
+    }
+}
+
+impl Default for StockingTables {
+    fn default() -> Self {
+        Self {
+            monsters: table(
+                "1d8",
+                &[
+                    ("1", "{2d4} giant rats"),
+                    ("2", "{2d4} kobolds"),
+                    ("3", "{1d6} goblins"),
+                    ("4", "{1d4+1} skeletons"),
+                    ("5", "{1d3} zombies"),
+                    ("6", "{1d4} orcs"),
+                    ("7", "A giant spider"),
+                    ("8", "{1d2} ghouls"),
+                ],
+            ),
+            treasure: table(
+                "1d6",
+                &[
+                    ("1-2", "{3d6}0 sp"),
+                    ("3-4", "{2d6}0 gp"),
+                    ("5", "A gem worth {1d6}0 gp"),
+                    ("6", "A magic item"),
+                ],
+            ),
+            traps: table(
+                "1d6",
+                &[
+                    ("1", "Pit, {1d6} damage"),
+                    ("2", "Falling block, {1d10} damage"),
+                    ("3", "Poison needle in a lock"),
+                    ("4", "Sleeping gas"),
+                    ("5", "Swinging blade, {1d8} damage"),
+                    ("6", "Alarm bell"),
+                ],
+            ),
+            dressing: table(
+                "2d6",
+                &[
+                    ("2", "Scattered bones"),
+                    ("3-4", "Thick cobwebs"),
+                    ("5-6", "Rubble from a fallen ceiling"),
+                    ("7", "Damp, mouldy walls"),
+                    ("8-9", "Broken furniture"),
+                    ("10-11", "Faded murals"),
+                    ("12", "A strange statue"),
+                ],
+            ),
+        }
+    }
+}
+
+/// What a room rolled on the contents table
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Contents {
+    Monster,
+    Trap,
+    Special,
+    Empty,
+}
+
+impl Contents {
+    /// 1-2 monster, 3 trap, 4 special, 5-6 empty
+    fn roll(rng: &mut Rng) -> Self {
+        match rng.range(1, 6) {
+            1 | 2 => Contents::Monster,
+            3 => Contents::Trap,
+            4 => Contents::Special,
+            _ => Contents::Empty,
+        }
+    }
+
+    /// The chance in six of treasure
+    fn treasure_chance(self) -> usize {
+        match self {
+            Contents::Monster => 3,
+            Contents::Trap => 2,
+            Contents::Special => 0,
+            Contents::Empty => 1,
+        }
+    }
+}
+
+/// The notes for one room, as a Markdown list
+fn stock_room(tables: &StockingTables, rng: &mut Rng) -> String {
+    let mut lines = Vec::new();
+    let mut line = |label: &str, table: &RandomTable, rng: &mut Rng| {
+        let result = table
+            .roll(rng)
+            .unwrap_or_else(|| String::from("(nothing on the table matched)"));
+        lines.push(format!("- **{}:** {}", label, result));
+    };
+
+    let contents = Contents::roll(rng);
+    match contents {
+        Contents::Monster => line("Monster", &tables.monsters, rng),
+        Contents::Trap => line("Trap", &tables.traps, rng),
+        Contents::Special => line("Special", &tables.dressing, rng),
+        Contents::Empty => {}
+    }
+    if rng.range(1, 6) <= contents.treasure_chance() {
+        line("Treasure", &tables.treasure, rng);
+    }
+    if contents != Contents::Special {
+        line("Dressing", &tables.dressing, rng);
+    }
+
+    lines.join("\n")
+}
+
+/// Whether the room is the corridors or tunnels of a generated map, rather than a room to put things in
+pub fn is_passage(room: &Room) -> bool {
+    room.name == bsp::CORRIDORS || room.name == caves::TUNNELS
+}
+
+/// Roll contents for every room, or every room but the generated passages, and add them to its GM notes. The same
+/// seed always stocks the rooms the same way.
+pub fn stock(rooms: &Rooms, tables: &StockingTables, seed: u64, skip_passages: bool) -> Rooms {
+    let mut rng = Rng::new(seed);
+
+    rooms.iter().fold(rooms.clone(), |stocked, (id, room)| {
+        if skip_passages && is_passage(room) {
+            return stocked;
+        }
+
+        let mut room = room.clone();
+        let notes = stock_room(tables, &mut rng);
+        room.gm_notes = if room.gm_notes.trim().is_empty() {
+            notes
+        } else {
+            format!("{}\n\n{}", room.gm_notes.trim_end(), notes)
+        };
+        stocked.set(id, room).unwrap_or(stocked)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::room::Room;
+    use crate::test_support::*;
+
+    #[test]
+    fn parses_dice_notation() {
+        let roll = |count, sides, modifier| Dice::Roll {
+            count,
+            sides,
+            modifier,
+        };
+
+        assert_eq!("2d6".parse(), Ok(roll(2, 6, 0)));
+        assert_eq!("d20".parse(), Ok(roll(1, 20, 0)));
+        assert_eq!("1D8 + 2".parse(), Ok(roll(1, 8, 2)));
+        assert_eq!("3d4-1".parse(), Ok(roll(3, 4, -1)));
+        assert_eq!("1-4".parse(), Ok(Dice::Range(1, 4)));
+        assert_eq!("7".parse(), Ok(Dice::Range(7, 7)));
+        for invalid in &["", "d", "2d", "0d6", "2d0", "4-1", "x", "1d6+", "3+"] {
+            assert_eq!(invalid.parse::<Dice>(), Err(()), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn rolls_stay_in_range() {
+        let mut rng = Rng::new(3);
+        let mut seen = [false; 13];
+        for _ in 0..1000 {
+            let total = "2d6".parse::<Dice>().unwrap().roll(&mut rng);
+            assert!((2..=12).contains(&total));
+            seen[total as usize] = true;
+        }
+        assert!(seen[2..].iter().all(|seen| *seen));
+
+        for _ in 0..100 {
+            assert!((5..=7).contains(&Dice::Range(5, 7).roll(&mut rng)));
+        }
+    }
+
+    #[test]
+    fn tables_match_entries_by_range() {
+        let ranges = table(
+            "1d6",
+            &[("1-2", "low"), ("3", "{1d1+4} middle"), ("4+", "high")],
+        );
+        let mut rng = Rng::new(1);
+        let results: Vec<_> = (0..200).filter_map(|_| ranges.roll(&mut rng)).collect();
+
+        assert_eq!(results.len(), 200);
+        for expected in &["low", "5 middle", "high"] {
+            assert!(
+                results.iter().any(|result| result == expected),
+                "{}",
+                expected
+            );
+        }
+        assert_eq!(roll_inline("{nope} and {2d", &mut rng), "{nope} and {2d");
+
+        let gap = table("1d6", &[("1", "one")]);
+        assert!((0..50).any(|_| gap.roll(&mut rng).is_none()));
+    }
+
+    #[test]
+    fn stocking_is_seeded_and_keeps_existing_notes() {
+        let material = materials().as_ref()[0].clone();
+        let mut first = Room::new(material.clone(), String::from("1"));
+        first.gm_notes = String::from("Secret lever");
+        let (rooms, first_id) = Rooms::new().push(first);
+        let (rooms, _) = rooms.push(Room::new(material, String::from("2")));
+        let tables = StockingTables::default();
+
+        let notes = |rooms: &Rooms| -> Vec<String> {
+            rooms
+                .iter()
+                .map(|(_, room)| room.gm_notes.clone())
+                .collect()
+        };
+        let stocked = stock(&rooms, &tables, 9, true);
+        assert_eq!(notes(&stocked), notes(&stock(&rooms, &tables, 9, true)));
+        assert!(stocked
+            .get(first_id)
+            .unwrap()
+            .gm_notes
+            .starts_with("Secret lever\n\n- **"));
+        assert!(notes(&stocked).iter().all(|notes| notes.contains("- **")));
+    }
+
+    #[test]
+    fn generated_passages_can_be_left_empty() {
+        let material = materials().as_ref()[0].clone();
+        let mut corridors = Room::new(material.clone(), String::from("C"));
+        corridors.name = String::from(bsp::CORRIDORS);
+        let (rooms, room) = Rooms::new().push(Room::new(material, String::from("1")));
+        let (rooms, corridors) = rooms.push(corridors);
+        let tables = StockingTables::default();
+
+        let skipped = stock(&rooms, &tables, 4, true);
+        assert_ne!(skipped.get(room).unwrap().gm_notes, "");
+        assert_eq!(skipped.get(corridors).unwrap().gm_notes, "");
+
+        let stocked = stock(&rooms, &tables, 4, false);
+        assert_ne!(stocked.get(corridors).unwrap().gm_notes, "");
+    }
+}
@@ -0,0 +1,154 @@
+use yew::prelude::*;
+
+use crate::input::change_value;
+use crate::rng;
+use crate::room::Rooms;
+use crate::stocking::{self, StockingTables, TableEntry, TableKind};
+
+pub struct StockingPanel {
+    props: Props,
+    link: ComponentLink<Self>,
+    seed: String,
+    /// Leave the corridors and tunnels of generated maps empty
+    skip_passages: bool,
+}
+
+pub enum Msg {
+    Seed(String),
+    RandomSeed,
+    SkipPassages(bool),
+    Stock,
+    Dice(TableKind, String),
+    EntryRoll(TableKind, usize, String),
+    EntryText(TableKind, usize, String),
+    AddEntry(TableKind),
+    RemoveEntry(TableKind, usize),
+}
+
+#[derive(Clone, Properties)]
+pub struct Props {
+    pub rooms: Rooms,
+    pub rooms_changed: Callback<Rooms>,
+    pub tables: StockingTables,
+    pub tables_changed: Callback<StockingTables>,
+}
+
+impl StockingPanel {
+    fn table(&self, kind: TableKind) -> Html {
+        let table = self.props.tables.get(kind);
+        let dice_class = if table.is_valid() { "" } else { "invalid" };
+
+        html!(
+            <details>
+                <summary>{ kind.to_string() }</summary>
+                <label>
+                    {"Roll"}
+                    <input type="text" class=dice_class value=table.dice.clone() onchange=self.link.callback(move |data| Msg::Dice(kind, change_value(data))) />
+                </label>
+                <table>
+                    { for table.entries.iter().enumerate().map(|(index, entry)| {
+                        let roll_class = if entry.is_valid() { "" } else { "invalid" };
+                        html!(
+                            <tr>
+                                <td><input type="text" size="5" class=roll_class value=entry.roll.clone() onchange=self.link.callback(move |data| Msg::EntryRoll(kind, index, change_value(data))) /></td>
+                                <td><input type="text" value=entry.text.clone() onchange=self.link.callback(move |data| Msg::EntryText(kind, index, change_value(data))) /></td>
+                                <td><button onclick=self.link.callback(move |_| Msg::RemoveEntry(kind, index))>{"Remove"}</button></td>
+                            </tr>
+                        )
+                    }) }
+                </table>
+                <button onclick=self.link.callback(move |_| Msg::AddEntry(kind))>{"Add Entry"}</button>
+            </details>
+        )
+    }
+}
+
+impl Component for StockingPanel {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        Self {
+            props,
+            link,
+            seed: String::from("1"),
+            skip_passages: true,
+        }
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        let mut tables = self.props.tables.clone();
+
+        match msg {
+            Msg::Seed(seed) => {
+                self.seed = seed;
+                return true;
+            }
+            Msg::RandomSeed => {
+                self.seed = ((js_sys::Math::random() * u32::MAX as f64) as u64).to_string();
+                return true;
+            }
+            Msg::SkipPassages(skip_passages) => {
+                self.skip_passages = skip_passages;
+                return true;
+            }
+            Msg::Stock => {
+                let rooms = stocking::stock(
+                    &self.props.rooms,
+                    &tables,
+                    rng::parse_seed(&self.seed),
+                    self.skip_passages,
+                );
+                self.props.rooms_changed.emit(rooms);
+                return false;
+            }
+            Msg::Dice(kind, dice) => tables.get_mut(kind).dice = dice,
+            Msg::EntryRoll(kind, index, roll) => {
+                if let Some(entry) = tables.get_mut(kind).entries.get_mut(index) {
+                    entry.roll = roll;
+                }
+            }
+            Msg::EntryText(kind, index, text) => {
+                if let Some(entry) = tables.get_mut(kind).entries.get_mut(index) {
+                    entry.text = text;
+                }
+            }
+            Msg::AddEntry(kind) => {
+                let entries = &mut tables.get_mut(kind).entries;
+                entries.push(TableEntry::new(&(entries.len() + 1).to_string(), ""));
+            }
+            Msg::RemoveEntry(kind, index) => {
+                let entries = &mut tables.get_mut(kind).entries;
+                if index < entries.len() {
+                    entries.remove(index);
+                }
+            }
+        }
+
+        self.props.tables_changed.emit(tables);
+
+        false
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.props = props;
+        true
+    }
+
+    fn view(&self) -> Html {
+        let skip_passages = self.skip_passages;
+
+        html!(
+            <div>
+                <label>
+                    {"Seed"}
+                    <input type="text" value=self.seed.clone() onchange=self.link.callback(|data| Msg::Seed(change_value(data))) />
+                    <button onclick=self.link.callback(|_| Msg::RandomSeed)>{"Random"}</button>
+                </label>
+                <label class="inline"><input type="checkbox" checked=skip_passages onclick=self.link.callback(move |_| Msg::SkipPassages(!skip_passages)) />{"Leave corridors and tunnels empty"}</label>
+                <button onclick=self.link.callback(|_| Msg::Stock)>{"Stock Dungeon"}</button>
+                { for TableKind::ALL.iter().map(|kind| self.table(*kind)) }
+            </div>
+        )
+    }
+}
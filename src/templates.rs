@@ -0,0 +1,338 @@
+use yew::prelude::*;
+
+use crate::scale::DiagonalRule;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Shape {
+    Cone,
+    Sphere,
+    Line,
+    Cube,
+    Cylinder,
+}
+
+impl Shape {
+    pub const ALL: [Shape; 5] = [
+        Shape::Cone,
+        Shape::Sphere,
+        Shape::Line,
+        Shape::Cube,
+        Shape::Cylinder,
+    ];
+}
+
+impl std::fmt::Display for Shape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Shape::Cone => "Cone",
+            Shape::Sphere => "Sphere / Burst",
+            Shape::Line => "Line",
+            Shape::Cube => "Cube",
+            Shape::Cylinder => "Cylinder",
+        })
+    }
+}
+
+/// Which game's rules decide whether a square is inside a template
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Ruleset {
+    /// A square is affected if the template covers at least half of it
+    FifthEdition,
+    /// Bursts and cones count 1-2-1 distance from the origin intersection to the square's far corner; cones span 90°
+    Pathfinder,
+}
+
+impl std::fmt::Display for Ruleset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Ruleset::FifthEdition => "5e",
+            Ruleset::Pathfinder => "Pathfinder",
+        })
+    }
+}
+
+/// The template to place next
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TemplateSettings {
+    pub shape: Shape,
+    pub feet: f64,
+    pub ruleset: Ruleset,
+}
+
+impl Default for TemplateSettings {
+    fn default() -> Self {
+        Self {
+            shape: Shape::Sphere,
+            feet: 20.0,
+            ruleset: Ruleset::FifthEdition,
+        }
+    }
+}
+
+/// The width of a line template
+const LINE_WIDTH_FEET: f64 = 5.0;
+
+/// Points sampled along each side of a cell when estimating coverage
+const SAMPLES: usize = 8;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Template {
+    pub shape: Shape,
+    pub feet: f64,
+    /// Point of origin, as (x, y) in cells. Always a grid intersection.
+    pub origin: (f64, f64),
+    /// Direction in degrees, clockwise from east
+    pub direction: f64,
+}
+
+impl Template {
+    pub fn new(settings: &TemplateSettings, x: f64, y: f64) -> Self {
+        Self {
+            shape: settings.shape,
+            feet: settings.feet,
+            origin: (x.round(), y.round()),
+            direction: 0.0,
+        }
+    }
+
+    /// Point the template at (x, y), snapping to 15° steps
+    pub fn point_towards(&mut self, x: f64, y: f64) {
+        let (dx, dy) = (x - self.origin.0, y - self.origin.1);
+        if dx != 0.0 || dy != 0.0 {
+            let degrees = dy.atan2(dx).to_degrees();
+            self.direction = ((degrees / 15.0).round() * 15.0).rem_euclid(360.0);
+        }
+    }
+
+    /// Convert a point into distance along the template's direction and distance either side of it
+    fn local(&self, x: f64, y: f64) -> (f64, f64) {
+        let (sin, cos) = self.direction.to_radians().sin_cos();
+        let (dx, dy) = (x - self.origin.0, y - self.origin.1);
+        (dx * cos + dy * sin, dy * cos - dx * sin)
+    }
+
+    fn contains(&self, size: f64, line_width: f64, ruleset: Ruleset, x: f64, y: f64) -> bool {
+        let (along, across) = self.local(x, y);
+        match self.shape {
+            Shape::Sphere | Shape::Cylinder => along.hypot(across) <= size,
+            Shape::Cone => match ruleset {
+                Ruleset::FifthEdition => {
+                    along >= 0.0 && along <= size && across.abs() <= along / 2.0
+                }
+                Ruleset::Pathfinder => {
+                    along >= 0.0 && along.hypot(across) <= size && across.abs() <= along
+                }
+            },
+            Shape::Line => along >= 0.0 && along <= size && across.abs() <= line_width / 2.0,
+            Shape::Cube => along >= 0.0 && along <= size && across.abs() <= size / 2.0,
+        }
+    }
+
+    /// The fraction of the cell at (row, col) covered by the template
+    fn coverage(
+        &self,
+        size: f64,
+        line_width: f64,
+        ruleset: Ruleset,
+        row: usize,
+        col: usize,
+    ) -> f64 {
+        let mut inside = 0;
+        for i in 0..SAMPLES {
+            for j in 0..SAMPLES {
+                let x = col as f64 + (i as f64 + 0.5) / SAMPLES as f64;
+                let y = row as f64 + (j as f64 + 0.5) / SAMPLES as f64;
+                if self.contains(size, line_width, ruleset, x, y) {
+                    inside += 1;
+                }
+            }
+        }
+        inside as f64 / (SAMPLES * SAMPLES) as f64
+    }
+
+    /// Pathfinder distance in squares from the origin to the far corner of a cell
+    fn far_corner_distance(&self, row: usize, col: usize) -> f64 {
+        let far = |low: usize, origin: f64| {
+            let low = low as f64;
+            if low + 0.5 >= origin {
+                low + 1.0 - origin
+            } else {
+                origin - low
+            }
+        };
+        let dx = far(col, self.origin.0).round() as usize;
+        let dy = far(row, self.origin.1).round() as usize;
+        DiagonalRule::Alternating.path_squares(&[(0, 0), (dy, dx)])
+    }
+
+    fn affects(
+        &self,
+        size: f64,
+        line_width: f64,
+        ruleset: Ruleset,
+        row: usize,
+        col: usize,
+    ) -> bool {
+        match (ruleset, self.shape) {
+            (Ruleset::Pathfinder, Shape::Sphere) | (Ruleset::Pathfinder, Shape::Cylinder) => {
+                self.far_corner_distance(row, col) <= size
+            }
+            (Ruleset::Pathfinder, Shape::Cone) => {
+                let (along, across) = self.local(col as f64 + 0.5, row as f64 + 0.5);
+                along > 0.0 && across.abs() <= along && self.far_corner_distance(row, col) <= size
+            }
+            (Ruleset::Pathfinder, Shape::Line) => {
+                self.coverage(size, line_width, ruleset, row, col) > 0.0
+            }
+            _ => self.coverage(size, line_width, ruleset, row, col) >= 0.5,
+        }
+    }
+
+    /// The cells the template affects, given the length of one cell in feet
+    pub fn affected_cells(
+        &self,
+        feet_per_cell: f64,
+        ruleset: Ruleset,
+        rows: usize,
+        cols: usize,
+    ) -> Vec<(usize, usize)> {
+        let size = self.feet / feet_per_cell;
+        let line_width = LINE_WIDTH_FEET / feet_per_cell;
+        // Nothing further than the far side of the map can be affected, however big the template
+        let reach = size.ceil().min(rows.max(cols) as f64) as isize + 1;
+        let (ox, oy) = (self.origin.0 as isize, self.origin.1 as isize);
+
+        let mut cells = Vec::new();
+        for row in (oy - reach).max(0)..(oy + reach).min(rows as isize) {
+            for col in (ox - reach).max(0)..(ox + reach).min(cols as isize) {
+                let (row, col) = (row as usize, col as usize);
+                if self.affects(size, line_width, ruleset, row, col) {
+                    cells.push((row, col));
+                }
+            }
+        }
+        cells
+    }
+
+    /// The template's outline, in cells
+    pub fn outline(&self, feet_per_cell: f64, ruleset: Ruleset) -> Html {
+        let size = self.feet / feet_per_cell;
+        let line_width = LINE_WIDTH_FEET / feet_per_cell;
+        let (x, y) = self.origin;
+        let rotate = format!("rotate({} {} {})", self.direction, x, y);
+
+        let rectangle = |width: f64| {
+            let top = y - width / 2.0;
+            html!(<rect x=x y=top width=size height=width transform=rotate.clone() />)
+        };
+
+        match self.shape {
+            Shape::Sphere | Shape::Cylinder => html!(<circle cx=x cy=y r=size />),
+            Shape::Line => rectangle(line_width),
+            Shape::Cube => rectangle(size),
+            Shape::Cone => {
+                let path = match ruleset {
+                    Ruleset::FifthEdition => format!(
+                        "M {} {} L {} {} L {} {} Z",
+                        x,
+                        y,
+                        x + size,
+                        y - size / 2.0,
+                        x + size,
+                        y + size / 2.0
+                    ),
+                    Ruleset::Pathfinder => {
+                        let offset = size * std::f64::consts::FRAC_1_SQRT_2;
+                        format!(
+                            "M {} {} L {} {} A {} {} 0 0 1 {} {} Z",
+                            x,
+                            y,
+                            x + offset,
+                            y - offset,
+                            size,
+                            size,
+                            x + offset,
+                            y + offset
+                        )
+                    }
+                };
+                html!(<path d=path transform=rotate />)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(shape: Shape, feet: f64, x: f64, y: f64) -> Template {
+        let settings = TemplateSettings {
+            shape,
+            feet,
+            ..TemplateSettings::default()
+        };
+        Template::new(&settings, x, y)
+    }
+
+    #[test]
+    fn bursts_cover_the_same_squares_under_both_rulesets() {
+        let burst = template(Shape::Sphere, 10.0, 3.0, 3.0);
+        let fifth = burst.affected_cells(5.0, Ruleset::FifthEdition, 6, 6);
+        let pathfinder = burst.affected_cells(5.0, Ruleset::Pathfinder, 6, 6);
+
+        // Three squares in each quarter: the far corner of the diagonal square is three squares away in Pathfinder,
+        // and less than half of it is inside the circle in 5e
+        assert_eq!(fifth.len(), 12);
+        assert_eq!(fifth, pathfinder);
+        assert!(!fifth.contains(&(1, 1)));
+    }
+
+    #[test]
+    fn cones_follow_each_ruleset() {
+        let cone = template(Shape::Cone, 15.0, 0.0, 3.0);
+
+        // 5e takes the squares at least half inside a triangle as wide as it is long
+        assert_eq!(
+            cone.affected_cells(5.0, Ruleset::FifthEdition, 6, 6),
+            vec![(2, 1), (2, 2), (3, 1), (3, 2)]
+        );
+
+        // Pathfinder takes the squares whose centres are within 45° either side and whose far corners are in range
+        assert_eq!(
+            cone.affected_cells(5.0, Ruleset::Pathfinder, 6, 6),
+            vec![
+                (1, 1),
+                (2, 0),
+                (2, 1),
+                (2, 2),
+                (3, 0),
+                (3, 1),
+                (3, 2),
+                (4, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn directions_snap_to_fifteen_degrees() {
+        let mut line = template(Shape::Line, 30.0, 2.2, 1.9);
+        assert_eq!(line.origin, (2.0, 2.0));
+
+        line.point_towards(2.0, 7.0);
+        assert_eq!(line.direction, 90.0);
+        line.point_towards(3.0, 2.1);
+        assert_eq!(line.direction, 0.0);
+        line.point_towards(1.0, 1.0);
+        assert_eq!(line.direction, 225.0);
+    }
+
+    #[test]
+    fn huge_templates_cover_the_whole_map() {
+        let burst = template(Shape::Sphere, 1e300, 1.0, 1.0);
+        assert_eq!(
+            burst.affected_cells(5.0, Ruleset::FifthEdition, 2, 3).len(),
+            6
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use yew::prelude::*;
+
+use crate::input::change_value;
+use crate::templates::{Ruleset, Shape, Template, TemplateSettings};
+
+pub struct TemplatesPanel {
+    props: Props,
+    link: ComponentLink<Self>,
+}
+
+pub enum Msg {
+    Shape(String),
+    Feet(String),
+    Ruleset(String),
+}
+
+#[derive(Clone, Properties)]
+pub struct Props {
+    pub settings: TemplateSettings,
+    pub settings_changed: Callback<TemplateSettings>,
+    /// The longest template worth placing: the distance across the map, corner to corner
+    pub max_feet: f64,
+    pub templates: Vec<Template>,
+    pub template_rotated: Callback<(usize, f64)>,
+    pub template_removed: Callback<usize>,
+}
+
+impl Component for TemplatesPanel {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        Self { props, link }
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        let mut settings = self.props.settings;
+
+        match msg {
+            Msg::Shape(shape) => {
+                if let Some(shape) = Shape::ALL.iter().find(|s| s.to_string() == shape) {
+                    settings.shape = *shape;
+                }
+            }
+            Msg::Feet(feet) => match feet.parse() {
+                Ok(feet) if feet > 0.0 && f64::is_finite(feet) => {
+                    settings.feet = feet.min(self.props.max_feet)
+                }
+                _ => return true,
+            },
+            Msg::Ruleset(ruleset) => {
+                settings.ruleset = if ruleset == Ruleset::Pathfinder.to_string() {
+                    Ruleset::Pathfinder
+                } else {
+                    Ruleset::FifthEdition
+                }
+            }
+        }
+
+        self.props.settings_changed.emit(settings);
+
+        false
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.props = props;
+        true
+    }
+
+    fn view(&self) -> Html {
+        let settings = self.props.settings;
+
+        html!(
+            <fieldset>
+                <legend>{"Templates"}</legend>
+                <div>
+                    <select onchange=self.link.callback(|data| Msg::Shape(change_value(data)))>
+                        { for Shape::ALL.iter().map(|shape| html!(
+                            <option selected=*shape == settings.shape>{ shape.to_string() }</option>
+                        )) }
+                    </select>
+                    <input type="number" min="5" step="5" value=settings.feet.to_string() onchange=self.link.callback(|data| Msg::Feet(change_value(data))) />
+                    {"ft "}
+                    <select onchange=self.link.callback(|data| Msg::Ruleset(change_value(data)))>
+                        { for [Ruleset::FifthEdition, Ruleset::Pathfinder].iter().map(|ruleset| html!(
+                            <option selected=*ruleset == settings.ruleset>{ ruleset.to_string() }</option>
+                        )) }
+                    </select>
+                </div>
+                <table>
+                    { for self.props.templates.iter().enumerate().map(|(index, template)| html!(
+                        <tr>
+                            <td>{ format!("{} ft {}", template.feet, template.shape) }</td>
+                            <td>
+                                <input type="range" min="0" max="345" step="15" value=template.direction.to_string()
+                                    oninput=self.props.template_rotated.reform(move |data: InputData| (index, data.value.parse().unwrap_or(0.0))) />
+                            </td>
+                            <td><button onclick=self.props.template_removed.reform(move |_| index)>{"Remove"}</button></td>
+                        </tr>
+                    )) }
+                </table>
+            </fieldset>
+        )
+    }
+}
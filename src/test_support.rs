@@ -0,0 +1,53 @@
+//! Fixtures shared by the tests of the generators and documents.
+
+use std::collections::BTreeSet;
+
+use crate::room::{RoomId, Rooms};
+use crate::tile;
+use crate::Cells;
+
+pub fn materials() -> tile::Materials {
+    serde_json::from_str(
+        r#"[{"name":"a","href":"a.png","size":1},{"name":"b","href":"b.png","size":1}]"#,
+    )
+    .unwrap()
+}
+
+/// Each room's label and material, which are compared by name since rooms and materials compare by identity
+pub fn room_names(rooms: &Rooms) -> Vec<(RoomId, String, String)> {
+    rooms
+        .iter()
+        .map(|(id, room)| (id, room.label.clone(), room.tile_material.to_string()))
+        .collect()
+}
+
+pub fn contents(cells: &Cells) -> Vec<Option<RoomId>> {
+    cells.iter().map(|(_, _, cell)| *cell).collect()
+}
+
+/// Whether every painted cell can be reached from every other
+pub fn is_connected(cells: &Cells) -> bool {
+    let painted: BTreeSet<_> = cells
+        .iter()
+        .filter(|(_, _, cell)| cell.is_some())
+        .map(|(row, col, _)| (row, col))
+        .collect();
+
+    let mut reached = BTreeSet::new();
+    let mut stack: Vec<_> = painted.iter().next().copied().into_iter().collect();
+    while let Some((row, col)) = stack.pop() {
+        if !painted.contains(&(row, col)) || !reached.insert((row, col)) {
+            continue;
+        }
+        stack.push((row + 1, col));
+        stack.push((row, col + 1));
+        if let Some(row) = row.checked_sub(1) {
+            stack.push((row, col));
+        }
+        if let Some(col) = col.checked_sub(1) {
+            stack.push((row, col));
+        }
+    }
+
+    reached.len() == painted.len()
+}
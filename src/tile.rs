@@ -1,13 +1,13 @@
 use std::rc::Rc;
 
-#[derive(Debug, serde::Deserialize)]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct MaterialInner {
     name: String,
     href: String,
     size: usize,
 }
 
-#[derive(Clone, Debug, serde::Deserialize)]
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct Material(Rc<MaterialInner>);
 
 impl Material {
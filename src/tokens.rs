@@ -0,0 +1,198 @@
+use yew::prelude::*;
+
+use crate::hex::GridShape;
+
+#[derive(Copy, Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum Size {
+    Tiny,
+    Small,
+    Medium,
+    Large,
+    Huge,
+    Gargantuan,
+}
+
+impl Size {
+    pub const ALL: [Size; 6] = [
+        Size::Tiny,
+        Size::Small,
+        Size::Medium,
+        Size::Large,
+        Size::Huge,
+        Size::Gargantuan,
+    ];
+
+    /// The number of cells along each side of the space the creature occupies
+    pub fn cells(&self) -> usize {
+        match self {
+            Size::Tiny | Size::Small | Size::Medium => 1,
+            Size::Large => 2,
+            Size::Huge => 3,
+            Size::Gargantuan => 4,
+        }
+    }
+}
+
+impl std::fmt::Display for Size {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self, f)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Token {
+    pub name: String,
+    pub colour: String,
+    /// URL of an image to show instead of the colour, if not empty
+    pub image: String,
+    pub size: Size,
+    /// Top left cell of the token's space
+    pub row: usize,
+    pub col: usize,
+    pub hp: i32,
+    pub max_hp: i32,
+    pub conditions: Vec<String>,
+}
+
+impl Token {
+    pub fn new(name: String, row: usize, col: usize) -> Self {
+        Self {
+            name,
+            colour: String::from("#4169e1"),
+            image: String::new(),
+            size: Size::Medium,
+            row,
+            col,
+            hp: 10,
+            max_hp: 10,
+            conditions: Vec::new(),
+        }
+    }
+
+    /// The token at another size, moved up and left as far as it needs to stay on a `rows` by `cols` map. `None` if
+    /// the map is too small for it.
+    pub fn resized(&self, size: Size, rows: usize, cols: usize) -> Option<Self> {
+        let cells = size.cells();
+        if cells > rows || cells > cols {
+            return None;
+        }
+
+        Some(Self {
+            size,
+            row: self.row.min(rows - cells),
+            col: self.col.min(cols - cells),
+            ..self.clone()
+        })
+    }
+
+    pub fn covers(&self, row: usize, col: usize) -> bool {
+        let size = self.size.cells();
+        (self.row..self.row + size).contains(&row) && (self.col..self.col + size).contains(&col)
+    }
+
+    /// Every cell in the token's space
+    pub fn cells(&self) -> impl Iterator<Item = (usize, usize)> {
+        let (top, left, size) = (self.row, self.col, self.size.cells());
+        (top..top + size).flat_map(move |row| (left..left + size).map(move |col| (row, col)))
+    }
+
+    /// The token standing with its top left cell at `row`, `col`, centred on its space as drawn in `shape`
+    pub fn view(&self, shape: GridShape, row: usize, col: usize) -> Html {
+        let size = self.size.cells() as f64;
+        let scale = match self.size {
+            Size::Tiny => 0.5,
+            Size::Small => 0.8,
+            _ => 0.9,
+        };
+        let diameter = size * scale;
+        let (x, y) = shape.point(col as f64 + size / 2.0, row as f64 + size / 2.0);
+        let (left, top) = (x - diameter / 2.0, y - diameter / 2.0);
+        let radius = diameter / 2.0;
+
+        let health = if self.max_hp > 0 {
+            (self.hp.max(0) as f64 / self.max_hp as f64).min(1.0)
+        } else {
+            1.0
+        };
+        let health_width = diameter * health;
+        let (right, bar_y) = (left + diameter, top + diameter);
+        let initial = self
+            .name
+            .chars()
+            .next()
+            .map(String::from)
+            .unwrap_or_default();
+
+        html!(
+            <g>
+                <title>{ format!("{} ({}/{} HP) {}", self.name, self.hp, self.max_hp, self.conditions.join(", ")) }</title>
+                <circle cx=x cy=y r=radius fill=self.colour.clone() stroke="black" stroke-width="0.05" />
+                { if self.image.is_empty() {
+                    html!(<text x=x y=y font-size=radius text-anchor="middle" dominant-baseline="central" fill="white">{ initial }</text>)
+                } else {
+                    html!(<image href=self.image.clone() x=left y=top width=diameter height=diameter clip-path="url(#tokenClip)" />)
+                } }
+                <rect x=left y=bar_y width=diameter height="0.1" fill="darkred" />
+                <rect x=left y=bar_y width=health_width height="0.1" fill="limegreen" />
+                { if self.conditions.is_empty() {
+                    html!()
+                } else {
+                    html!(<circle cx=right cy=top r="0.12" fill="gold" stroke="black" stroke-width="0.02" />)
+                } }
+            </g>
+        )
+    }
+}
+
+pub type Tokens = crate::list::List<Token>;
+
+/// A token being dragged, which is only written to the document when the mouse is released
+#[derive(Copy, Clone, Debug)]
+pub struct TokenDrag {
+    pub index: usize,
+    /// Where the token was grabbed, relative to its top left cell
+    offset: (usize, usize),
+    pub row: usize,
+    pub col: usize,
+}
+
+impl TokenDrag {
+    pub fn new(index: usize, token: &Token, row: usize, col: usize) -> Self {
+        Self {
+            index,
+            offset: (row - token.row, col - token.col),
+            row: token.row,
+            col: token.col,
+        }
+    }
+
+    /// Move the grabbed cell to (row, col), keeping the whole token within `rows` by `cols` cells
+    pub fn move_to(&mut self, token: &Token, row: usize, col: usize, rows: usize, cols: usize) {
+        let size = token.size.cells();
+        self.row = row
+            .saturating_sub(self.offset.0)
+            .min(rows.saturating_sub(size));
+        self.col = col
+            .saturating_sub(self.offset.1)
+            .min(cols.saturating_sub(size));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn growing_tokens_stay_on_the_map() {
+        let token = Token::new(String::from("Ogre"), 3, 1);
+
+        let large = token.resized(Size::Large, 4, 4).unwrap();
+        assert_eq!((large.row, large.col), (2, 1));
+        assert!(large.cells().all(|(row, col)| row < 4 && col < 4));
+
+        let gargantuan = token.resized(Size::Gargantuan, 4, 4).unwrap();
+        assert_eq!((gargantuan.row, gargantuan.col), (0, 0));
+
+        assert_eq!(token.resized(Size::Huge, 2, 8), None);
+    }
+}
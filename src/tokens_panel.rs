@@ -0,0 +1,183 @@
+use yew::prelude::*;
+
+use crate::input::change_value;
+use crate::tokens::{Size, Token, Tokens};
+
+pub struct TokensPanel {
+    props: Props,
+    link: ComponentLink<Self>,
+}
+
+pub enum Msg {
+    NewToken,
+    RemoveToken(usize),
+    TokenNameChanged(usize, String),
+    TokenColourChanged(usize, String),
+    TokenImageChanged(usize, String),
+    TokenSizeChanged(usize, String),
+    TokenHpChanged(usize, String),
+    TokenMaxHpChanged(usize, String),
+    TokenConditionsChanged(usize, String),
+}
+
+#[derive(Clone, Properties)]
+pub struct Props {
+    pub tokens: Tokens,
+    pub tokens_changed: Callback<Tokens>,
+    /// The size of the map, which tokens must stay on
+    pub rows: usize,
+    pub cols: usize,
+    pub selected_token: Option<usize>,
+    pub selected_token_changed: Callback<Option<usize>>,
+}
+
+impl TokensPanel {
+    fn update_token(&self, index: usize, f: impl FnOnce(&mut Token)) {
+        if let Some(mut token) = self.props.tokens.get(index).cloned() {
+            f(&mut token);
+            if let Some(new_tokens) = self.props.tokens.set(index, token) {
+                self.props.tokens_changed.emit(new_tokens);
+            }
+        }
+    }
+
+    fn details(&self) -> Html {
+        let index = match self.props.selected_token {
+            Some(index) => index,
+            None => return html!(),
+        };
+
+        let token = match self.props.tokens.get(index) {
+            Some(token) => token,
+            None => return html!(),
+        };
+
+        html!(
+            <div class="room-details">
+                <label>{"Name"}<input type="text" value=token.name.clone() onchange=self.link.callback(move |data| Msg::TokenNameChanged(index, change_value(data))) /></label>
+                <label>{"Colour"}<input type="color" value=token.colour.clone() onchange=self.link.callback(move |data| Msg::TokenColourChanged(index, change_value(data))) /></label>
+                <label>{"Image URL"}<input type="url" value=token.image.clone() onchange=self.link.callback(move |data| Msg::TokenImageChanged(index, change_value(data))) /></label>
+                <label>
+                    {"Size"}
+                    <select onchange=self.link.callback(move |data| Msg::TokenSizeChanged(index, change_value(data)))>
+                        { for Size::ALL.iter().map(|size| html!(
+                            <option selected=*size == token.size>{ size.to_string() }</option>
+                        )) }
+                    </select>
+                </label>
+                <label>{"HP"}<input type="number" value=token.hp.to_string() onchange=self.link.callback(move |data| Msg::TokenHpChanged(index, change_value(data))) /></label>
+                <label>{"Max HP"}<input type="number" value=token.max_hp.to_string() onchange=self.link.callback(move |data| Msg::TokenMaxHpChanged(index, change_value(data))) /></label>
+                <label>{"Conditions"}<input type="text" placeholder="comma separated" value=token.conditions.join(", ") onchange=self.link.callback(move |data| Msg::TokenConditionsChanged(index, change_value(data))) /></label>
+            </div>
+        )
+    }
+}
+
+impl Component for TokensPanel {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        Self { props, link }
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        match msg {
+            Msg::NewToken => {
+                if self.props.rows == 0 || self.props.cols == 0 {
+                    return false;
+                }
+                let name = format!("Token {}", self.props.tokens.len() + 1);
+                self.props
+                    .tokens_changed
+                    .emit(self.props.tokens.push_back(Token::new(name, 0, 0)));
+                self.props
+                    .selected_token_changed
+                    .emit(Some(self.props.tokens.len()));
+            }
+            Msg::RemoveToken(index) => {
+                if let Some(new_tokens) = self.props.tokens.remove(index) {
+                    self.props.tokens_changed.emit(new_tokens);
+                    self.props.selected_token_changed.emit(None);
+                }
+            }
+            Msg::TokenNameChanged(index, name) => {
+                self.update_token(index, |token| token.name = name)
+            }
+            Msg::TokenColourChanged(index, colour) => {
+                self.update_token(index, |token| token.colour = colour)
+            }
+            Msg::TokenImageChanged(index, image) => {
+                self.update_token(index, |token| token.image = image)
+            }
+            Msg::TokenSizeChanged(index, size) => {
+                let (rows, cols) = (self.props.rows, self.props.cols);
+                let resized = Size::ALL
+                    .iter()
+                    .find(|s| s.to_string() == size)
+                    .zip(self.props.tokens.get(index))
+                    .and_then(|(size, token)| token.resized(*size, rows, cols));
+                if let Some(token) = resized {
+                    if let Some(new_tokens) = self.props.tokens.set(index, token) {
+                        self.props.tokens_changed.emit(new_tokens);
+                    }
+                }
+            }
+            Msg::TokenHpChanged(index, hp) => {
+                if let Ok(hp) = hp.parse() {
+                    self.update_token(index, |token| token.hp = hp)
+                }
+            }
+            Msg::TokenMaxHpChanged(index, max_hp) => {
+                if let Ok(max_hp) = max_hp.parse() {
+                    self.update_token(index, |token| token.max_hp = max_hp)
+                }
+            }
+            Msg::TokenConditionsChanged(index, conditions) => self.update_token(index, |token| {
+                token.conditions = conditions
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|condition| !condition.is_empty())
+                    .map(String::from)
+                    .collect()
+            }),
+        }
+
+        false
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.props = props;
+        true
+    }
+
+    fn view(&self) -> Html {
+        html!(
+            <fieldset>
+                <legend>{"Tokens"}</legend>
+                <table>
+                    <tbody>
+                        { for self.props.tokens.iter().enumerate().map(|(index, token)| {
+                            let class = if Some(index) == self.props.selected_token {
+                                "selected"
+                            } else {
+                                ""
+                            };
+                            html!(
+                                <tr class=class>
+                                    <td style=format!("border-left:0.5em solid {}", token.colour)>{ &token.name }</td>
+                                    <td>{ format!("{}/{} HP", token.hp, token.max_hp) }</td>
+                                    <td>{ token.conditions.join(", ") }</td>
+                                    <td><button onclick=self.props.selected_token_changed.reform(move |_| Some(index))>{"Select"}</button></td>
+                                    <td><button onclick=self.link.callback(move |_| Msg::RemoveToken(index))>{"Remove"}</button></td>
+                                </tr>
+                            )
+                        }) }
+                    </tbody>
+                </table>
+                <button onclick=self.link.callback(|_| Msg::NewToken)>{"Add Token"}</button>
+                { self.details() }
+            </fieldset>
+        )
+    }
+}
@@ -0,0 +1,418 @@
+//! Line of sight, using symmetric shadowcasting.
+//!
+//! Walls sit on the edges between cells, so the map is first expanded to a grid twice as fine: each cell becomes
+//! a tile at odd coordinates, each edge a tile between two cells and each corner a tile where four edges meet.
+//! Shadowcasting then runs on those tiles, treating walls and cells outside any room as opaque. Edges and corners
+//! take up a whole tile, so walls cast slightly wider shadows than their true thickness would.
+
+use std::collections::BTreeMap;
+
+use crate::edges::{Edge, EdgeId, Edges};
+use crate::grid::Grid;
+use crate::Cells;
+
+/// How vision is shown on the map
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct VisionSettings {
+    pub show: bool,
+    pub open_doors: bool,
+    /// Clear the fog from whatever can be seen
+    pub auto_reveal: bool,
+}
+
+/// The tiles of the expanded map, indexed by (row, col)
+struct Tiles {
+    rows: usize,
+    cols: usize,
+    opaque: Vec<bool>,
+}
+
+impl Tiles {
+    fn new(cells: &Cells, edges: &Edges, open_doors: bool) -> Self {
+        let (rows, cols) = (cells.rows() * 2 + 1, cells.cols() * 2 + 1);
+        let is_rock = |row: usize, col: usize| !cells.get(row, col).is_some_and(Option::is_some);
+        let blocks = |id: EdgeId| match edges.get(id) {
+            Some(Edge::Wall) | Some(Edge::SecretDoor) => true,
+            Some(Edge::Door) => !open_doors,
+            None => false,
+        };
+
+        // Edges block sight if there's a wall on them or rock on either side
+        let edge_opaque = |id: EdgeId| {
+            let (before, after) = id.cells();
+            blocks(id)
+                || before.is_none_or(|(row, col)| is_rock(row, col))
+                || is_rock(after.0, after.1)
+        };
+
+        let mut opaque = vec![false; rows * cols];
+        for row in 0..rows {
+            for col in 0..cols {
+                let (cell_row, cell_col) = (row / 2, col / 2);
+                opaque[row * cols + col] = match (row % 2, col % 2) {
+                    (1, 1) => is_rock(cell_row, cell_col),
+                    (0, 1) => edge_opaque(EdgeId::top(cell_row, cell_col)),
+                    (1, 0) => edge_opaque(EdgeId::left(cell_row, cell_col)),
+                    _ => {
+                        // Corners close the gaps where walls meet
+                        let touching = [
+                            cell_row
+                                .checked_sub(1)
+                                .map(|row| EdgeId::left(row, cell_col)),
+                            Some(EdgeId::left(cell_row, cell_col)),
+                            cell_col
+                                .checked_sub(1)
+                                .map(|col| EdgeId::top(cell_row, col)),
+                            Some(EdgeId::top(cell_row, cell_col)),
+                        ];
+                        touching.iter().flatten().any(|id| edge_opaque(*id))
+                    }
+                };
+            }
+        }
+
+        Self { rows, cols, opaque }
+    }
+
+    fn index(&self, (row, col): (isize, isize)) -> Option<usize> {
+        if row < 0 || col < 0 || row as usize >= self.rows || col as usize >= self.cols {
+            None
+        } else {
+            Some(row as usize * self.cols + col as usize)
+        }
+    }
+
+    fn is_opaque(&self, tile: (isize, isize)) -> bool {
+        self.index(tile).is_none_or(|index| self.opaque[index])
+    }
+}
+
+#[derive(Copy, Clone)]
+enum Quadrant {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Quadrant {
+    const ALL: [Quadrant; 4] = [
+        Quadrant::North,
+        Quadrant::East,
+        Quadrant::South,
+        Quadrant::West,
+    ];
+
+    /// Convert a row and column relative to the quadrant into a tile
+    fn transform(self, (row, col): (isize, isize), depth: isize, column: isize) -> (isize, isize) {
+        match self {
+            Quadrant::North => (row - depth, col + column),
+            Quadrant::South => (row + depth, col + column),
+            Quadrant::East => (row + column, col + depth),
+            Quadrant::West => (row + column, col - depth),
+        }
+    }
+}
+
+/// A slope as an exact fraction, with a positive denominator
+#[derive(Copy, Clone)]
+struct Slope {
+    numerator: isize,
+    denominator: isize,
+}
+
+impl Slope {
+    /// The slope through the edge on the near side of a tile
+    fn of(depth: isize, column: isize) -> Self {
+        Self {
+            numerator: 2 * column - 1,
+            denominator: 2 * depth,
+        }
+    }
+}
+
+/// A row of tiles at one distance from the origin, between two slopes
+struct Row {
+    depth: isize,
+    start: Slope,
+    end: Slope,
+}
+
+impl Row {
+    /// The first column, rounding depth × start half-way values up
+    fn min_column(&self) -> isize {
+        let Slope {
+            numerator,
+            denominator,
+        } = self.start;
+        (2 * self.depth * numerator + denominator).div_euclid(2 * denominator)
+    }
+
+    /// The last column, rounding depth × end half-way values down
+    fn max_column(&self) -> isize {
+        let Slope {
+            numerator,
+            denominator,
+        } = self.end;
+        -(denominator - 2 * self.depth * numerator).div_euclid(2 * denominator)
+    }
+
+    /// Whether the tile's centre lies within the row's slopes, which keeps vision symmetric
+    fn is_symmetric(&self, column: isize) -> bool {
+        column * self.start.denominator >= self.depth * self.start.numerator
+            && column * self.end.denominator <= self.depth * self.end.numerator
+    }
+
+    fn next(&self, end: Slope) -> Self {
+        Self {
+            depth: self.depth + 1,
+            start: self.start,
+            end,
+        }
+    }
+}
+
+/// The cells that can be seen from the centre of `origin`. Walls and secret doors block sight, as do doors unless
+/// `open_doors` is set, and cells that aren't part of any room.
+pub fn visible_cells(
+    cells: &Cells,
+    edges: &Edges,
+    origin: (usize, usize),
+    open_doors: bool,
+) -> Grid<bool> {
+    let tiles = Tiles::new(cells, edges, open_doors);
+    let mut visible = vec![false; tiles.opaque.len()];
+    let start = (origin.0 as isize * 2 + 1, origin.1 as isize * 2 + 1);
+
+    if let Some(index) = tiles.index(start) {
+        visible[index] = true;
+
+        for quadrant in Quadrant::ALL.iter().copied() {
+            let mut rows = vec![Row {
+                depth: 1,
+                start: Slope {
+                    numerator: -1,
+                    denominator: 1,
+                },
+                end: Slope {
+                    numerator: 1,
+                    denominator: 1,
+                },
+            }];
+
+            while let Some(mut row) = rows.pop() {
+                let mut previous_opaque = None;
+
+                for column in row.min_column()..=row.max_column() {
+                    let tile = quadrant.transform(start, row.depth, column);
+                    let opaque = tiles.is_opaque(tile);
+
+                    if opaque || row.is_symmetric(column) {
+                        if let Some(index) = tiles.index(tile) {
+                            visible[index] = true;
+                        }
+                    }
+
+                    match (previous_opaque, opaque) {
+                        (Some(true), false) => row.start = Slope::of(row.depth, column),
+                        (Some(false), true) => rows.push(row.next(Slope::of(row.depth, column))),
+                        _ => {}
+                    }
+
+                    previous_opaque = Some(opaque);
+                }
+
+                if previous_opaque == Some(false) {
+                    rows.push(row.next(row.end));
+                }
+            }
+        }
+    }
+
+    cells.map_indexed(|row, col, _| visible[(row * 2 + 1) * tiles.cols + col * 2 + 1])
+}
+
+/// An SVG path tracing the boundary of the visible cells, suitable for filling as a polygon
+pub fn outline(visible: &Grid<bool>) -> String {
+    let is_visible = |row: usize, col: usize| visible.get(row, col) == Some(&true);
+
+    // Directed edges with the visible cell on their right, as (x, y) corners
+    let mut next: BTreeMap<(usize, usize), Vec<(usize, usize)>> = BTreeMap::new();
+    for (row, col, _) in visible.iter().filter(|(_, _, visible)| **visible) {
+        let sides = [
+            (
+                row == 0 || !is_visible(row - 1, col),
+                (col, row),
+                (col + 1, row),
+            ),
+            (
+                !is_visible(row, col + 1),
+                (col + 1, row),
+                (col + 1, row + 1),
+            ),
+            (
+                !is_visible(row + 1, col),
+                (col + 1, row + 1),
+                (col, row + 1),
+            ),
+            (
+                col == 0 || !is_visible(row, col - 1),
+                (col, row + 1),
+                (col, row),
+            ),
+        ];
+        for (boundary, from, to) in sides.iter().copied() {
+            if boundary {
+                next.entry(from).or_default().push(to);
+            }
+        }
+    }
+
+    let mut path = String::new();
+    while let Some(&first) = next.keys().next() {
+        path.push_str(&format!("M {} {}", first.0, first.1));
+
+        let mut point = first;
+        while let Some(to) = next.get_mut(&point).and_then(Vec::pop) {
+            if next.get(&point).is_some_and(Vec::is_empty) {
+                next.remove(&point);
+            }
+            if to == first {
+                break;
+            }
+            path.push_str(&format!(" L {} {}", to.0, to.1));
+            point = to;
+        }
+
+        path.push_str(" Z ");
+    }
+
+    path.trim_end().to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::room::RoomId;
+
+    /// Build a layout from strings, where `.` is floor and `#` is rock
+    fn layout(rows: &[&str]) -> Cells {
+        let room: RoomId = "0".parse().unwrap();
+        let mut cells = Cells::with_dimensions(rows.len(), rows[0].len());
+        for (row, line) in rows.iter().enumerate() {
+            for (col, c) in line.chars().enumerate() {
+                if c == '.' {
+                    cells = cells.set(row, col, Some(room)).unwrap();
+                }
+            }
+        }
+        cells
+    }
+
+    fn render(visible: &Grid<bool>) -> Vec<String> {
+        (0..visible.rows())
+            .map(|row| {
+                (0..visible.cols())
+                    .map(|col| {
+                        if visible.get(row, col) == Some(&true) {
+                            'v'
+                        } else {
+                            '-'
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn open_room_is_fully_visible() {
+        let cells = layout(&["....", "....", "...."]);
+        let visible = visible_cells(&cells, &Edges::new(), (1, 1), false);
+        assert!(visible.iter().all(|(_, _, visible)| *visible));
+    }
+
+    #[test]
+    fn rock_blocks_sight() {
+        let cells = layout(&[".....", "..#..", "....."]);
+        let visible = visible_cells(&cells, &Edges::new(), (1, 0), false);
+        assert_eq!(render(&visible), ["vvv--", "vv---", "vvv--"]);
+    }
+
+    #[test]
+    fn walls_on_edges_block_sight() {
+        let cells = layout(&["....", "....", "...."]);
+        let edges = (0..3).fold(Edges::new(), |edges, row| {
+            edges.set(EdgeId::left(row, 2), Some(Edge::Wall))
+        });
+        let visible = visible_cells(&cells, &edges, (1, 0), false);
+        assert_eq!(render(&visible), ["vv--", "vv--", "vv--"]);
+    }
+
+    #[test]
+    fn doors_block_sight_unless_open() {
+        let cells = layout(&["...", "...", "..."]);
+        let edges = Edges::new()
+            .set(EdgeId::top(1, 0), Some(Edge::Wall))
+            .set(EdgeId::top(1, 1), Some(Edge::Door))
+            .set(EdgeId::top(1, 2), Some(Edge::Wall));
+
+        let closed = visible_cells(&cells, &edges, (2, 1), false);
+        assert_eq!(render(&closed), ["---", "vvv", "vvv"]);
+
+        let open = visible_cells(&cells, &edges, (2, 1), true);
+        // The corners of the far room are hidden by the walls either side of the door
+        assert_eq!(render(&open), ["-v-", "vvv", "vvv"]);
+    }
+
+    #[test]
+    fn secret_doors_always_block_sight() {
+        let cells = layout(&["..", ".."]);
+        let edges = Edges::new()
+            .set(EdgeId::top(1, 0), Some(Edge::SecretDoor))
+            .set(EdgeId::top(1, 1), Some(Edge::SecretDoor));
+        let visible = visible_cells(&cells, &edges, (1, 0), true);
+        assert_eq!(render(&visible), ["--", "vv"]);
+    }
+
+    #[test]
+    fn vision_is_symmetric() {
+        let cells = layout(&[
+            "........", "..#.....", ".....#..", "........", ".#...#..", "........",
+        ]);
+        let edges = Edges::new()
+            .set(EdgeId::left(3, 4), Some(Edge::Wall))
+            .set(EdgeId::top(5, 2), Some(Edge::Wall));
+
+        let floor: Vec<_> = cells
+            .iter()
+            .filter(|(_, _, cell)| cell.is_some())
+            .map(|(row, col, _)| (row, col))
+            .collect();
+        let views: Vec<_> = floor
+            .iter()
+            .map(|origin| visible_cells(&cells, &edges, *origin, false))
+            .collect();
+
+        for (a, view_a) in floor.iter().zip(&views) {
+            for (b, view_b) in floor.iter().zip(&views) {
+                assert_eq!(
+                    view_a.get(b.0, b.1),
+                    view_b.get(a.0, a.1),
+                    "{:?} and {:?} disagree",
+                    a,
+                    b
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn outline_traces_the_boundary() {
+        let visible = Grid::<bool>::with_dimensions(2, 2).set(0, 0, true).unwrap();
+        assert_eq!(outline(&visible), "M 0 0 L 1 0 L 1 1 L 0 1 Z");
+
+        let empty = Grid::<bool>::with_dimensions(2, 2);
+        assert_eq!(outline(&empty), "");
+    }
+}
@@ -0,0 +1,589 @@
+//! Wave function collapse with the overlapping model: every N×N window of a sample map becomes a pattern, and a new
+//! map is built in which every N×N window is one of those patterns, so it follows the sample's local structure.
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::grid::Grid;
+use crate::rng::Rng;
+
+/// What a cell holds: a room or material index, or nothing
+pub type Value = Option<usize>;
+
+/// Offsets to the neighbouring pattern positions: up, right, down, left
+const DIRECTIONS: [(isize, isize); 4] = [(-1, 0), (0, 1), (1, 0), (0, -1)];
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct WfcSettings {
+    pub seed: u64,
+    pub rows: usize,
+    pub cols: usize,
+    /// Width and height of the patterns taken from the sample
+    pub pattern_size: usize,
+    /// Also take patterns from the sample rotated and reflected
+    pub symmetry: bool,
+    /// How many contradictions to back out of before giving up
+    pub max_contradictions: usize,
+}
+
+impl Default for WfcSettings {
+    fn default() -> Self {
+        Self {
+            seed: 1,
+            rows: 32,
+            cols: 32,
+            pattern_size: 3,
+            symmetry: false,
+            max_contradictions: 500,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum WfcError {
+    SampleTooSmall,
+    OutputTooSmall,
+    /// No pattern fitted somewhere, and backtracking didn't find a way round it
+    Contradiction {
+        contradictions: usize,
+    },
+}
+
+impl std::fmt::Display for WfcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WfcError::SampleTooSmall => f.write_str("The sample is smaller than a pattern"),
+            WfcError::OutputTooSmall => f.write_str("The map is smaller than a pattern"),
+            WfcError::Contradiction { contradictions } => write!(
+                f,
+                "Gave up after {} contradictions; try another seed or a smaller pattern size",
+                contradictions
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Generated {
+    pub cells: Grid<Value>,
+    /// How many times generation backtracked
+    pub contradictions: usize,
+}
+
+/// N×N values, row by row
+type Pattern = Vec<Value>;
+
+fn rotate(pattern: &[Value], n: usize) -> Pattern {
+    (0..n * n)
+        .map(|index| pattern[(n - 1 - index % n) * n + index / n])
+        .collect()
+}
+
+fn reflect(pattern: &[Value], n: usize) -> Pattern {
+    (0..n * n)
+        .map(|index| pattern[index / n * n + n - 1 - index % n])
+        .collect()
+}
+
+/// The distinct patterns in the sample, in the order they're first seen, and how often each occurs
+fn patterns(sample: &Grid<Value>, n: usize, symmetry: bool) -> (Vec<Pattern>, Vec<f64>) {
+    let mut patterns = Vec::new();
+    let mut weights = Vec::new();
+    let mut indices = HashMap::new();
+
+    for top in 0..=sample.rows() - n {
+        for left in 0..=sample.cols() - n {
+            let pattern: Pattern = (0..n * n)
+                .map(|index| {
+                    sample
+                        .get(top + index / n, left + index % n)
+                        .copied()
+                        .flatten()
+                })
+                .collect();
+
+            let mut variants = vec![pattern];
+            if symmetry {
+                for index in 0..3 {
+                    variants.push(rotate(&variants[index], n));
+                }
+                for index in 0..4 {
+                    variants.push(reflect(&variants[index], n));
+                }
+            }
+
+            for variant in variants {
+                let index = *indices.entry(variant.clone()).or_insert_with(|| {
+                    patterns.push(variant);
+                    weights.push(0.0);
+                    patterns.len() - 1
+                });
+                weights[index] += 1.0;
+            }
+        }
+    }
+
+    (patterns, weights)
+}
+
+/// Whether `b` can sit `dr` rows down and `dc` columns across from `a`, with the two agreeing where they overlap
+fn agrees(a: &[Value], b: &[Value], n: usize, dr: isize, dc: isize) -> bool {
+    let n = n as isize;
+    (dr.max(0)..(n + dr).min(n)).all(|row| {
+        (dc.max(0)..(n + dc).min(n))
+            .all(|col| a[(row * n + col) as usize] == b[((row - dr) * n + col - dc) as usize])
+    })
+}
+
+/// An undecided position and its entropy when it was queued. Ordered so the lowest entropy comes out of a
+/// `BinaryHeap` first.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Candidate {
+    entropy: f64,
+    position: usize,
+}
+
+impl Eq for Candidate {}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .entropy
+            .total_cmp(&self.entropy)
+            .then(other.position.cmp(&self.position))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The patterns still possible at each position, as a bit set per position, with what's needed to pick the next
+/// position and to back out of choices cheaply
+struct Wave {
+    words: usize,
+    bits: Vec<u64>,
+    /// For each position, the total weight of its patterns and the total of weight × ln(weight), kept up to date as
+    /// patterns are removed so entropies never need recounting
+    sums: Vec<f64>,
+    sum_logs: Vec<f64>,
+    /// A little noise per position breaks ties without always favouring the top left
+    noise: Vec<f64>,
+    /// Undecided positions by entropy. Entries go stale as positions change, and are skipped when they come out.
+    candidates: BinaryHeap<Candidate>,
+    /// Every word changed, as (position, word, bits before), so choices can be undone by replaying it backwards
+    trail: Vec<(usize, usize, u64)>,
+}
+
+impl Wave {
+    fn new(positions: usize, weights: &[f64], rng: &mut Rng) -> Self {
+        let patterns = weights.len();
+        let words = patterns.div_ceil(64);
+        let mut all = vec![0; words];
+        for pattern in 0..patterns {
+            all[pattern / 64] |= 1 << (pattern % 64);
+        }
+        let sum: f64 = weights.iter().sum();
+        let sum_log: f64 = weights.iter().map(|weight| weight * weight.ln()).sum();
+
+        let mut wave = Self {
+            words,
+            bits: all.repeat(positions),
+            sums: vec![sum; positions],
+            sum_logs: vec![sum_log; positions],
+            noise: (0..positions).map(|_| rng.fraction() * 1e-6).collect(),
+            candidates: BinaryHeap::new(),
+            trail: Vec::new(),
+        };
+        for position in 0..positions {
+            wave.queue(position);
+        }
+        wave
+    }
+
+    fn get(&self, position: usize) -> &[u64] {
+        &self.bits[position * self.words..(position + 1) * self.words]
+    }
+
+    fn patterns(&self, position: usize) -> impl Iterator<Item = usize> + '_ {
+        patterns_in(self.get(position))
+    }
+
+    fn count(&self, position: usize) -> u32 {
+        self.get(position)
+            .iter()
+            .map(|bits| bits.count_ones())
+            .sum()
+    }
+
+    fn entropy(&self, position: usize) -> f64 {
+        let sum = self.sums[position];
+        sum.ln() - self.sum_logs[position] / sum + self.noise[position]
+    }
+
+    fn queue(&mut self, position: usize) {
+        if self.count(position) > 1 {
+            self.candidates.push(Candidate {
+                entropy: self.entropy(position),
+                position,
+            });
+        }
+    }
+
+    /// Keep only the patterns in `keep` among one word of a position's patterns. Returns whether any were removed.
+    fn retain(&mut self, position: usize, word: usize, keep: u64, weights: &[f64]) -> bool {
+        let index = position * self.words + word;
+        let old = self.bits[index];
+        let removed = old & !keep;
+        if removed == 0 {
+            return false;
+        }
+
+        self.trail.push((position, word, old));
+        self.bits[index] = old & keep;
+        for pattern in patterns_in(&[removed]) {
+            let weight = weights[word * 64 + pattern];
+            self.sums[position] -= weight;
+            self.sum_logs[position] -= weight * weight.ln();
+        }
+        self.queue(position);
+        true
+    }
+
+    /// Put back every change made since the trail was `mark` long
+    fn undo(&mut self, mark: usize, weights: &[f64]) {
+        while self.trail.len() > mark {
+            let (position, word, old) = match self.trail.pop() {
+                Some(change) => change,
+                None => break,
+            };
+            let index = position * self.words + word;
+            for pattern in patterns_in(&[old & !self.bits[index]]) {
+                let weight = weights[word * 64 + pattern];
+                self.sums[position] += weight;
+                self.sum_logs[position] += weight * weight.ln();
+            }
+            self.bits[index] = old;
+            self.queue(position);
+        }
+    }
+
+    /// The undecided position with the lowest entropy
+    fn lowest_entropy(&mut self) -> Option<usize> {
+        while let Some(candidate) = self.candidates.pop() {
+            let position = candidate.position;
+            if self.count(position) > 1 && self.entropy(position) == candidate.entropy {
+                return Some(position);
+            }
+        }
+        None
+    }
+}
+
+/// The patterns in a bit set
+fn patterns_in(bits: &[u64]) -> impl Iterator<Item = usize> + '_ {
+    bits.iter().enumerate().flat_map(|(word, &bits)| {
+        // Clearing the lowest set bit each time visits only the bits that are set
+        let set = |bits: u64| Some(bits).filter(|bits| *bits != 0);
+        std::iter::successors(set(bits), move |bits| set(bits & (bits - 1)))
+            .map(move |bits| word * 64 + bits.trailing_zeros() as usize)
+    })
+}
+
+struct Solver {
+    n: usize,
+    /// How many pattern positions there are down and across, each covering the N×N cells below and right of it
+    rows: usize,
+    cols: usize,
+    patterns: Vec<Pattern>,
+    weights: Vec<f64>,
+    /// For each pattern and direction, the bit set of patterns that can sit next to it that way
+    compatible: Vec<[Vec<u64>; 4]>,
+}
+
+impl Solver {
+    fn neighbour(&self, position: usize, (dr, dc): (isize, isize)) -> Option<usize> {
+        let row = (position / self.cols) as isize + dr;
+        let col = (position % self.cols) as isize + dc;
+        if row < 0 || col < 0 || row as usize >= self.rows || col as usize >= self.cols {
+            None
+        } else {
+            Some(row as usize * self.cols + col as usize)
+        }
+    }
+
+    /// Remove the patterns that no longer fit next to the positions in `changed`, and so on outwards. Fails with the
+    /// position that was left with no patterns at all.
+    fn propagate(&self, wave: &mut Wave, mut changed: Vec<usize>) -> Result<(), usize> {
+        while let Some(position) = changed.pop() {
+            for (direction, offset) in DIRECTIONS.iter().enumerate() {
+                let neighbour = match self.neighbour(position, *offset) {
+                    Some(neighbour) => neighbour,
+                    None => continue,
+                };
+
+                let mut possible = vec![0; wave.words];
+                for pattern in wave.patterns(position) {
+                    for (possible, bits) in possible
+                        .iter_mut()
+                        .zip(&self.compatible[pattern][direction])
+                    {
+                        *possible |= bits;
+                    }
+                }
+
+                let mut shrunk = false;
+                for (word, possible) in possible.into_iter().enumerate() {
+                    shrunk |= wave.retain(neighbour, word, possible, &self.weights);
+                }
+
+                if shrunk {
+                    if wave.count(neighbour) == 0 {
+                        return Err(neighbour);
+                    }
+                    changed.push(neighbour);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Pick the undecided position with the fewest options, weighted by how common they are, and a pattern for it
+    fn observe(&self, wave: &mut Wave, rng: &mut Rng) -> Option<(usize, usize)> {
+        let position = wave.lowest_entropy()?;
+        let total: f64 = wave.patterns(position).map(|p| self.weights[p]).sum();
+        let mut target = rng.fraction() * total;
+        let mut chosen = None;
+        for pattern in wave.patterns(position) {
+            chosen = Some(pattern);
+            target -= self.weights[pattern];
+            if target < 0.0 {
+                break;
+            }
+        }
+
+        Some((position, chosen?))
+    }
+
+    /// Keep only `pattern` at `position`
+    fn choose(&self, wave: &mut Wave, position: usize, pattern: usize) {
+        for word in 0..wave.words {
+            let keep = if word == pattern / 64 {
+                1 << (pattern % 64)
+            } else {
+                0
+            };
+            wave.retain(position, word, keep, &self.weights);
+        }
+    }
+}
+
+/// Generate a map following the patterns of `sample`. The same sample and settings always give the same map.
+pub fn generate(sample: &Grid<Value>, settings: &WfcSettings) -> Result<Generated, WfcError> {
+    let n = settings.pattern_size.max(1);
+    if sample.rows() < n || sample.cols() < n {
+        return Err(WfcError::SampleTooSmall);
+    }
+    if settings.rows < n || settings.cols < n {
+        return Err(WfcError::OutputTooSmall);
+    }
+
+    let (patterns, weights) = patterns(sample, n, settings.symmetry);
+    let words = patterns.len().div_ceil(64);
+    let compatible = patterns
+        .iter()
+        .map(|a| {
+            let mut compatible = [
+                vec![0; words],
+                vec![0; words],
+                vec![0; words],
+                vec![0; words],
+            ];
+            for (direction, (dr, dc)) in DIRECTIONS.iter().enumerate() {
+                for (index, b) in patterns.iter().enumerate() {
+                    if agrees(a, b, n, *dr, *dc) {
+                        compatible[direction][index / 64] |= 1 << (index % 64);
+                    }
+                }
+            }
+            compatible
+        })
+        .collect();
+
+    let solver = Solver {
+        n,
+        rows: settings.rows - n + 1,
+        cols: settings.cols - n + 1,
+        patterns,
+        weights,
+        compatible,
+    };
+    let positions = solver.rows * solver.cols;
+
+    let mut rng = Rng::new(settings.seed);
+    let mut wave = Wave::new(positions, &solver.weights, &mut rng);
+    let mut contradictions = 0;
+
+    // Patterns from the sample's edges may have nothing that can follow them, so they can only go on the map's edges
+    solver
+        .propagate(&mut wave, (0..positions).collect())
+        .map_err(|_| WfcError::Contradiction { contradictions })?;
+    wave.trail.clear();
+
+    // Each choice made, with how long the trail was just before, to back out of it if it leads to a contradiction
+    let mut choices: Vec<(usize, usize, usize)> = Vec::new();
+
+    while let Some((position, pattern)) = solver.observe(&mut wave, &mut rng) {
+        choices.push((wave.trail.len(), position, pattern));
+        solver.choose(&mut wave, position, pattern);
+        let mut result = solver.propagate(&mut wave, vec![position]);
+
+        while result.is_err() {
+            contradictions += 1;
+            let (mark, position, pattern) = match choices.pop() {
+                Some(choice) if contradictions <= settings.max_contradictions => choice,
+                _ => return Err(WfcError::Contradiction { contradictions }),
+            };
+
+            // Go back to before the choice and rule it out
+            wave.undo(mark, &solver.weights);
+            wave.retain(
+                position,
+                pattern / 64,
+                !(1 << (pattern % 64)),
+                &solver.weights,
+            );
+            result = if wave.count(position) == 0 {
+                Err(position)
+            } else {
+                solver.propagate(&mut wave, vec![position])
+            };
+        }
+    }
+
+    let cells =
+        Grid::with_dimensions(settings.rows, settings.cols).map_indexed(|row, col, _: &Value| {
+            let (top, left) = (row.min(solver.rows - 1), col.min(solver.cols - 1));
+            let pattern = wave.patterns(top * solver.cols + left).next();
+            pattern
+                .and_then(|pattern| solver.patterns[pattern][(row - top) * solver.n + col - left])
+        });
+
+    Ok(Generated {
+        cells,
+        contradictions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A grid from rows of characters, with `.` for an empty cell and a digit for a value
+    fn sample(rows: &[&str]) -> Grid<Value> {
+        Grid::with_dimensions(rows.len(), rows[0].len()).map_indexed(|row, col, _: &Value| {
+            rows[row].as_bytes()[col]
+                .checked_sub(b'0')
+                .filter(|digit| *digit < 10)
+                .map(usize::from)
+        })
+    }
+
+    fn windows(grid: &Grid<Value>, n: usize) -> Vec<Pattern> {
+        patterns(grid, n, false).0
+    }
+
+    const ROOMS: &[&str] = &[
+        "........", ".111.22.", ".111.22.", ".111....", "....333.", ".44.333.", ".44.....",
+    ];
+
+    #[test]
+    fn same_seed_gives_same_map() {
+        let settings = WfcSettings {
+            rows: 16,
+            cols: 16,
+            ..WfcSettings::default()
+        };
+        let a = generate(&sample(ROOMS), &settings).unwrap();
+        let b = generate(&sample(ROOMS), &settings).unwrap();
+
+        assert_eq!(
+            a.cells.iter().map(|(_, _, cell)| *cell).collect::<Vec<_>>(),
+            b.cells.iter().map(|(_, _, cell)| *cell).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn every_window_comes_from_the_sample() {
+        for seed in 0..5 {
+            for n in 2..=3 {
+                let settings = WfcSettings {
+                    seed,
+                    rows: 12,
+                    cols: 20,
+                    pattern_size: n,
+                    ..WfcSettings::default()
+                };
+                let generated = generate(&sample(ROOMS), &settings).unwrap();
+                let known = windows(&sample(ROOMS), n);
+
+                assert_eq!(generated.cells.rows(), 12);
+                assert_eq!(generated.cells.cols(), 20);
+                for window in windows(&generated.cells, n) {
+                    assert!(
+                        known.contains(&window),
+                        "seed {} n {}: {:?}",
+                        seed,
+                        n,
+                        window
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn large_maps_are_generated() {
+        let settings = WfcSettings {
+            rows: 64,
+            cols: 64,
+            symmetry: true,
+            ..WfcSettings::default()
+        };
+        let generated = generate(&sample(ROOMS), &settings).unwrap();
+        let known = patterns(&sample(ROOMS), 3, true).0;
+
+        for window in windows(&generated.cells, 3) {
+            assert!(known.contains(&window));
+        }
+    }
+
+    #[test]
+    fn symmetry_adds_rotated_patterns() {
+        let stripes = sample(&["1.", "1.", "1."]);
+        assert_eq!(patterns(&stripes, 2, false).0.len(), 1);
+        assert_eq!(patterns(&stripes, 2, true).0.len(), 4);
+    }
+
+    #[test]
+    fn impossible_maps_report_a_contradiction() {
+        // The only pattern can't overlap itself, so nothing bigger than the sample can be made
+        let settings = WfcSettings {
+            rows: 4,
+            cols: 4,
+            ..WfcSettings::default()
+        };
+
+        assert!(matches!(
+            generate(&sample(&["123", "456", "789"]), &settings),
+            Err(WfcError::Contradiction { .. })
+        ));
+        assert_eq!(
+            generate(&sample(&["12", "34"]), &settings).unwrap_err(),
+            WfcError::SampleTooSmall
+        );
+    }
+}